@@ -0,0 +1,10 @@
+CREATE TABLE job_definitions (
+    name TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    interval_secs BIGINT NOT NULL CHECK (interval_secs > 0),
+    params JSONB NOT NULL DEFAULT '{}'
+);
+
+INSERT INTO job_definitions (name, kind, interval_secs, params)
+VALUES ('movies', 'movies', 3600, '{}');
@@ -0,0 +1,2 @@
+-- Jobs with a tenant store their data in the Postgres schema named after the tenant
+ALTER TABLE job_definitions ADD COLUMN tenant TEXT;
@@ -0,0 +1,13 @@
+CREATE TABLE fetch_tasks (
+    id BIGSERIAL PRIMARY KEY,
+    queue TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    visible_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    finished_at TIMESTAMPTZ,
+    last_error TEXT
+);
+
+CREATE INDEX fetch_tasks_pending_index
+ON fetch_tasks(queue, id) WHERE finished_at IS NULL;
@@ -0,0 +1,15 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Generated columns are maintained by Postgres on every insert/upsert
+ALTER TABLE shows ADD COLUMN search_vector tsvector
+GENERATED ALWAYS AS (to_tsvector('simple', title)) STORED;
+
+ALTER TABLE ratings ADD COLUMN search_vector tsvector
+GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', title), 'A') ||
+    setweight(to_tsvector('english', coalesce("description", '')), 'B')
+) STORED;
+
+CREATE INDEX shows_search_index ON shows USING GIN (search_vector);
+CREATE INDEX ratings_search_index ON ratings USING GIN (search_vector);
+CREATE INDEX shows_title_trgm_index ON shows USING GIN (title gin_trgm_ops);
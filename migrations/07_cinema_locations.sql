@@ -0,0 +1,10 @@
+CREATE EXTENSION IF NOT EXISTS cube;
+CREATE EXTENSION IF NOT EXISTS earthdistance;
+
+ALTER TABLE cinemas
+ADD COLUMN latitude FLOAT,
+ADD COLUMN longitude FLOAT;
+
+CREATE INDEX cinemas_location_index
+ON cinemas USING GIST (ll_to_earth(latitude, longitude))
+WHERE latitude IS NOT NULL AND longitude IS NOT NULL;
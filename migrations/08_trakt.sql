@@ -0,0 +1,17 @@
+CREATE TABLE watchlist (
+    source TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    year INTEGER,
+    imdb_id TEXT,
+    tmdb_id INTEGER,
+    listed_at TEXT,
+    PRIMARY KEY(source, external_id)
+);
+
+CREATE TABLE oauth_tokens (
+    provider TEXT PRIMARY KEY,
+    access_token TEXT NOT NULL,
+    refresh_token TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,13 @@
+CREATE TABLE planned_screenings (
+    id BIGSERIAL PRIMARY KEY,
+    show_slug TEXT NOT NULL REFERENCES shows (slug),
+    cinema_slug TEXT NOT NULL REFERENCES cinemas (slug),
+    time TEXT NOT NULL,
+    auditorium_name TEXT NOT NULL,
+    cancelled BOOLEAN NOT NULL DEFAULT FALSE,
+    calendar_event_id TEXT,
+    -- Hash of the event contents as last written to the calendar
+    calendar_hash TEXT,
+    planned_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    UNIQUE(show_slug, cinema_slug, time, auditorium_name)
+);
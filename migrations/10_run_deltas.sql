@@ -0,0 +1,12 @@
+CREATE TABLE run_deltas (
+    jobname TEXT NOT NULL,
+    run_dt TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    table_name TEXT NOT NULL,
+    submitted BIGINT NOT NULL,
+    inserted BIGINT NOT NULL,
+    updated BIGINT NOT NULL,
+    unchanged BIGINT NOT NULL
+);
+
+CREATE INDEX run_deltas_dt_index
+ON run_deltas(jobname, table_name, run_dt DESC);
@@ -0,0 +1,7 @@
+CREATE TABLE run_anomalies (
+    jobname TEXT NOT NULL,
+    run_dt TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    table_name TEXT NOT NULL,
+    submitted BIGINT NOT NULL,
+    baseline FLOAT NOT NULL
+);
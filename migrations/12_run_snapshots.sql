@@ -0,0 +1,26 @@
+ALTER TABLE joblogs ADD COLUMN id BIGSERIAL PRIMARY KEY;
+
+CREATE TABLE snapshot_shows (
+    run_id BIGINT NOT NULL REFERENCES joblogs (id) ON DELETE CASCADE,
+    slug TEXT NOT NULL,
+    title TEXT NOT NULL,
+    PRIMARY KEY(run_id, slug)
+);
+
+CREATE TABLE snapshot_showtimes (
+    run_id BIGINT NOT NULL REFERENCES joblogs (id) ON DELETE CASCADE,
+    show_slug TEXT NOT NULL,
+    cinema_slug TEXT NOT NULL,
+    time TEXT NOT NULL,
+    auditorium_name TEXT NOT NULL,
+    PRIMARY KEY(run_id, show_slug, cinema_slug, time, auditorium_name)
+);
+
+CREATE TABLE snapshot_ratings (
+    run_id BIGINT NOT NULL REFERENCES joblogs (id) ON DELETE CASCADE,
+    show_slug TEXT NOT NULL,
+    rating_slug TEXT NOT NULL,
+    critics_score INTEGER,
+    audience_score INTEGER,
+    PRIMARY KEY(run_id, show_slug)
+);
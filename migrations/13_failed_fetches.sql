@@ -0,0 +1,10 @@
+CREATE TABLE failed_fetches (
+    jobname TEXT NOT NULL,
+    url TEXT NOT NULL,
+    context JSONB NOT NULL,
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    first_failed_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    last_failed_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (jobname, url)
+);
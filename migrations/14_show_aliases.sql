@@ -0,0 +1,10 @@
+CREATE TABLE show_aliases (
+    alias_slug TEXT PRIMARY KEY REFERENCES shows (slug),
+    canonical_slug TEXT NOT NULL REFERENCES shows (slug)
+);
+
+-- Maps every show to the show it is considered to be the same film as (possibly itself)
+CREATE VIEW canonical_shows AS
+SELECT s.slug, COALESCE(a.canonical_slug, s.slug) AS canonical_slug
+FROM shows s
+LEFT JOIN show_aliases a ON a.alias_slug = s.slug;
@@ -0,0 +1,3 @@
+ALTER TABLE shows
+    ADD COLUMN original_title TEXT,
+    ADD COLUMN title_language TEXT;
@@ -0,0 +1,8 @@
+CREATE TABLE endpoint_hashes (
+    endpoint TEXT PRIMARY KEY,
+    hash TEXT NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
+
+INSERT INTO job_definitions (name, kind, interval_secs)
+VALUES ('shows_watch', 'showswatch', 300);
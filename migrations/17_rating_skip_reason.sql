@@ -0,0 +1 @@
+ALTER TABLE shows ADD COLUMN rating_skip_reason TEXT;
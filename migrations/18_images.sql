@@ -0,0 +1,27 @@
+CREATE TABLE images (
+    show_slug TEXT NOT NULL REFERENCES shows (slug),
+    image_type TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    variant TEXT NOT NULL,
+    url TEXT NOT NULL,
+    width INTEGER,
+    height INTEGER,
+    PRIMARY KEY (show_slug, image_type, position, variant)
+);
+
+INSERT INTO images (show_slug, image_type, position, variant, url)
+SELECT p.show_slug, 'poster', 0, v.variant, v.url
+FROM posters p, LATERAL (VALUES ('lg', p.lg), ('md', p.md)) AS v (variant, url)
+WHERE v.url IS NOT NULL;
+
+DROP TABLE posters;
+
+-- The posters as they used to be stored, for existing consumers
+CREATE VIEW posters AS
+SELECT
+    show_slug,
+    max(url) FILTER (WHERE variant = 'lg') AS lg,
+    max(url) FILTER (WHERE variant = 'md') AS md
+FROM images
+WHERE image_type = 'poster' AND position = 0
+GROUP BY show_slug;
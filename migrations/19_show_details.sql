@@ -0,0 +1,9 @@
+ALTER TABLE shows
+    ADD COLUMN synopsis TEXT,
+    ADD COLUMN age_rating TEXT;
+
+CREATE TABLE content_warnings (
+    show_slug TEXT NOT NULL REFERENCES shows (slug),
+    warning TEXT NOT NULL,
+    PRIMARY KEY (show_slug, warning)
+);
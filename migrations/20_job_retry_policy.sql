@@ -0,0 +1,2 @@
+-- NULL uses the default retry policy
+ALTER TABLE job_definitions ADD COLUMN retry_policy JSONB;
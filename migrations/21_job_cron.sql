@@ -0,0 +1,5 @@
+-- Jobs either run at an interval or at the times matching a cron expression
+ALTER TABLE job_definitions
+    ADD COLUMN cron TEXT,
+    ALTER COLUMN interval_secs DROP NOT NULL,
+    ADD CONSTRAINT job_definitions_schedule CHECK ((interval_secs IS NULL) <> (cron IS NULL));
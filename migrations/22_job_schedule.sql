@@ -0,0 +1,5 @@
+-- Moment each scheduled job (by name) last ran, such that schedules survive restarts
+CREATE TABLE job_schedule (
+    name TEXT PRIMARY KEY,
+    last_ran TIMESTAMPTZ NOT NULL
+);
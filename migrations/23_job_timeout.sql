@@ -0,0 +1,2 @@
+-- Runs taking longer than this are cancelled and recorded as failed, NULL never times out
+ALTER TABLE job_definitions ADD COLUMN timeout_secs BIGINT CHECK (timeout_secs > 0);
@@ -0,0 +1,12 @@
+-- Pathé lists local times without an offset, existing rows are interpreted as such
+SET LOCAL TIME ZONE 'Europe/Amsterdam';
+
+ALTER TABLE showtimes
+    ALTER COLUMN time TYPE TIMESTAMPTZ USING time::timestamptz,
+    ALTER COLUMN end_time TYPE TIMESTAMPTZ USING NULLIF(end_time, '')::timestamptz;
+
+ALTER TABLE snapshot_showtimes
+    ALTER COLUMN time TYPE TIMESTAMPTZ USING time::timestamptz;
+
+ALTER TABLE planned_screenings
+    ALTER COLUMN time TYPE TIMESTAMPTZ USING time::timestamptz;
@@ -0,0 +1,6 @@
+-- Capacities which are not a positive number (empty strings, "N/A") are unknown
+ALTER TABLE showtimes
+    ALTER COLUMN auditorium_capacity TYPE INTEGER USING CASE
+        WHEN trim(auditorium_capacity) ~ '^[0-9]{1,9}$' AND trim(auditorium_capacity)::integer > 0
+            THEN trim(auditorium_capacity)::integer
+    END;
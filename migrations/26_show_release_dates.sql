@@ -0,0 +1,7 @@
+-- Shows can be released on multiple dates (e.g. premieres and the regular release),
+-- shows.release_at holds the first of them
+CREATE TABLE show_release_dates (
+    show_slug TEXT NOT NULL REFERENCES shows (slug),
+    release_date DATE NOT NULL,
+    PRIMARY KEY (show_slug, release_date)
+);
@@ -0,0 +1,9 @@
+-- Shows for which no rating hit scored well enough, with the closest hit for review
+CREATE TABLE unmatched_ratings (
+    show_slug TEXT PRIMARY KEY REFERENCES shows (slug),
+    title TEXT NOT NULL,
+    year INTEGER,
+    best_title TEXT,
+    best_score DOUBLE PRECISION,
+    checked_at TIMESTAMPTZ NOT NULL
+);
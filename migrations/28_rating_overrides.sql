@@ -0,0 +1,8 @@
+-- Ratings of shows fixed by hand, consulted before matching. The rating_slug is
+-- either the slug of the rating to link, or 'no_match' to never link any rating.
+CREATE TABLE rating_overrides (
+    show_slug TEXT PRIMARY KEY,
+    rating_slug TEXT NOT NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
@@ -0,0 +1,32 @@
+CREATE TABLE tmdb_ratings (
+    tmdb_id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    original_title TEXT,
+    release_year INTEGER,
+    vote_average DOUBLE PRECISION,
+    vote_count INTEGER,
+    popularity DOUBLE PRECISION
+);
+
+-- Links shows to their TMDb entry, like shows.rating_slug does for Rotten Tomatoes
+CREATE TABLE tmdb_rating_shows (
+    show_slug TEXT PRIMARY KEY REFERENCES shows (slug),
+    tmdb_id INTEGER NOT NULL REFERENCES tmdb_ratings (tmdb_id),
+    match_score DOUBLE PRECISION NOT NULL
+);
+
+-- Scores of both sources side by side on a scale of 0 to 100, combined_score being
+-- the average of the available ones
+CREATE VIEW show_scores AS
+SELECT
+    s.slug AS show_slug,
+    r.audience_score AS rt_audience_score,
+    r.critics_score AS rt_critics_score,
+    round(t.vote_average * 10)::INTEGER AS tmdb_score,
+    (
+        SELECT avg(score) FROM (VALUES (r.audience_score::FLOAT), (t.vote_average * 10)) AS v (score)
+    ) AS combined_score
+FROM shows s
+LEFT JOIN ratings r ON r.slug = s.rating_slug
+LEFT JOIN tmdb_rating_shows ts ON ts.show_slug = s.slug
+LEFT JOIN tmdb_ratings t ON t.tmdb_id = ts.tmdb_id;
@@ -0,0 +1,2 @@
+-- Moment the rating was last fetched, such that recent matches can be reused
+ALTER TABLE ratings ADD COLUMN fetched_at TIMESTAMPTZ;
@@ -0,0 +1,33 @@
+CREATE TABLE venues (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    city TEXT,
+    address TEXT,
+    postal_code TEXT,
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION
+);
+
+CREATE TABLE events (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    venue_id TEXT REFERENCES venues (id),
+    -- NULL for events of which only the day is known
+    starts_at TIMESTAMPTZ,
+    local_date DATE,
+    status TEXT,
+    segment TEXT,
+    genre TEXT,
+    price_min DOUBLE PRECISION,
+    price_max DOUBLE PRECISION,
+    currency TEXT
+);
+
+CREATE INDEX events_local_date ON events (local_date);
+
+CREATE TABLE event_ticket_links (
+    event_id TEXT NOT NULL REFERENCES events (id),
+    url TEXT NOT NULL,
+    link_type TEXT NOT NULL,
+    PRIMARY KEY (event_id, url)
+);
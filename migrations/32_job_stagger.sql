@@ -0,0 +1,5 @@
+-- Delay of the runs of a job after their scheduled moment, such that jobs sharing
+-- a schedule do not all start at once. The offset is fixed, the jitter random.
+ALTER TABLE job_definitions
+    ADD COLUMN offset_secs BIGINT CHECK (offset_secs >= 0),
+    ADD COLUMN jitter_secs BIGINT CHECK (jitter_secs > 0);
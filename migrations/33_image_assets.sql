@@ -0,0 +1,24 @@
+-- Local copies of images, by the URL they were downloaded from
+CREATE TABLE image_assets (
+    url TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    sha256 TEXT NOT NULL,
+    downloaded_at TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX image_assets_sha256 ON image_assets (sha256);
+
+-- Where the posters are stored locally, next to their remote URLs
+CREATE OR REPLACE VIEW posters AS
+SELECT
+    i.show_slug,
+    max(i.url) FILTER (WHERE i.variant = 'lg') AS lg,
+    max(i.url) FILTER (WHERE i.variant = 'md') AS md,
+    max(a.path) FILTER (WHERE i.variant = 'lg') AS lg_path,
+    max(a.sha256) FILTER (WHERE i.variant = 'lg') AS lg_sha256,
+    max(a.path) FILTER (WHERE i.variant = 'md') AS md_path,
+    max(a.sha256) FILTER (WHERE i.variant = 'md') AS md_sha256
+FROM images i
+LEFT JOIN image_assets a ON a.url = i.url
+WHERE i.image_type = 'poster' AND i.position = 0
+GROUP BY i.show_slug;
@@ -0,0 +1,92 @@
+-- Genres used to be stored per show as listed by Pathé, in Dutch. Shows are now
+-- linked to canonical genres, to which every listed label is mapped.
+ALTER TABLE genres RENAME TO listed_genres;
+
+-- Slug of a label which has no canonical genre yet
+CREATE FUNCTION slugify(label TEXT) RETURNS TEXT AS $$
+    SELECT trim(BOTH '-' FROM regexp_replace(lower(trim(label)), '[^a-z0-9]+', '-', 'g'))
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE TABLE genres (
+    slug TEXT PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+INSERT INTO genres (slug, name) VALUES
+    ('action', 'Action'),
+    ('adventure', 'Adventure'),
+    ('animation', 'Animation'),
+    ('biography', 'Biography'),
+    ('comedy', 'Comedy'),
+    ('crime', 'Crime'),
+    ('documentary', 'Documentary'),
+    ('drama', 'Drama'),
+    ('family', 'Family'),
+    ('fantasy', 'Fantasy'),
+    ('history', 'History'),
+    ('horror', 'Horror'),
+    ('music', 'Music'),
+    ('musical', 'Musical'),
+    ('mystery', 'Mystery'),
+    ('romance', 'Romance'),
+    ('science-fiction', 'Science Fiction'),
+    ('sport', 'Sport'),
+    ('thriller', 'Thriller'),
+    ('war', 'War'),
+    ('western', 'Western');
+
+-- Canonical genre of every label, by the lowercased label
+CREATE TABLE genre_mappings (
+    label TEXT PRIMARY KEY,
+    genre_slug TEXT NOT NULL REFERENCES genres (slug)
+);
+
+INSERT INTO genre_mappings (label, genre_slug)
+SELECT lower(name), slug FROM genres;
+
+INSERT INTO genre_mappings (label, genre_slug) VALUES
+    ('actie', 'action'),
+    ('avontuur', 'adventure'),
+    ('animatie', 'animation'),
+    ('biografie', 'biography'),
+    ('komedie', 'comedy'),
+    ('misdaad', 'crime'),
+    ('documentaire', 'documentary'),
+    ('familie', 'family'),
+    ('kinderfilm', 'family'),
+    ('geschiedenis', 'history'),
+    ('historisch', 'history'),
+    ('muziek', 'music'),
+    ('mysterie', 'mystery'),
+    ('romantiek', 'romance'),
+    ('romantisch', 'romance'),
+    ('sci-fi', 'science-fiction'),
+    ('sciencefiction', 'science-fiction'),
+    ('oorlog', 'war');
+
+-- Labels without a mapping become a genre of their own, which can be remapped later
+INSERT INTO genres (slug, name)
+SELECT DISTINCT ON (slugify(genre)) slugify(genre), trim(genre)
+FROM listed_genres
+WHERE slugify(genre) <> ''
+    AND NOT EXISTS (SELECT 1 FROM genre_mappings m WHERE m.label = lower(trim(genre)))
+ON CONFLICT (slug) DO NOTHING;
+
+INSERT INTO genre_mappings (label, genre_slug)
+SELECT DISTINCT lower(trim(genre)), slugify(genre)
+FROM listed_genres
+WHERE slugify(genre) <> ''
+ON CONFLICT (label) DO NOTHING;
+
+CREATE TABLE show_genres (
+    show_slug TEXT NOT NULL REFERENCES shows (slug),
+    genre_slug TEXT NOT NULL REFERENCES genres (slug),
+    PRIMARY KEY (show_slug, genre_slug)
+);
+
+INSERT INTO show_genres (show_slug, genre_slug)
+SELECT DISTINCT l.show_slug, m.genre_slug
+FROM listed_genres l
+JOIN genre_mappings m ON m.label = lower(trim(l.genre));
+
+DROP TABLE listed_genres;
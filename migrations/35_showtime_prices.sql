@@ -0,0 +1,5 @@
+-- Ticket prices in euro cents, only listed by Pathé for some showtimes
+ALTER TABLE showtimes
+    ADD COLUMN price_cents INTEGER,
+    ADD COLUMN surcharge_3d_cents INTEGER,
+    ADD COLUMN surcharge_imax_cents INTEGER;
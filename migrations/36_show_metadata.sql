@@ -0,0 +1,16 @@
+ALTER TABLE shows
+    ADD COLUMN directors TEXT;
+
+CREATE TABLE show_cast (
+    show_slug TEXT NOT NULL REFERENCES shows (slug),
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (show_slug, position)
+);
+
+CREATE TABLE show_trailers (
+    show_slug TEXT NOT NULL REFERENCES shows (slug),
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (show_slug, position)
+);
@@ -0,0 +1,13 @@
+-- Language versions and formats of showtimes, such as `ov`, `nl` or `imax`
+CREATE TABLE showtime_tags (
+    show_slug TEXT NOT NULL,
+    cinema_slug TEXT NOT NULL,
+    time TIMESTAMPTZ NOT NULL,
+    auditorium_name TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (show_slug, cinema_slug, time, auditorium_name, tag),
+    FOREIGN KEY (show_slug, cinema_slug, time, auditorium_name)
+        REFERENCES showtimes (show_slug, cinema_slug, time, auditorium_name) ON DELETE CASCADE
+);
+
+CREATE INDEX showtime_tags_tag ON showtime_tags (tag);
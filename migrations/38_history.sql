@@ -0,0 +1,97 @@
+-- Every version of a rating or showtime, valid from when it was stored until it was
+-- changed or removed. The current version is the one without `valid_to`.
+CREATE TABLE ratings_history (
+    slug TEXT NOT NULL,
+    title TEXT NOT NULL,
+    "description" TEXT,
+    release_year INTEGER,
+    audience_score INTEGER,
+    score_sentiment TEXT,
+    want_to_see_count INTEGER,
+    critics_score INTEGER,
+    certified_fresh BOOLEAN,
+    new_adjusted_tm_score INTEGER,
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX ratings_history_current ON ratings_history (slug) WHERE valid_to IS NULL;
+CREATE INDEX ratings_history_slug ON ratings_history (slug, valid_from);
+
+CREATE TABLE showtimes_history (
+    show_slug TEXT NOT NULL,
+    cinema_slug TEXT NOT NULL,
+    time TIMESTAMPTZ NOT NULL,
+    reservation_url TEXT,
+    auditorium_name TEXT NOT NULL,
+    auditorium_capacity INTEGER,
+    end_time TIMESTAMPTZ,
+    price_cents INTEGER,
+    surcharge_3d_cents INTEGER,
+    surcharge_imax_cents INTEGER,
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX showtimes_history_current
+    ON showtimes_history (show_slug, cinema_slug, time, auditorium_name) WHERE valid_to IS NULL;
+CREATE INDEX showtimes_history_time ON showtimes_history (time, valid_from);
+
+-- Closes the current version of the row and stores the new one. The first argument is
+-- the history table, the others are the columns identifying a row. Columns which are
+-- not kept in the history, such as `fetched_at`, do not make a new version.
+CREATE FUNCTION record_version() RETURNS trigger AS $$
+DECLARE
+    history TEXT := TG_ARGV[0];
+    matches TEXT;
+    unchanged BOOLEAN;
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        EXECUTE format(
+            'SELECT jsonb_populate_record(NULL::%1$I, to_jsonb($1))
+                IS NOT DISTINCT FROM jsonb_populate_record(NULL::%1$I, to_jsonb($2))',
+            history
+        ) INTO unchanged USING OLD, NEW;
+        IF unchanged THEN
+            RETURN NULL;
+        END IF;
+    END IF;
+
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        SELECT string_agg(format('%1$I = ($1).%1$I', key), ' AND ')
+        INTO matches
+        FROM unnest(TG_ARGV[1:]) AS key;
+        EXECUTE format('UPDATE %I SET valid_to = now() WHERE valid_to IS NULL AND %s', history, matches)
+        USING OLD;
+    END IF;
+
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        EXECUTE format(
+            'INSERT INTO %1$I
+            SELECT (jsonb_populate_record(NULL::%1$I,
+                to_jsonb($1) || jsonb_build_object(''valid_from'', now()))).*',
+            history
+        ) USING NEW;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+INSERT INTO ratings_history
+SELECT slug, title, "description", release_year, audience_score, score_sentiment,
+    want_to_see_count, critics_score, certified_fresh, new_adjusted_tm_score,
+    coalesce(fetched_at, now()), NULL
+FROM ratings;
+
+INSERT INTO showtimes_history
+SELECT show_slug, cinema_slug, time, reservation_url, auditorium_name, auditorium_capacity,
+    end_time, price_cents, surcharge_3d_cents, surcharge_imax_cents, now(), NULL
+FROM showtimes;
+
+CREATE TRIGGER ratings_history AFTER INSERT OR UPDATE OR DELETE ON ratings
+    FOR EACH ROW EXECUTE FUNCTION record_version('ratings_history', 'slug');
+
+CREATE TRIGGER showtimes_history AFTER INSERT OR UPDATE OR DELETE ON showtimes
+    FOR EACH ROW EXECUTE FUNCTION record_version(
+        'showtimes_history', 'show_slug', 'cinema_slug', 'time', 'auditorium_name'
+    );
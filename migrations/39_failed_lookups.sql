@@ -0,0 +1,3 @@
+-- Shows of which looking up the details and rating failed during the run, which keep
+-- what they had instead of failing the whole run
+ALTER TABLE joblogs ADD COLUMN failed_lookups INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Country of the Pathé site a city or cinema is listed on, such that sites of several
+-- countries can be fetched into the same tables. Existing rows get it on the next run.
+ALTER TABLE cities ADD COLUMN country_code TEXT;
+ALTER TABLE cinemas ADD COLUMN country_code TEXT;
@@ -0,0 +1,24 @@
+CREATE TABLE books (
+    isbn13 TEXT PRIMARY KEY,
+    isbn10 TEXT,
+    title TEXT NOT NULL,
+    author TEXT NOT NULL,
+    publisher TEXT,
+    "description" TEXT,
+    -- Price in dollar cents, NULL when the list does not know it
+    price_cents INTEGER,
+    image_url TEXT
+);
+
+-- Rank of a book on the bestseller list of a week
+CREATE TABLE bestsellers (
+    list_name TEXT NOT NULL,
+    published_date DATE NOT NULL,
+    isbn13 TEXT NOT NULL REFERENCES books (isbn13),
+    rank INTEGER NOT NULL,
+    rank_last_week INTEGER,
+    weeks_on_list INTEGER,
+    PRIMARY KEY (list_name, published_date, isbn13)
+);
+
+CREATE INDEX bestsellers_isbn13 ON bestsellers (isbn13);
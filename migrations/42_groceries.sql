@@ -0,0 +1,33 @@
+CREATE TABLE grocery_categories (
+    store TEXT NOT NULL,
+    name TEXT NOT NULL,
+    -- NULL for the top level categories
+    parent TEXT,
+    PRIMARY KEY (store, name)
+);
+
+CREATE TABLE grocery_products (
+    store TEXT NOT NULL,
+    product_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    brand TEXT,
+    unit_size TEXT,
+    category TEXT,
+    image_url TEXT,
+    PRIMARY KEY (store, product_id)
+);
+
+-- Price of a product as found by every run, all prices of a run share `fetched_at`
+CREATE TABLE grocery_prices (
+    store TEXT NOT NULL,
+    product_id TEXT NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL,
+    price_cents INTEGER NOT NULL,
+    -- Price without the promotion, NULL when the product is not on promotion
+    regular_price_cents INTEGER,
+    promotion TEXT,
+    PRIMARY KEY (store, product_id, fetched_at),
+    FOREIGN KEY (store, product_id) REFERENCES grocery_products (store, product_id)
+);
+
+CREATE INDEX grocery_prices_fetched_at ON grocery_prices (fetched_at);
@@ -0,0 +1,23 @@
+-- Hourly forecasts of the cities of the cinemas, a newer forecast of an hour replaces
+-- the older one
+CREATE TABLE weather_forecasts (
+    city_slug TEXT NOT NULL REFERENCES cities (slug),
+    time TIMESTAMPTZ NOT NULL,
+    -- Degrees Celsius
+    temperature DOUBLE PRECISION NOT NULL,
+    feels_like DOUBLE PRECISION,
+    humidity INTEGER,
+    -- Percentage of the sky covered by clouds
+    cloudiness INTEGER,
+    -- Meters per second
+    wind_speed DOUBLE PRECISION,
+    -- Between 0 and 1
+    precipitation_probability DOUBLE PRECISION,
+    rain_mm DOUBLE PRECISION,
+    condition TEXT,
+    "description" TEXT,
+    fetched_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (city_slug, time)
+);
+
+CREATE INDEX weather_forecasts_time ON weather_forecasts (time);
@@ -0,0 +1,3 @@
+-- Rating tasks used to carry the TMDb API key, which the workers now read from their
+-- own configuration instead
+UPDATE fetch_tasks SET payload = payload - 'tmdb_api_key' WHERE payload ? 'tmdb_api_key';
@@ -0,0 +1,369 @@
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, InputObject, Object, Result, Schema,
+    SimpleObject,
+};
+use axum::{Json, Router, extract::State, routing::post};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder};
+
+/// Listings return at most this many items, whatever `limit` is asked for
+const MAX_PAGE_SIZE: i64 = 500;
+
+pub type ApiSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+#[derive(Debug, FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct Show {
+    pub slug: String,
+    pub title: String,
+    pub original_title: Option<String>,
+    /// First release date, as `YYYY-MM-DD`
+    pub release_at: Option<String>,
+    pub movie_type: String,
+    pub duration: i32,
+    pub synopsis: Option<String>,
+    pub age_rating: Option<String>,
+    #[graphql(skip)]
+    pub rating_slug: Option<String>,
+}
+
+#[derive(Debug, FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct Cinema {
+    pub slug: String,
+    pub name: String,
+    pub city_slug: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(Debug, FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct Showtime {
+    pub show_slug: String,
+    pub cinema_slug: String,
+    pub time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub auditorium_name: String,
+    pub reservation_url: Option<String>,
+    /// Price of a regular ticket in euro cents, when Pathé lists it
+    pub price_cents: Option<i32>,
+    pub surcharge_3d_cents: Option<i32>,
+    pub surcharge_imax_cents: Option<i32>,
+}
+
+#[derive(Debug, FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct Rating {
+    pub slug: String,
+    pub title: String,
+    pub release_year: Option<i32>,
+    pub critics_score: Option<i32>,
+    pub audience_score: Option<i32>,
+    pub certified_fresh: Option<bool>,
+}
+
+/// Filters on shows, all given ones have to match
+#[derive(Debug, Default, InputObject)]
+pub struct ShowFilter {
+    /// Part of the title, case insensitive
+    title: Option<String>,
+    genre: Option<String>,
+    /// Lowest critics score (or audience score when there is none)
+    min_score: Option<i32>,
+    /// Only shows with upcoming showtimes
+    playing: Option<bool>,
+}
+
+/// Filters on showtimes, all given ones have to match
+#[derive(Debug, Default, InputObject)]
+pub struct ShowtimeFilter {
+    /// Now when omitted, such that only upcoming showtimes are listed
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    city: Option<String>,
+    /// Part of the auditorium name, such as `IMAX`
+    auditorium: Option<String>,
+    /// Tag of the showtime, such as `ov` for the original version or `imax`
+    tag: Option<String>,
+}
+
+fn pool<'a>(ctx: &Context<'a>) -> Result<&'a PgPool> {
+    ctx.data::<PgPool>()
+}
+
+fn page_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(100).clamp(0, MAX_PAGE_SIZE)
+}
+
+/// Showtimes matching the filter, next to the conditions already in the query
+async fn showtimes(
+    pool: &PgPool,
+    mut query: QueryBuilder<'_, Postgres>,
+    filter: ShowtimeFilter,
+    limit: Option<i64>,
+) -> Result<Vec<Showtime>> {
+    query
+        .push(" AND st.time >= ")
+        .push_bind(filter.from.unwrap_or_else(Utc::now));
+    if let Some(until) = filter.until {
+        query.push(" AND st.time < ").push_bind(until);
+    }
+    if let Some(city) = filter.city {
+        query
+            .push(" AND EXISTS (SELECT 1 FROM cinemas c WHERE c.slug = st.cinema_slug AND c.city_slug = ")
+            .push_bind(city)
+            .push(")");
+    }
+    if let Some(auditorium) = filter.auditorium {
+        query
+            .push(" AND st.auditorium_name ILIKE '%' || ")
+            .push_bind(auditorium)
+            .push(" || '%'");
+    }
+    if let Some(tag) = filter.tag {
+        query
+            .push(" AND EXISTS (SELECT 1 FROM showtime_tags t WHERE t.show_slug = st.show_slug AND t.cinema_slug = st.cinema_slug AND t.time = st.time AND t.auditorium_name = st.auditorium_name AND t.tag = ")
+            .push_bind(tag.to_lowercase())
+            .push(")");
+    }
+    query
+        .push(" ORDER BY st.time, st.cinema_slug LIMIT ")
+        .push_bind(page_limit(limit));
+    Ok(query.build_query_as().fetch_all(pool).await?)
+}
+
+static SHOWTIME_QUERY: &str = r#"SELECT
+        st.show_slug, st.cinema_slug, st.time, st.end_time, st.auditorium_name,
+        st.reservation_url, st.price_cents, st.surcharge_3d_cents, st.surcharge_imax_cents
+    FROM showtimes st WHERE "#;
+
+static SHOW_QUERY: &str = r#"SELECT
+        s.slug, s.title, s.original_title, s.release_at, s.movie_type, s.duration,
+        s.synopsis, s.age_rating, s.rating_slug
+    FROM shows s"#;
+
+#[ComplexObject]
+impl Show {
+    async fn genres(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar(
+            r#"SELECT g.name FROM show_genres sg
+                JOIN genres g ON g.slug = sg.genre_slug
+                WHERE sg.show_slug = $1
+                ORDER BY g.name"#,
+        )
+        .bind(&self.slug)
+        .fetch_all(pool(ctx)?)
+        .await?)
+    }
+
+    async fn rating(&self, ctx: &Context<'_>) -> Result<Option<Rating>> {
+        let Some(rating_slug) = &self.rating_slug else {
+            return Ok(None);
+        };
+        QueryRoot.rating(ctx, rating_slug.clone()).await
+    }
+
+    async fn showtimes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: ShowtimeFilter,
+        limit: Option<i64>,
+    ) -> Result<Vec<Showtime>> {
+        let mut query = QueryBuilder::new(SHOWTIME_QUERY);
+        query.push("st.show_slug = ").push_bind(self.slug.clone());
+        showtimes(pool(ctx)?, query, filter, limit).await
+    }
+}
+
+#[ComplexObject]
+impl Cinema {
+    async fn showtimes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: ShowtimeFilter,
+        limit: Option<i64>,
+    ) -> Result<Vec<Showtime>> {
+        let mut query = QueryBuilder::new(SHOWTIME_QUERY);
+        query.push("st.cinema_slug = ").push_bind(self.slug.clone());
+        showtimes(pool(ctx)?, query, filter, limit).await
+    }
+}
+
+#[ComplexObject]
+impl Showtime {
+    async fn show(&self, ctx: &Context<'_>) -> Result<Option<Show>> {
+        QueryRoot.show(ctx, self.show_slug.clone()).await
+    }
+
+    async fn cinema(&self, ctx: &Context<'_>) -> Result<Option<Cinema>> {
+        QueryRoot.cinema(ctx, self.cinema_slug.clone()).await
+    }
+
+    /// Language versions and formats, such as `ov`, `nl` or `imax`
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar(
+            r#"SELECT tag FROM showtime_tags
+            WHERE show_slug = $1 AND cinema_slug = $2 AND time = $3 AND auditorium_name = $4
+            ORDER BY tag"#,
+        )
+        .bind(&self.show_slug)
+        .bind(&self.cinema_slug)
+        .bind(self.time)
+        .bind(&self.auditorium_name)
+        .fetch_all(pool(ctx)?)
+        .await?)
+    }
+}
+
+#[ComplexObject]
+impl Rating {
+    /// Shows rated by this rating, e.g. the original and the dubbed version
+    async fn shows(&self, ctx: &Context<'_>) -> Result<Vec<Show>> {
+        Ok(sqlx::query_as(&format!(
+            "{SHOW_QUERY} WHERE s.rating_slug = $1 ORDER BY s.slug"
+        ))
+        .bind(&self.slug)
+        .fetch_all(pool(ctx)?)
+        .await?)
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Shows ordered by title
+    async fn shows(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: ShowFilter,
+        limit: Option<i64>,
+        #[graphql(default)] offset: i64,
+    ) -> Result<Vec<Show>> {
+        let mut query = QueryBuilder::new(SHOW_QUERY);
+        query.push(" LEFT JOIN ratings r ON r.slug = s.rating_slug WHERE TRUE");
+        if let Some(title) = filter.title {
+            query
+                .push(" AND s.title ILIKE '%' || ")
+                .push_bind(title)
+                .push(" || '%'");
+        }
+        if let Some(genre) = filter.genre {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM show_genres g JOIN genre_mappings m ON m.genre_slug = g.genre_slug WHERE g.show_slug = s.slug AND m.label ILIKE ")
+                .push_bind(genre)
+                .push(")");
+        }
+        if let Some(min_score) = filter.min_score {
+            query
+                .push(" AND COALESCE(r.critics_score, r.audience_score) >= ")
+                .push_bind(min_score);
+        }
+        if let Some(playing) = filter.playing {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM showtimes st WHERE st.show_slug = s.slug AND st.time >= current_timestamp) = ")
+                .push_bind(playing);
+        }
+        query
+            .push(" ORDER BY s.title, s.slug LIMIT ")
+            .push_bind(page_limit(limit))
+            .push(" OFFSET ")
+            .push_bind(offset);
+        Ok(query.build_query_as().fetch_all(pool(ctx)?).await?)
+    }
+
+    async fn show(&self, ctx: &Context<'_>, slug: String) -> Result<Option<Show>> {
+        Ok(sqlx::query_as(&format!("{SHOW_QUERY} WHERE s.slug = $1"))
+            .bind(slug)
+            .fetch_optional(pool(ctx)?)
+            .await?)
+    }
+
+    /// Cinemas ordered by name, optionally only the ones in a city
+    async fn cinemas(&self, ctx: &Context<'_>, city: Option<String>) -> Result<Vec<Cinema>> {
+        Ok(sqlx::query_as(
+            r#"SELECT slug, name, city_slug, latitude, longitude FROM cinemas
+            WHERE $1::text IS NULL OR city_slug = $1
+            ORDER BY name"#,
+        )
+        .bind(city)
+        .fetch_all(pool(ctx)?)
+        .await?)
+    }
+
+    async fn cinema(&self, ctx: &Context<'_>, slug: String) -> Result<Option<Cinema>> {
+        Ok(sqlx::query_as(
+            "SELECT slug, name, city_slug, latitude, longitude FROM cinemas WHERE slug = $1",
+        )
+        .bind(slug)
+        .fetch_optional(pool(ctx)?)
+        .await?)
+    }
+
+    /// All genres any show is listed under
+    async fn genres(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar(
+            r#"SELECT DISTINCT g.name FROM show_genres sg
+                JOIN genres g ON g.slug = sg.genre_slug
+                ORDER BY g.name"#,
+        )
+        .fetch_all(pool(ctx)?)
+        .await?)
+    }
+
+    /// Ratings, best critics score first
+    async fn ratings(
+        &self,
+        ctx: &Context<'_>,
+        min_critics_score: Option<i32>,
+        limit: Option<i64>,
+        #[graphql(default)] offset: i64,
+    ) -> Result<Vec<Rating>> {
+        Ok(sqlx::query_as(
+            r#"SELECT slug, title, release_year, critics_score, audience_score, certified_fresh
+            FROM ratings
+            WHERE $1::integer IS NULL OR critics_score >= $1
+            ORDER BY critics_score DESC NULLS LAST, audience_score DESC NULLS LAST, slug
+            LIMIT $2 OFFSET $3"#,
+        )
+        .bind(min_critics_score)
+        .bind(page_limit(limit))
+        .bind(offset)
+        .fetch_all(pool(ctx)?)
+        .await?)
+    }
+
+    async fn rating(&self, ctx: &Context<'_>, slug: String) -> Result<Option<Rating>> {
+        Ok(sqlx::query_as(
+            r#"SELECT slug, title, release_year, critics_score, audience_score, certified_fresh
+            FROM ratings WHERE slug = $1"#,
+        )
+        .bind(slug)
+        .fetch_optional(pool(ctx)?)
+        .await?)
+    }
+}
+
+/// Schema over the scraped data. Nested fields are resolved with a query each, so
+/// the depth of queries is limited.
+pub fn schema(pool: PgPool) -> ApiSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(pool)
+        .limit_depth(8)
+        .finish()
+}
+
+async fn graphql(
+    State(schema): State<ApiSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+/// Serves the schema on `POST /graphql`
+pub fn router(pool: PgPool) -> Router {
+    Router::new()
+        .route("/graphql", post(graphql))
+        .with_state(schema(pool))
+}
@@ -0,0 +1,197 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use axum::{Json, Router, extract::State, http::StatusCode, routing::get};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+
+use crate::job::{JobStatuses, util::endpoint_fallbacks};
+
+/// By default a job which failed this many times in a row is considered
+/// permanently failing
+pub const PERMANENT_FAILURE_THRESHOLD: u32 = 5;
+
+#[derive(Clone)]
+struct HealthState {
+    pool: PgPool,
+    statuses: JobStatuses,
+    max_failures: u32,
+}
+
+#[derive(Serialize)]
+struct Health {
+    healthy: bool,
+    database: String,
+    failing_jobs: Vec<FailingJob>,
+}
+
+#[derive(Serialize)]
+struct FailingJob {
+    name: String,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+}
+
+/// Healthy when the database is reachable and no job failed its last runs, meant
+/// for watchdogs which restart the scraper when it is stuck
+async fn healthz(State(state): State<HealthState>) -> (StatusCode, Json<Health>) {
+    let database = match sqlx::query("SELECT 1").execute(&state.pool).await {
+        Ok(_) => "ok".to_string(),
+        Err(err) => err.to_string(),
+    };
+    let failing_jobs: Vec<FailingJob> = {
+        let statuses = state.statuses.read().unwrap_or_else(|e| e.into_inner());
+        statuses
+            .iter()
+            .filter(|(_, status)| status.consecutive_failures >= state.max_failures)
+            .map(|(name, status)| FailingJob {
+                name: name.clone(),
+                consecutive_failures: status.consecutive_failures,
+                last_error: status.last_error.clone(),
+            })
+            .collect()
+    };
+    let healthy = database == "ok" && failing_jobs.is_empty();
+    let code = match healthy {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (
+        code,
+        Json(Health {
+            healthy,
+            database,
+            failing_jobs,
+        }),
+    )
+}
+
+/// The process is alive as long as the runtime is able to handle this request
+async fn livez() -> &'static str {
+    "ok"
+}
+
+/// Ready when the database is reachable, all migrations are applied and no job is
+/// permanently failing
+async fn readyz(State(state): State<HealthState>) -> (StatusCode, String) {
+    match not_ready_reason(&state).await {
+        None => (StatusCode::OK, "ok".to_string()),
+        Some(reason) => (StatusCode::SERVICE_UNAVAILABLE, reason),
+    }
+}
+
+async fn not_ready_reason(state: &HealthState) -> Option<String> {
+    let applied: i64 =
+        match sqlx::query_scalar("SELECT count(*) FROM _sqlx_migrations WHERE success")
+            .fetch_one(&state.pool)
+            .await
+        {
+            Ok(applied) => applied,
+            Err(err) => return Some(format!("database unreachable: {err}")),
+        };
+    let expected = sqlx::migrate!().iter().count() as i64;
+    if applied < expected {
+        return Some(format!("{applied} of {expected} migrations applied"));
+    }
+
+    let statuses = state.statuses.read().unwrap_or_else(|e| e.into_inner());
+    statuses
+        .iter()
+        .find(|(_, status)| status.consecutive_failures >= state.max_failures)
+        .map(|(name, status)| {
+            format!(
+                "job {name} failed {} times in a row",
+                status.consecutive_failures
+            )
+        })
+}
+
+#[derive(FromRow)]
+struct LatestDelta {
+    jobname: String,
+    table_name: String,
+    inserted: i64,
+    updated: i64,
+    unchanged: i64,
+}
+
+/// Prometheus metrics of the job statuses and the row changes of their latest run
+async fn metrics(State(state): State<HealthState>) -> (StatusCode, String) {
+    let mut out = String::new();
+
+    {
+        let statuses = state.statuses.read().unwrap_or_else(|e| e.into_inner());
+        out.push_str("# TYPE schraper_job_runs_total counter\n");
+        for (name, status) in statuses.iter() {
+            out.push_str(&format!(
+                "schraper_job_runs_total{{job=\"{name}\"}} {}\n",
+                status.runs
+            ));
+        }
+        out.push_str("# TYPE schraper_job_consecutive_failures gauge\n");
+        for (name, status) in statuses.iter() {
+            out.push_str(&format!(
+                "schraper_job_consecutive_failures{{job=\"{name}\"}} {}\n",
+                status.consecutive_failures
+            ));
+        }
+    }
+
+    out.push_str("# TYPE schraper_endpoint_fallbacks_total counter\n");
+    for (endpoint, fallbacks) in endpoint_fallbacks() {
+        out.push_str(&format!(
+            "schraper_endpoint_fallbacks_total{{endpoint=\"{endpoint}\"}} {fallbacks}\n"
+        ));
+    }
+
+    let deltas: Vec<LatestDelta> = match sqlx::query_as(
+        r#"SELECT DISTINCT ON (jobname, table_name) jobname, table_name, inserted, updated, unchanged
+        FROM run_deltas
+        ORDER BY jobname, table_name, run_dt DESC"#,
+    )
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(deltas) => deltas,
+        Err(err) => return (StatusCode::SERVICE_UNAVAILABLE, err.to_string()),
+    };
+    out.push_str("# TYPE schraper_run_rows gauge\n");
+    for delta in deltas {
+        for (change, rows) in [
+            ("inserted", delta.inserted),
+            ("updated", delta.updated),
+            ("unchanged", delta.unchanged),
+        ] {
+            out.push_str(&format!(
+                "schraper_run_rows{{job=\"{}\",table=\"{}\",change=\"{change}\"}} {rows}\n",
+                delta.jobname, delta.table_name
+            ));
+        }
+    }
+    (StatusCode::OK, out)
+}
+
+/// Serves `/livez` and `/readyz` on the given address, for use as liveness and
+/// readiness probes by e.g. Kubernetes, next to a JSON health report on `/healthz`
+/// and Prometheus metrics on `/metrics`. Jobs which failed `max_failures` times in
+/// a row make the process unhealthy.
+pub async fn serve_health(
+    addr: SocketAddr,
+    pool: PgPool,
+    statuses: JobStatuses,
+    max_failures: u32,
+) -> Result<()> {
+    let app = Router::new()
+        .route("/livez", get(livez))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics))
+        .with_state(HealthState {
+            pool,
+            statuses,
+            max_failures,
+        });
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
+}
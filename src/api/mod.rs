@@ -0,0 +1,6 @@
+//! HTTP interface over the scraped data.
+
+pub mod graphql;
+pub mod health;
+pub mod openapi;
+pub mod rest;
@@ -0,0 +1,51 @@
+use utoipa::OpenApi;
+
+use super::rest;
+use crate::query::{
+    catalog::{CinemaEntry, RatingEntry, ScheduleEntry, ShowEntry, ShowShowtime},
+    diff::{RatingChange, RunDiff, ShowRef, ShowtimeRef},
+    nearby::NearbyShowtime,
+    search::SearchHit,
+    tonight::Recommendation,
+};
+
+/// OpenAPI document describing the routes and models of the REST API, such that
+/// frontends can generate typed clients from it. Served at `/openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "schraper",
+        description = "Read API over the scraped cinema data"
+    ),
+    paths(
+        rest::shows,
+        rest::show,
+        rest::show_showtimes,
+        rest::cinemas,
+        rest::cinema_schedule,
+        rest::ratings,
+        rest::tonight,
+        rest::search,
+        rest::showtimes_nearby
+    ),
+    components(schemas(
+        Recommendation,
+        SearchHit,
+        NearbyShowtime,
+        RunDiff,
+        ShowRef,
+        ShowtimeRef,
+        RatingChange,
+        ShowEntry,
+        ShowShowtime,
+        CinemaEntry,
+        ScheduleEntry,
+        RatingEntry
+    ))
+)]
+pub struct ApiDoc;
+
+/// The OpenAPI document as pretty printed JSON
+pub fn openapi_json() -> serde_json::Result<String> {
+    ApiDoc::openapi().to_pretty_json()
+}
@@ -0,0 +1,349 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::error;
+use utoipa::{IntoParams, OpenApi};
+
+use super::{graphql, openapi::ApiDoc};
+use crate::{
+    job::movies::PATHE_TIMEZONE,
+    query::{
+        catalog::{self, CinemaEntry, RatingEntry, ScheduleEntry, ShowEntry, ShowShowtime},
+        nearby::{NearbyShowtime, showtimes_near},
+        search::{SearchHit, search_shows},
+        tonight::{Recommendation, Tonight},
+    },
+};
+
+/// Upper bound of the `limit` of listings
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// Upper bound of the radius around a location in which showtimes are listed
+const MAX_RADIUS_KM: f64 = 250.0;
+
+/// Failure of a request, database errors are logged instead of exposed
+enum ApiError {
+    NotFound(String),
+    BadRequest(String),
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        ApiError::Internal(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::NotFound(what) => (StatusCode::NOT_FOUND, format!("Unknown {what}")),
+            ApiError::BadRequest(reason) => (StatusCode::BAD_REQUEST, reason),
+            ApiError::Internal(err) => {
+                error!("Request failed: {err:#}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal error".to_string(),
+                )
+            }
+        }
+        .into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct Page {
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: i64,
+}
+
+/// Limit of a listing, 100 by default
+fn page_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(100).clamp(0, MAX_PAGE_SIZE)
+}
+
+/// Not built on `Page`, since flattened query parameters are all strings
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ShowFilter {
+    genre: Option<String>,
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TonightFilter {
+    city: Option<String>,
+    min_score: Option<i32>,
+    genre: Option<String>,
+    format: Option<String>,
+    tag: Option<String>,
+    /// Showtimes starting within this many hours, 8 by default
+    hours: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct Location {
+    lat: f64,
+    lon: f64,
+    /// 10 km by default
+    radius_km: Option<f64>,
+    limit: Option<i64>,
+}
+
+impl Location {
+    fn validate(&self) -> Result<(), ApiError> {
+        if !(-90.0..=90.0).contains(&self.lat) {
+            return Err(ApiError::BadRequest(
+                "lat should be between -90 and 90".to_string(),
+            ));
+        }
+        if !(-180.0..=180.0).contains(&self.lon) {
+            return Err(ApiError::BadRequest(
+                "lon should be between -180 and 180".to_string(),
+            ));
+        }
+        if let Some(radius_km) = self.radius_km
+            && !(radius_km > 0.0 && radius_km <= MAX_RADIUS_KM)
+        {
+            return Err(ApiError::BadRequest(format!(
+                "radius_km should be positive and at most {MAX_RADIUS_KM}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchQuery {
+    q: String,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ScheduleDate {
+    /// Today (in the time zone of Pathé) when omitted
+    date: Option<NaiveDate>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/shows",
+    params(ShowFilter),
+    responses((status = 200, body = Vec<ShowEntry>)),
+)]
+async fn shows(
+    State(pool): State<PgPool>,
+    Query(filter): Query<ShowFilter>,
+) -> ApiResult<Vec<ShowEntry>> {
+    let shows = catalog::shows(
+        &pool,
+        filter.genre.as_deref(),
+        page_limit(filter.limit),
+        filter.offset,
+    )
+    .await?;
+    Ok(Json(shows))
+}
+
+#[utoipa::path(
+    get,
+    path = "/shows/{slug}",
+    params(("slug" = String, Path, description = "Slug of the show")),
+    responses(
+        (status = 200, body = ShowEntry),
+        (status = 404, description = "Unknown slug"),
+    ),
+)]
+async fn show(State(pool): State<PgPool>, Path(slug): Path<String>) -> ApiResult<ShowEntry> {
+    match catalog::show(&pool, &slug).await? {
+        Some(show) => Ok(Json(show)),
+        None => Err(ApiError::NotFound(format!("show {slug}"))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/shows/{slug}/showtimes",
+    params(("slug" = String, Path, description = "Slug of the show")),
+    responses(
+        (status = 200, body = Vec<ShowShowtime>),
+        (status = 404, description = "Unknown slug"),
+    ),
+)]
+async fn show_showtimes(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+) -> ApiResult<Vec<ShowShowtime>> {
+    if catalog::show(&pool, &slug).await?.is_none() {
+        return Err(ApiError::NotFound(format!("show {slug}")));
+    }
+    Ok(Json(catalog::show_showtimes(&pool, &slug).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/cinemas",
+    responses((status = 200, body = Vec<CinemaEntry>)),
+)]
+async fn cinemas(State(pool): State<PgPool>) -> ApiResult<Vec<CinemaEntry>> {
+    Ok(Json(catalog::cinemas(&pool).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/cinemas/{slug}/schedule",
+    params(("slug" = String, Path, description = "Slug of the cinema"), ScheduleDate),
+    responses(
+        (status = 200, body = Vec<ScheduleEntry>),
+        (status = 404, description = "Unknown slug"),
+    ),
+)]
+async fn cinema_schedule(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+    Query(ScheduleDate { date }): Query<ScheduleDate>,
+) -> ApiResult<Vec<ScheduleEntry>> {
+    if !catalog::cinema_exists(&pool, &slug).await? {
+        return Err(ApiError::NotFound(format!("cinema {slug}")));
+    }
+    let date = date.unwrap_or_else(|| Utc::now().with_timezone(&PATHE_TIMEZONE).date_naive());
+    Ok(Json(catalog::cinema_schedule(&pool, &slug, date).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/ratings",
+    params(Page),
+    responses((status = 200, body = Vec<RatingEntry>)),
+)]
+async fn ratings(
+    State(pool): State<PgPool>,
+    Query(page): Query<Page>,
+) -> ApiResult<Vec<RatingEntry>> {
+    Ok(Json(
+        catalog::ratings(&pool, page_limit(page.limit), page.offset).await?,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tonight",
+    params(TonightFilter),
+    responses((status = 200, body = Vec<Recommendation>)),
+)]
+async fn tonight(
+    State(pool): State<PgPool>,
+    Query(filter): Query<TonightFilter>,
+) -> ApiResult<Vec<Recommendation>> {
+    let mut tonight = Tonight::new().limit(page_limit(filter.limit));
+    if let Some(hours) = filter.hours {
+        let now = Utc::now();
+        tonight = tonight.between(now, now + Duration::hours(hours.clamp(0, 24 * 14)));
+    }
+    if let Some(city) = filter.city {
+        tonight = tonight.city(city);
+    }
+    if let Some(min_score) = filter.min_score {
+        tonight = tonight.min_score(min_score);
+    }
+    if let Some(genre) = filter.genre {
+        tonight = tonight.genre(genre);
+    }
+    if let Some(format) = filter.format {
+        tonight = tonight.format(format);
+    }
+    if let Some(tag) = filter.tag {
+        tonight = tonight.tag(tag);
+    }
+    Ok(Json(tonight.fetch(&pool).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    params(SearchQuery),
+    responses(
+        (status = 200, body = Vec<SearchHit>),
+        (status = 400, description = "Invalid query parameters"),
+    ),
+)]
+async fn search(
+    State(pool): State<PgPool>,
+    Query(SearchQuery { q, limit }): Query<SearchQuery>,
+) -> ApiResult<Vec<SearchHit>> {
+    if q.trim().is_empty() {
+        return Err(ApiError::BadRequest("Empty search query".to_string()));
+    }
+    Ok(Json(search_shows(&pool, &q, page_limit(limit)).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/showtimes",
+    params(Location),
+    responses(
+        (status = 200, body = Vec<NearbyShowtime>),
+        (status = 400, description = "Invalid query parameters"),
+    ),
+)]
+async fn showtimes_nearby(
+    State(pool): State<PgPool>,
+    Query(location): Query<Location>,
+) -> ApiResult<Vec<NearbyShowtime>> {
+    location.validate()?;
+    Ok(Json(
+        showtimes_near(
+            &pool,
+            location.lat,
+            location.lon,
+            location.radius_km.unwrap_or(10.0),
+            page_limit(location.limit),
+        )
+        .await?,
+    ))
+}
+
+/// Routes of the read-only API over the scraped data, including the GraphQL schema
+pub fn router(pool: PgPool) -> Router {
+    Router::new()
+        .route("/shows", get(shows))
+        .route("/shows/{slug}", get(show))
+        .route("/shows/{slug}/showtimes", get(show_showtimes))
+        .route("/cinemas", get(cinemas))
+        .route("/cinemas/{slug}/schedule", get(cinema_schedule))
+        .route("/ratings", get(ratings))
+        .route("/tonight", get(tonight))
+        .route("/search", get(search))
+        .route("/showtimes", get(showtimes_nearby))
+        .route("/openapi.json", get(async || Json(ApiDoc::openapi())))
+        .with_state(pool.clone())
+        .merge(graphql::router(pool))
+}
+
+/// Serves the read-only API on the given address until the process stops
+pub async fn serve_api(addr: SocketAddr, pool: PgPool) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(pool)).await?;
+    Ok(())
+}
@@ -1,17 +1,542 @@
-use std::{thread, time::Duration};
+use std::{
+    env,
+    fs::File,
+    io::{self, BufWriter, Write},
+    net::SocketAddr,
+    path::PathBuf,
+};
 
-use anyhow::Result;
-use schraper::job::{
-    JobKind, Jobs,
+use anyhow::{Result, bail};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand};
+use schraper::{
+    api::{
+        health::{PERMANENT_FAILURE_THRESHOLD, serve_health},
+        rest::serve_api,
+    },
+    export::{
+        ics::{CalendarTarget, export_ics},
+        letterboxd::{LetterboxdFilter, export_letterboxd},
+        table::{TableFormat, export_table},
+    },
+    job::{
+        Jobs, RunContext,
+        calendar::{plan_screening, unplan_screening},
+        control::{send_command, serve_control},
+        movies::{MovieConfig, MovieFetcher, ScrapeTarget, seed_demo},
+        trakt::{TraktConfig, TraktSync},
+    },
+    query::{diff::diff_runs, search::search_shows, tonight::Tonight},
+    tui::Dashboard,
 };
+use tokio::signal::unix::{SignalKind, signal};
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// TOML file with job definitions, next to the ones in the database
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Run the jobs without writing to the database, only logging what they would
+    /// have written
+    #[arg(long, global = true)]
+    dry_run: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the scheduled jobs, which is also done without a subcommand
+    Daemon,
+    /// Runs a single job, by name or by kind
+    Run {
+        job: String,
+        /// Run the job right away and exit, instead of running it on its schedule
+        #[arg(long)]
+        once: bool,
+    },
+    /// Lists the jobs with their schedules
+    ListJobs,
+    /// Runs the jobs while showing them on a terminal dashboard
+    Tui,
+    /// Scrapes the data of a single entity once, outside of the scheduled jobs
+    Scrape {
+        #[command(subcommand)]
+        scraper: Scraper,
+    },
+    /// Matches the ratings of the shows which had no confident match again
+    Rematch {
+        /// Highest match score at which a rating is accepted, 0 being a perfect match
+        #[arg(long)]
+        max_score: Option<f64>,
+    },
+    /// Serves a read-only REST (and GraphQL) API over the scraped data, without running
+    /// any jobs
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: SocketAddr,
+    },
+    /// Exports the scraped data for use in other tools, dumps a table unless a feed
+    /// is given
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Export {
+        #[command(subcommand)]
+        feed: Option<ExportFeed>,
+        #[command(flatten)]
+        table: TableExport,
+    },
+    /// Sends a command to the control socket of a running daemon: pause <job>,
+    /// resume <job>, trigger <job> or status
+    Ctl {
+        /// Path of the socket, as set by CONTROL_SOCKET for the daemon
+        #[arg(long, env = "CONTROL_SOCKET")]
+        socket: PathBuf,
+        #[arg(required = true, num_args = 1..)]
+        command: Vec<String>,
+    },
+    /// Lists the upcoming showtimes ranked by the rating of their show
+    Tonight(TonightArgs),
+    /// Searches the shows by title, tolerating typos
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 10)]
+        limit: i64,
+    },
+    /// Lists the shows, showtimes and ratings which changed between two runs, by their
+    /// ids in the job logs
+    Diff { run_a: i64, run_b: i64 },
+    /// Plans a showtime, such that it is synchronized to the calendar
+    Plan {
+        /// Slug of the show
+        #[arg(long)]
+        show: String,
+        /// Slug of the cinema, e.g. amsterdam-arena
+        #[arg(long)]
+        cinema: String,
+        /// Start of the showtime, e.g. 2024-05-01T20:30:00+02:00
+        #[arg(long)]
+        time: DateTime<FixedOffset>,
+        #[arg(long)]
+        auditorium: String,
+    },
+    /// Unplans a planned screening by its id, removing it from the calendar
+    Unplan { id: i64 },
+    /// Authorizes the Trakt synchronization, by entering the printed code on the Trakt
+    /// website
+    TraktAuth,
+    /// Populates the database without scraping anything
+    Seed {
+        /// Synthetic cities, cinemas, shows, showtimes and ratings
+        #[arg(long, required = true)]
+        demo: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum Scraper {
+    Movies(MoviesTarget),
+}
+
+#[derive(Subcommand)]
+enum ExportFeed {
+    /// iCalendar feed of the upcoming showtimes of a cinema or show
+    Ics {
+        #[command(flatten)]
+        target: MoviesTarget,
+        /// File to write the feed to, instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Letterboxd importable CSV of the films which are currently playing
+    Letterboxd {
+        /// Slug of the city, e.g. amsterdam
+        #[arg(long)]
+        city: Option<String>,
+        /// Lowest critics (or else audience) score
+        #[arg(long)]
+        min_score: Option<i32>,
+        #[arg(long)]
+        genre: Option<String>,
+        /// File to write the list to, instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct TableExport {
+    /// Scraped table to dump, e.g. showtimes
+    #[arg(long, required = true)]
+    table: Option<String>,
+    #[arg(long, value_enum, default_value_t = TableFormat::Csv)]
+    format: TableFormat,
+    /// Only the rows of this date or later, for tables with a time column
+    #[arg(long)]
+    since: Option<NaiveDate>,
+    /// File to write the table to, instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct TonightArgs {
+    /// Slug of the city, e.g. amsterdam
+    #[arg(long)]
+    city: Option<String>,
+    /// Lowest critics (or else audience) score
+    #[arg(long)]
+    min_score: Option<i32>,
+    #[arg(long)]
+    genre: Option<String>,
+    /// Premium format of the auditorium, e.g. IMAX
+    #[arg(long)]
+    format: Option<String>,
+    /// Tag of the screening, e.g. ov
+    #[arg(long)]
+    tag: Option<String>,
+    /// Showtimes starting within this many hours
+    #[arg(long, default_value_t = 8)]
+    hours: i64,
+    #[arg(long, default_value_t = 20)]
+    limit: i64,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct MoviesTarget {
+    /// Slug of the cinema, e.g. amsterdam-arena
+    #[arg(long)]
+    cinema: Option<String>,
+    /// Slug of the show
+    #[arg(long)]
+    show: Option<String>,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let poll_rate = Duration::from_secs(1);
-    let mut jobs = Jobs::init().await?.add(JobKind::Movies, Duration::from_secs(3600));
+    let cli = Cli::parse();
+
+    // Log lines are shown inside the dashboard, which does not render colors
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .with_ansi(!matches!(cli.command, Some(Command::Tui)))
+        .init();
+
+    // The daemon is controlled without connecting to the database
+    if let Some(Command::Ctl { socket, command }) = &cli.command {
+        let answer = send_command(socket, &command.join(" ")).await?;
+        for line in &answer {
+            println!("{line}");
+        }
+        if answer.last().is_some_and(|line| line.starts_with("error:")) {
+            bail!("The command failed");
+        }
+        return Ok(());
+    }
+    let jobs = Jobs::init().await?;
+
+    if let Some(Command::Scrape {
+        scraper: Scraper::Movies(target),
+    }) = cli.command
+    {
+        let target = match (target.cinema, target.show) {
+            (Some(cinema), _) => ScrapeTarget::Cinema(cinema),
+            (_, show) => ScrapeTarget::Show(show.unwrap_or_default()),
+        };
+        let fetcher = MovieFetcher {
+            pool: jobs.pool(),
+            config: MovieConfig::default().with_clients(jobs.clients()),
+        };
+        let mut context = RunContext::default();
+        context.dry_run = cli.dry_run;
+        return fetcher.scrape(&target, &context).await;
+    }
+
+    if let Some(Command::Rematch { max_score }) = cli.command {
+        let config = match max_score {
+            Some(score) => MovieConfig::default().with_max_match_score(score),
+            None => MovieConfig::default(),
+        };
+        let fetcher = MovieFetcher {
+            pool: jobs.pool(),
+            config: config.with_clients(jobs.clients()),
+        };
+        return fetcher.rematch().await;
+    }
+
+    if let Some(Command::TraktAuth) = cli.command {
+        let sync = TraktSync {
+            pool: jobs.pool(),
+            config: TraktConfig::default(),
+        };
+        return sync.authorize().await;
+    }
+
+    if let Some(Command::Serve { addr }) = cli.command {
+        info!("Serving the API on {addr}");
+        return serve_api(addr, jobs.pool()).await;
+    }
+
+    if let Some(Command::Export {
+        feed: Some(ExportFeed::Ics { target, output }),
+        ..
+    }) = cli.command
+    {
+        let target = match (target.cinema, target.show) {
+            (Some(cinema), _) => CalendarTarget::Cinema(cinema),
+            (_, show) => CalendarTarget::Show(show.unwrap_or_default()),
+        };
+        let count = match output {
+            Some(path) => export_ics(&jobs.pool(), &target, File::create(path)?).await?,
+            None => export_ics(&jobs.pool(), &target, io::stdout().lock()).await?,
+        };
+        info!("Exported {count} showtimes");
+        return Ok(());
+    }
+
+    if let Some(Command::Export {
+        feed:
+            Some(ExportFeed::Letterboxd {
+                city,
+                min_score,
+                genre,
+                output,
+            }),
+        ..
+    }) = cli.command
+    {
+        let filter = LetterboxdFilter {
+            city,
+            min_score,
+            genre,
+        };
+        let count = match output {
+            Some(path) => export_letterboxd(&jobs.pool(), &filter, File::create(path)?).await?,
+            None => export_letterboxd(&jobs.pool(), &filter, io::stdout().lock()).await?,
+        };
+        info!("Exported {count} films");
+        return Ok(());
+    }
+
+    if let Some(Command::Export {
+        feed: None,
+        table:
+            TableExport {
+                table: Some(table),
+                format,
+                since,
+                output,
+            },
+    }) = cli.command
+    {
+        let writer: Box<dyn Write + Send> = match output {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        let count = export_table(&jobs.pool(), &table, format, since, writer).await?;
+        info!("Exported {count} rows of {table}");
+        return Ok(());
+    }
+
+    if let Some(Command::Tonight(args)) = cli.command {
+        let now = Utc::now();
+        let mut tonight = Tonight::new()
+            .between(now, now + Duration::hours(args.hours))
+            .limit(args.limit);
+        if let Some(city) = args.city {
+            tonight = tonight.city(city);
+        }
+        if let Some(min_score) = args.min_score {
+            tonight = tonight.min_score(min_score);
+        }
+        if let Some(genre) = args.genre {
+            tonight = tonight.genre(genre);
+        }
+        if let Some(format) = args.format {
+            tonight = tonight.format(format);
+        }
+        if let Some(tag) = args.tag {
+            tonight = tonight.tag(tag);
+        }
+        for showtime in tonight.fetch(&jobs.pool()).await? {
+            let score = showtime.score.map(|score| format!("{score}%"));
+            println!(
+                "{} {:<5} {:<40} {}",
+                showtime.time.with_timezone(&Local).format("%a %H:%M"),
+                score.as_deref().unwrap_or("-"),
+                showtime.title,
+                showtime.cinema_name
+            );
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Search { query, limit }) = &cli.command {
+        for hit in search_shows(&jobs.pool(), query, *limit).await? {
+            match hit.rating_title {
+                Some(rating_title) if rating_title != hit.title => {
+                    println!("{:<40} {} ({rating_title})", hit.slug, hit.title)
+                }
+                _ => println!("{:<40} {}", hit.slug, hit.title),
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Diff { run_a, run_b }) = cli.command {
+        let diff = diff_runs(&jobs.pool(), run_a, run_b).await?;
+        if diff.is_empty() {
+            println!("No differences between run {run_a} and run {run_b}");
+            return Ok(());
+        }
+        for show in &diff.new_shows {
+            println!("+ show      {:<40} {}", show.slug, show.title);
+        }
+        for show in &diff.removed_shows {
+            println!("- show      {:<40} {}", show.slug, show.title);
+        }
+        let showtimes = [("+", &diff.added_showtimes), ("-", &diff.removed_showtimes)];
+        for (sign, showtimes) in showtimes {
+            for showtime in showtimes {
+                println!(
+                    "{sign} showtime  {:<40} {} {} ({})",
+                    showtime.show_slug,
+                    showtime.time.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    showtime.cinema_slug,
+                    showtime.auditorium_name
+                );
+            }
+        }
+        let percentage = |score: Option<i32>| score.map_or("-".to_string(), |s| format!("{s}%"));
+        let score = |critics, audience| format!("{}/{}", percentage(critics), percentage(audience));
+        for change in &diff.rating_changes {
+            println!(
+                "~ rating    {:<40} {} -> {} {} -> {}",
+                change.show_slug,
+                change.rating_slug_before.as_deref().unwrap_or("-"),
+                change.rating_slug_after.as_deref().unwrap_or("-"),
+                score(change.critics_score_before, change.audience_score_before),
+                score(change.critics_score_after, change.audience_score_after)
+            );
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Plan {
+        show,
+        cinema,
+        time,
+        auditorium,
+    }) = &cli.command
+    {
+        let id = plan_screening(&jobs.pool(), show, cinema, *time, auditorium).await?;
+        println!("Planned screening {id}");
+        return Ok(());
+    }
+
+    if let Some(Command::Unplan { id }) = cli.command {
+        unplan_screening(&jobs.pool(), id).await?;
+        println!("Unplanned screening {id}");
+        return Ok(());
+    }
+
+    if let Some(Command::Seed { .. }) = cli.command {
+        return seed_demo(&jobs.pool()).await;
+    }
+
+    let mut jobs = match cli.config {
+        Some(path) => jobs.with_config_file(path),
+        None => jobs,
+    };
+    jobs = jobs.with_definitions().await?;
+    if cli.dry_run {
+        jobs = jobs.with_dry_run();
+    }
+
+    match &cli.command {
+        Some(Command::ListJobs) => {
+            for job in jobs.summaries() {
+                let last_ran = job
+                    .last_ran
+                    .map(|time| time.with_timezone(&Local).to_string());
+                println!(
+                    "{:<20} {:<12} {:<24} last ran {:<32} next run {}",
+                    job.name,
+                    job.kind,
+                    job.schedule,
+                    last_ran.as_deref().unwrap_or("never"),
+                    job.next_run.with_timezone(&Local)
+                );
+            }
+            return Ok(());
+        }
+        Some(Command::Run { job, once: true }) => {
+            let result = jobs.run_once(job).await;
+            jobs.close().await;
+            return result;
+        }
+        Some(Command::Run { job, once: false }) if !jobs.retain_only(job) => {
+            bail!("No job named {job}")
+        }
+        _ => (),
+    }
+    jobs = jobs.with_leader_election();
+
+    if let Ok(addr) = env::var("HEALTH_ADDR") {
+        let max_failures = match env::var("HEALTH_MAX_FAILURES") {
+            Ok(max_failures) => max_failures.parse()?,
+            Err(_) => PERMANENT_FAILURE_THRESHOLD,
+        };
+        tokio::spawn(serve_health(
+            addr.parse()?,
+            jobs.pool(),
+            jobs.statuses(),
+            max_failures,
+        ));
+    }
+
+    if let Ok(path) = env::var("CONTROL_SOCKET") {
+        let statuses = jobs.statuses();
+        tokio::spawn(async move {
+            if let Err(err) = serve_control(path, statuses).await {
+                error!("The control socket stopped: {err:#}");
+            }
+        });
+    }
+
+    // SIGINT and SIGTERM stop the jobs gracefully
+    let shutdown = jobs.shutdown_handle();
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            tokio::select! {
+                _ = interrupt.recv() => (),
+                _ = terminate.recv() => (),
+            }
+            info!("Shutdown requested, no new jobs are started");
+            shutdown.request();
+        }
+    });
+
+    // The dashboard shows the jobs while they run, failing jobs are reported in
+    // their status
+    let dashboard = match cli.command {
+        Some(Command::Tui) => Some(Dashboard::start(jobs.statuses(), shutdown)?),
+        _ => None,
+    };
+
+    let result = jobs.run_forever().await;
 
-    loop {
-        jobs.poll().await?;
-        thread::sleep(poll_rate);
+    if let Some(dashboard) = dashboard {
+        dashboard.stop()?;
     }
+    jobs.close().await;
+    result
 }
@@ -0,0 +1,186 @@
+use std::io::Write;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::{FromRow, PgPool};
+
+/// Lines of an iCalendar file are folded at this many octets
+const MAX_LINE_LENGTH: usize = 75;
+
+/// The schedule which is turned into a calendar feed
+#[derive(Debug, Clone)]
+pub enum CalendarTarget {
+    /// Every showtime at the cinema with the slug
+    Cinema(String),
+    /// Every showtime of the show with the slug, at any cinema
+    Show(String),
+}
+
+/// A showtime as an event of the calendar
+#[derive(Debug, FromRow)]
+pub struct CalendarEvent {
+    pub show_slug: String,
+    pub title: String,
+    pub cinema_slug: String,
+    pub cinema_name: String,
+    pub city_name: String,
+    pub auditorium_name: String,
+    pub time: DateTime<Utc>,
+    /// The duration of the show is used when there is no end time
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration: i32,
+    pub reservation_url: Option<String>,
+}
+
+impl CalendarEvent {
+    /// Stays the same between exports, such that calendar apps update the event
+    /// instead of adding it again
+    fn uid(&self) -> String {
+        format!(
+            "{}-{}-{}-{}@schraper",
+            self.show_slug,
+            self.cinema_slug,
+            self.time.timestamp(),
+            self.auditorium_name.to_lowercase().replace(' ', "-")
+        )
+    }
+
+    fn end(&self) -> DateTime<Utc> {
+        self.end_time
+            .unwrap_or_else(|| self.time + TimeDelta::minutes(self.duration.into()))
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes a TEXT value as described in RFC 5545
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a content line, folded such that no line is longer than 75 octets
+fn write_line<W: Write>(writer: &mut W, line: &str) -> Result<()> {
+    let mut start = 0;
+    let mut limit = MAX_LINE_LENGTH;
+    while line.len() - start > limit {
+        // Never split a multi-byte character
+        let mut end = start + limit;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        writer.write_all(&line.as_bytes()[start..end])?;
+        writer.write_all(b"\r\n ")?;
+        start = end;
+        // Continuation lines start with a space
+        limit = MAX_LINE_LENGTH - 1;
+    }
+    writer.write_all(&line.as_bytes()[start..])?;
+    writer.write_all(b"\r\n")?;
+    Ok(())
+}
+
+/// Writes the events as an iCalendar file with the given calendar name
+pub fn write_calendar<W: Write>(name: &str, events: &[CalendarEvent], mut writer: W) -> Result<()> {
+    let stamp = format_time(Utc::now());
+    let w = &mut writer;
+    write_line(w, "BEGIN:VCALENDAR")?;
+    write_line(w, "VERSION:2.0")?;
+    write_line(w, "PRODID:-//schraper//showtimes//EN")?;
+    write_line(w, "CALSCALE:GREGORIAN")?;
+    write_line(w, &format!("X-WR-CALNAME:{}", escape(name)))?;
+    for event in events {
+        write_line(w, "BEGIN:VEVENT")?;
+        write_line(w, &format!("UID:{}", event.uid()))?;
+        write_line(w, &format!("DTSTAMP:{stamp}"))?;
+        write_line(w, &format!("DTSTART:{}", format_time(event.time)))?;
+        write_line(w, &format!("DTEND:{}", format_time(event.end())))?;
+        write_line(w, &format!("SUMMARY:{}", escape(&event.title)))?;
+        write_line(
+            w,
+            &format!(
+                "LOCATION:{}",
+                escape(&format!(
+                    "{}, {} ({})",
+                    event.cinema_name, event.city_name, event.auditorium_name
+                ))
+            ),
+        )?;
+        if let Some(url) = &event.reservation_url {
+            write_line(w, &format!("URL:{url}"))?;
+        }
+        write_line(w, "END:VEVENT")?;
+    }
+    write_line(w, "END:VCALENDAR")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes an iCalendar feed of the upcoming showtimes of a cinema or show
+pub async fn export_ics<W: Write>(
+    pool: &PgPool,
+    target: &CalendarTarget,
+    writer: W,
+) -> Result<usize> {
+    let (name_query, condition, kind, slug) = match target {
+        CalendarTarget::Cinema(slug) => (
+            "SELECT name FROM cinemas WHERE slug = $1",
+            "c.slug",
+            "cinema",
+            slug,
+        ),
+        CalendarTarget::Show(slug) => (
+            "SELECT title FROM shows WHERE slug = $1",
+            "s.slug",
+            "show",
+            slug,
+        ),
+    };
+    // An unknown slug is told apart from a schedule without showtimes
+    let name: Option<String> = sqlx::query_scalar(name_query)
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?;
+    let Some(name) = name else {
+        bail!("Unknown {kind} {slug}");
+    };
+
+    let events: Vec<CalendarEvent> = sqlx::query_as(&format!(
+        r#"SELECT
+            s.slug AS show_slug,
+            s.title,
+            c.slug AS cinema_slug,
+            c.name AS cinema_name,
+            ci.name AS city_name,
+            st.auditorium_name,
+            st.time,
+            st.end_time,
+            s.duration,
+            st.reservation_url
+        FROM showtimes st
+        JOIN shows s ON s.slug = st.show_slug
+        JOIN cinemas c ON c.slug = st.cinema_slug
+        JOIN cities ci ON ci.slug = c.city_slug
+        WHERE {condition} = $1 AND st.time >= current_timestamp
+        ORDER BY st.time, s.title"#
+    ))
+    .bind(slug)
+    .fetch_all(pool)
+    .await?;
+
+    write_calendar(&name, &events, writer)?;
+    Ok(events.len())
+}
@@ -0,0 +1,97 @@
+use std::io::Write;
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder};
+
+/// A row of a Letterboxd import file. Letterboxd matches films on title and year,
+/// the scores are added to the notes of the list entry.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct LetterboxdRow {
+    title: String,
+    year: Option<i32>,
+    review: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct PlayingFilm {
+    title: String,
+    year: Option<i32>,
+    critics_score: Option<i32>,
+    audience_score: Option<i32>,
+}
+
+impl From<PlayingFilm> for LetterboxdRow {
+    fn from(film: PlayingFilm) -> Self {
+        let scores = [
+            film.critics_score.map(|score| format!("Critics {score}%")),
+            film.audience_score.map(|score| format!("Audience {score}%")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        LetterboxdRow {
+            title: film.title,
+            year: film.year,
+            review: (!scores.is_empty()).then(|| format!("Rotten Tomatoes: {}", scores.join(", "))),
+        }
+    }
+}
+
+/// Filter on the films which are exported, by default every film which has an
+/// upcoming showtime is exported.
+#[derive(Debug, Default, Clone)]
+pub struct LetterboxdFilter {
+    pub city: Option<String>,
+    pub min_score: Option<i32>,
+    pub genre: Option<String>,
+}
+
+/// Writes a Letterboxd importable CSV of the films currently playing
+pub async fn export_letterboxd<W: Write>(
+    pool: &PgPool,
+    filter: &LetterboxdFilter,
+    writer: W,
+) -> Result<usize> {
+    // The Rotten Tomatoes title is the English title, which is what Letterboxd uses
+    let mut query: QueryBuilder<Postgres> = QueryBuilder::new(
+        r#"SELECT
+            COALESCE(r.title, s.title) AS title,
+            COALESCE(r.release_year, EXTRACT(YEAR FROM s.release_at::date)::integer) AS year,
+            MAX(r.critics_score) AS critics_score,
+            MAX(r.audience_score) AS audience_score
+        FROM shows s
+        JOIN showtimes st ON st.show_slug = s.slug
+        JOIN cinemas c ON c.slug = st.cinema_slug
+        LEFT JOIN ratings r ON r.slug = s.rating_slug
+        WHERE st.time >= current_timestamp"#,
+    );
+    if let Some(city) = &filter.city {
+        query.push(" AND c.city_slug = ").push_bind(city);
+    }
+    if let Some(min_score) = filter.min_score {
+        query
+            .push(" AND COALESCE(r.critics_score, r.audience_score) >= ")
+            .push_bind(min_score);
+    }
+    if let Some(genre) = &filter.genre {
+        query
+            .push(
+                " AND EXISTS (SELECT 1 FROM show_genres g JOIN genre_mappings m ON m.genre_slug = g.genre_slug WHERE g.show_slug = s.slug AND m.label ILIKE ",
+            )
+            .push_bind(genre)
+            .push(")");
+    }
+    query.push(" GROUP BY 1, 2 ORDER BY 1");
+
+    let films: Vec<PlayingFilm> = query.build_query_as().fetch_all(pool).await?;
+    let count = films.len();
+
+    let mut csv = csv::Writer::from_writer(writer);
+    for film in films {
+        csv.serialize(LetterboxdRow::from(film))?;
+    }
+    csv.flush()?;
+    Ok(count)
+}
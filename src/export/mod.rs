@@ -0,0 +1,5 @@
+//! Exports of the scraped data into formats understood by other tools.
+
+pub mod ics;
+pub mod letterboxd;
+pub mod table;
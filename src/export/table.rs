@@ -0,0 +1,283 @@
+use std::{io::Write, sync::Arc};
+
+use anyhow::{Result, bail};
+use arrow_array::{
+    ArrayRef, BooleanArray, Date32Array, Float64Array, Int32Array, Int64Array, RecordBatch,
+    StringArray, TimestampMicrosecondArray, types::Date32Type,
+};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
+use itertools::Itertools;
+use parquet::arrow::ArrowWriter;
+use sqlx::{Decode, FromRow, PgPool, Postgres, Row, Type, postgres::PgRow};
+
+/// Rows are read from the database and written in batches of this size
+const BATCH_SIZE: usize = 10_000;
+
+/// Tables which can be exported, with the column `since` filters on. Tables with
+/// credentials or job bookkeeping are left out.
+pub const EXPORTABLE_TABLES: &[(&str, Option<&str>)] = &[
+    ("cities", None),
+    ("cinemas", None),
+    ("shows", None),
+    ("genres", None),
+    ("genre_mappings", None),
+    ("show_genres", None),
+    ("images", None),
+    ("show_release_dates", Some("release_date")),
+    ("show_cast", None),
+    ("show_trailers", None),
+    ("content_warnings", None),
+    ("showtimes", Some("time")),
+    ("showtime_tags", Some("time")),
+    ("showtimes_history", Some("time")),
+    ("ratings", Some("fetched_at")),
+    ("ratings_history", Some("valid_from")),
+    ("tmdb_ratings", None),
+    ("venues", None),
+    ("events", Some("starts_at")),
+    ("event_ticket_links", None),
+    ("books", None),
+    ("bestsellers", Some("published_date")),
+    ("grocery_categories", None),
+    ("grocery_products", None),
+    ("grocery_prices", Some("fetched_at")),
+    ("weather_forecasts", Some("time")),
+    ("run_deltas", Some("run_dt")),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TableFormat {
+    Csv,
+    Parquet,
+}
+
+/// Type of an exported column, columns of other types are exported as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Integer,
+    BigInt,
+    Float,
+    Boolean,
+    Timestamp,
+    Date,
+    Text,
+}
+
+impl ColumnType {
+    fn from_postgres(data_type: &str) -> Self {
+        match data_type {
+            "smallint" | "integer" => ColumnType::Integer,
+            "bigint" => ColumnType::BigInt,
+            "real" | "double precision" | "numeric" => ColumnType::Float,
+            "boolean" => ColumnType::Boolean,
+            "timestamp with time zone" | "timestamp without time zone" => ColumnType::Timestamp,
+            "date" => ColumnType::Date,
+            _ => ColumnType::Text,
+        }
+    }
+
+    /// Postgres type the column is cast to, such that it decodes into a single type
+    fn cast(self) -> &'static str {
+        match self {
+            ColumnType::Integer => "integer",
+            ColumnType::BigInt => "bigint",
+            ColumnType::Float => "double precision",
+            ColumnType::Boolean => "boolean",
+            ColumnType::Timestamp => "timestamptz",
+            ColumnType::Date => "date",
+            ColumnType::Text => "text",
+        }
+    }
+
+    fn arrow(self) -> DataType {
+        match self {
+            ColumnType::Integer => DataType::Int32,
+            ColumnType::BigInt => DataType::Int64,
+            ColumnType::Float => DataType::Float64,
+            ColumnType::Boolean => DataType::Boolean,
+            ColumnType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            ColumnType::Date => DataType::Date32,
+            ColumnType::Text => DataType::Utf8,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct Column {
+    column_name: String,
+    data_type: String,
+}
+
+fn values<'r, T>(rows: &'r [PgRow], index: usize) -> Result<Vec<Option<T>>>
+where
+    T: Decode<'r, Postgres> + Type<Postgres>,
+{
+    Ok(rows
+        .iter()
+        .map(|row| row.try_get(index))
+        .collect::<Result<_, _>>()?)
+}
+
+fn column_array(rows: &[PgRow], index: usize, column_type: ColumnType) -> Result<ArrayRef> {
+    Ok(match column_type {
+        ColumnType::Integer => Arc::new(Int32Array::from(values::<i32>(rows, index)?)),
+        ColumnType::BigInt => Arc::new(Int64Array::from(values::<i64>(rows, index)?)),
+        ColumnType::Float => Arc::new(Float64Array::from(values::<f64>(rows, index)?)),
+        ColumnType::Boolean => Arc::new(BooleanArray::from(values::<bool>(rows, index)?)),
+        ColumnType::Timestamp => Arc::new(
+            TimestampMicrosecondArray::from(
+                values::<DateTime<Utc>>(rows, index)?
+                    .into_iter()
+                    .map(|time| time.map(|time| time.timestamp_micros()))
+                    .collect::<Vec<_>>(),
+            )
+            .with_timezone("UTC"),
+        ),
+        ColumnType::Date => Arc::new(Date32Array::from(
+            values::<NaiveDate>(rows, index)?
+                .into_iter()
+                .map(|date| date.map(Date32Type::from_naive_date))
+                .collect::<Vec<_>>(),
+        )),
+        ColumnType::Text => Arc::new(StringArray::from(values::<String>(rows, index)?)),
+    })
+}
+
+enum TableWriter<W: Write + Send> {
+    Csv(csv::Writer<W>),
+    Parquet(ArrowWriter<W>),
+}
+
+impl<W: Write + Send> TableWriter<W> {
+    fn new(format: TableFormat, schema: SchemaRef, writer: W) -> Result<Self> {
+        Ok(match format {
+            TableFormat::Csv => {
+                let mut csv = csv::Writer::from_writer(writer);
+                csv.write_record(schema.fields().iter().map(|field| field.name()))?;
+                TableWriter::Csv(csv)
+            }
+            TableFormat::Parquet => {
+                TableWriter::Parquet(ArrowWriter::try_new(writer, schema, None)?)
+            }
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match self {
+            TableWriter::Csv(csv) => {
+                // Nulls become empty fields and timestamps are written as RFC 3339
+                let formatters = batch
+                    .columns()
+                    .iter()
+                    .map(|array| ArrayFormatter::try_new(array, &FormatOptions::default()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for row in 0..batch.num_rows() {
+                    csv.write_record(
+                        formatters
+                            .iter()
+                            .map(|formatter| formatter.value(row).to_string()),
+                    )?;
+                }
+            }
+            TableWriter::Parquet(parquet) => parquet.write(batch)?,
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            TableWriter::Csv(mut csv) => csv.flush()?,
+            TableWriter::Parquet(parquet) => {
+                parquet.close()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Dumps a scraped table as CSV or Parquet, optionally only the rows of `since` or
+/// later. Returns the number of exported rows.
+pub async fn export_table<W: Write + Send>(
+    pool: &PgPool,
+    table: &str,
+    format: TableFormat,
+    since: Option<NaiveDate>,
+    writer: W,
+) -> Result<usize> {
+    let Some((table, since_column)) = EXPORTABLE_TABLES
+        .iter()
+        .find(|(name, _)| *name == table)
+        .copied()
+    else {
+        bail!(
+            "Cannot export {table}, expected one of {}",
+            EXPORTABLE_TABLES.iter().map(|(name, _)| name).join(", ")
+        );
+    };
+
+    let columns: Vec<Column> = sqlx::query_as(
+        r#"SELECT column_name::text, data_type::text
+        FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = $1
+        ORDER BY ordinal_position"#,
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+    let columns = columns
+        .into_iter()
+        .map(|column| {
+            let column_type = ColumnType::from_postgres(&column.data_type);
+            (column.column_name, column_type)
+        })
+        .collect::<Vec<_>>();
+    let schema = Arc::new(Schema::new(
+        columns
+            .iter()
+            .map(|(name, column_type)| Field::new(name, column_type.arrow(), true))
+            .collect::<Vec<_>>(),
+    ));
+
+    let mut query = format!(
+        "SELECT {} FROM {table}",
+        columns
+            .iter()
+            .map(|(name, column_type)| format!("\"{name}\"::{}", column_type.cast()))
+            .join(", ")
+    );
+    match (since, since_column) {
+        // A date is only digits and dashes, so it is safe to put in the query
+        (Some(since), Some(column)) => query.push_str(&format!(" WHERE {column} >= '{since}'")),
+        (Some(_), None) => bail!("Table {table} has no time column to export since"),
+        (None, _) => (),
+    }
+
+    // A cursor keeps the whole table out of memory
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!("DECLARE export NO SCROLL CURSOR FOR {query}"))
+        .execute(&mut *tx)
+        .await?;
+    let mut writer = TableWriter::new(format, schema.clone(), writer)?;
+    let mut count = 0;
+    loop {
+        let rows = sqlx::query(&format!("FETCH {BATCH_SIZE} FROM export"))
+            .fetch_all(&mut *tx)
+            .await?;
+        if rows.is_empty() {
+            break;
+        }
+        let arrays = columns
+            .iter()
+            .enumerate()
+            .map(|(index, (_, column_type))| column_array(&rows, index, *column_type))
+            .collect::<Result<Vec<_>>>()?;
+        writer.write(&RecordBatch::try_new(schema.clone(), arrays)?)?;
+        count += rows.len();
+    }
+    tx.rollback().await?;
+    writer.finish()?;
+    Ok(count)
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+
+use anyhow::Result;
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::job::{RunContext, delta::TableDelta};
+
+/// Amount of previous runs making up the baseline
+const BASELINE_RUNS: i64 = 10;
+
+/// A table for which a run fetched a lot less (or more) than usual
+#[derive(Debug, Clone)]
+pub struct VolumeAnomaly {
+    pub table: &'static str,
+    pub submitted: u64,
+    pub baseline: f64,
+}
+
+impl VolumeAnomaly {
+    /// Relative drop compared to the baseline, as a percentage
+    pub fn drop_percentage(&self) -> f64 {
+        100.0 * (1.0 - self.submitted as f64 / self.baseline)
+    }
+
+    /// Relative change compared to the baseline, as a percentage which is negative
+    /// for drops
+    pub fn deviation_percentage(&self) -> f64 {
+        -self.drop_percentage()
+    }
+}
+
+impl fmt::Display for VolumeAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, {:+.0}% compared to the baseline of {:.0}",
+            self.submitted,
+            self.table,
+            self.deviation_percentage(),
+            self.baseline
+        )
+    }
+}
+
+/// Average amount of rows of the table submitted by the previous runs of the job
+async fn baseline(pool: &PgPool, jobname: &str, table: &str) -> Result<Option<f64>> {
+    let baseline: Option<f64> = sqlx::query_scalar(
+        r#"SELECT avg(submitted)::float FROM (
+            SELECT submitted FROM run_deltas
+            WHERE jobname = $1 AND table_name = $2
+            ORDER BY run_dt DESC
+            LIMIT $3
+        ) previous"#,
+    )
+    .bind(jobname)
+    .bind(table)
+    .bind(BASELINE_RUNS)
+    .fetch_one(pool)
+    .await?;
+    Ok(baseline.filter(|baseline| *baseline > 0.0))
+}
+
+/// Flags the run in the `run_anomalies` table, unless it is a dry run
+async fn flag(
+    pool: &PgPool,
+    jobname: &str,
+    anomaly: &VolumeAnomaly,
+    context: &RunContext,
+) -> Result<()> {
+    warn!("ALERT: {jobname} fetched {anomaly}");
+    if context.dry_run {
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO run_anomalies(jobname, table_name, submitted, baseline) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(jobname)
+    .bind(anomaly.table)
+    .bind(anomaly.submitted as i64)
+    .bind(anomaly.baseline)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Compares the amount of rows fetched for a table against the average of the
+/// previous runs (as recorded in `run_deltas`). When it dropped by more than
+/// `max_drop_percentage` the run is flagged in the `run_anomalies` table.
+///
+/// Upstream half-outages look like a legitimately shrinking schedule, so callers
+/// should refrain from removing data when an anomaly is returned.
+pub async fn check_volume(
+    pool: &PgPool,
+    jobname: &str,
+    table: &'static str,
+    submitted: usize,
+    max_drop_percentage: f64,
+    context: &RunContext,
+) -> Result<Option<VolumeAnomaly>> {
+    let Some(baseline) = baseline(pool, jobname, table).await? else {
+        return Ok(None);
+    };
+    let anomaly = VolumeAnomaly {
+        table,
+        submitted: submitted as u64,
+        baseline,
+    };
+    if anomaly.drop_percentage() <= max_drop_percentage {
+        return Ok(None);
+    }
+    flag(pool, jobname, &anomaly, context).await?;
+    Ok(Some(anomaly))
+}
+
+/// Compares the amount of rows written to the tables by a run of the job against the
+/// average of the previous runs, flagging the tables which deviate by more than
+/// `max_deviation_percentage` in either direction. Has to be called before the
+/// deltas are stored, such that the run is not part of its own baseline.
+///
+/// A sudden drop (or explosion) of rows almost always means that the shape of an
+/// upstream API changed, even when the run itself succeeded.
+pub async fn check_deltas<'a>(
+    pool: &PgPool,
+    jobname: &str,
+    deltas: impl IntoIterator<Item = &'a TableDelta>,
+    max_deviation_percentage: f64,
+    context: &RunContext,
+) -> Result<Vec<VolumeAnomaly>> {
+    let mut anomalies = vec![];
+    for delta in deltas {
+        let Some(baseline) = baseline(pool, jobname, delta.table).await? else {
+            continue;
+        };
+        let anomaly = VolumeAnomaly {
+            table: delta.table,
+            submitted: delta.submitted,
+            baseline,
+        };
+        if anomaly.deviation_percentage().abs() > max_deviation_percentage {
+            flag(pool, jobname, &anomaly, context).await?;
+            anomalies.push(anomaly);
+        }
+    }
+    Ok(anomalies)
+}
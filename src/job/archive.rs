@@ -0,0 +1,56 @@
+//! Archive of the raw responses of upstream APIs, such that historical data can be
+//! re-processed when the schema or the matching logic improves.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use chrono::Utc;
+use object_store::{ObjectStore, local::LocalFileSystem, path::Path};
+use reqwest::Url;
+use tracing::warn;
+
+/// Storage to which responses are archived, either a local directory or a bucket of
+/// an S3-compatible object store
+pub struct Archive {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+}
+
+/// Opens the store at `location`, which is either a directory or the URL of a bucket
+/// with an optional prefix (e.g. `s3://schraper/raw`). Credentials, region and the
+/// endpoint of S3-compatible stores are read from the `AWS_*` variables.
+pub(crate) fn open_store(location: &str) -> Result<(Arc<dyn ObjectStore>, Path)> {
+    let Ok(url) = Url::parse(location) else {
+        std::fs::create_dir_all(location)?;
+        return Ok((
+            Arc::new(LocalFileSystem::new_with_prefix(location)?),
+            Path::default(),
+        ));
+    };
+    let options = std::env::vars().map(|(key, value)| (key.to_lowercase(), value));
+    let (store, prefix) = object_store::parse_url_opts(&url, options)
+        .with_context(|| format!("Could not open the store at {location}"))?;
+    Ok((store.into(), prefix))
+}
+
+impl Archive {
+    /// Opens the archive at `location`, see `open_store`
+    pub fn open(location: &str) -> Result<Self> {
+        let (store, prefix) = open_store(location)?;
+        Ok(Archive { store, prefix })
+    }
+
+    /// Stores the body as `<prefix>/<date>/<time>-<name>`. Failing to archive does not
+    /// fail the request, so errors are only logged.
+    pub async fn store(&self, name: &str, body: Bytes) {
+        let now = Utc::now();
+        let location = self
+            .prefix
+            .child(now.format("%Y-%m-%d").to_string())
+            .child(format!("{}-{name}", now.format("%H%M%S%.3f")));
+        if let Err(e) = self.store.put(&location, body).await {
+            warn!("Could not archive the response as {location}: {e}");
+        }
+    }
+}
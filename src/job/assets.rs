@@ -0,0 +1,121 @@
+//! Local copies of the posters of shows, since the URLs listed by Pathé may expire.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use object_store::{ObjectStore, path::Path};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tracing::{debug, warn};
+
+use crate::job::{archive::open_store, util::Client};
+
+/// Size variants of posters which are downloaded
+const POSTER_VARIANTS: &[&str] = &["lg", "md"];
+
+/// Storage to which posters are downloaded, either a local directory or a bucket of
+/// an S3-compatible object store
+pub struct AssetStore {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+}
+
+/// Outcome of downloading the posters
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PosterDownloads {
+    pub downloaded: usize,
+    /// Posters of which the content was stored already under another URL
+    pub duplicates: usize,
+    pub failed: usize,
+}
+
+/// Name under which the image is stored, the hash of its content followed by the
+/// extension of the URL
+fn asset_name(url: &str, sha256: &str) -> String {
+    let extension = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .and_then(|file| file.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_lowercase())
+        .filter(|extension| !extension.is_empty() && extension.len() <= 5)
+        .unwrap_or_else(|| "jpg".to_string());
+    format!("{sha256}.{extension}")
+}
+
+impl AssetStore {
+    /// Opens the store at `location`, see `open_store`
+    pub fn open(location: &str) -> Result<Self> {
+        let (store, prefix) = open_store(location)?;
+        Ok(AssetStore { store, prefix })
+    }
+
+    /// Downloads the `lg` and `md` posters which were not downloaded before, storing
+    /// them as `<prefix>/posters/<sha256>.<extension>` and recording where they went
+    /// in `image_assets`. Failing to download a poster is logged, it is retried during
+    /// the next run.
+    pub async fn download_posters(
+        &self,
+        pool: &PgPool,
+        client: &Client,
+    ) -> Result<PosterDownloads> {
+        let urls: Vec<String> = sqlx::query_scalar(
+            r#"SELECT DISTINCT i.url
+            FROM images i
+            LEFT JOIN image_assets a ON a.url = i.url
+            WHERE i.image_type = 'poster' AND i.position = 0 AND i.variant = ANY($1)
+                AND a.url IS NULL"#,
+        )
+        .bind(POSTER_VARIANTS)
+        .fetch_all(pool)
+        .await?;
+
+        let mut downloads = PosterDownloads::default();
+        for url in urls {
+            match self.download(pool, client, &url).await {
+                Ok(true) => downloads.downloaded += 1,
+                Ok(false) => downloads.duplicates += 1,
+                Err(e) => {
+                    warn!("Could not download the poster at {url}: {e:#}");
+                    downloads.failed += 1;
+                }
+            }
+        }
+        Ok(downloads)
+    }
+
+    /// Downloads a single image, returning whether its content was new
+    async fn download(&self, pool: &PgPool, client: &Client, url: &str) -> Result<bool> {
+        let body = client.get(url).await?;
+        let sha256 = hex::encode(Sha256::digest(&body));
+        // The same image is often listed under several URLs
+        let stored: Option<String> =
+            sqlx::query_scalar("SELECT path FROM image_assets WHERE sha256 = $1 LIMIT 1")
+                .bind(&sha256)
+                .fetch_optional(pool)
+                .await?;
+        let new = stored.is_none();
+        let path = match stored {
+            Some(path) => path,
+            None => {
+                let location = self.prefix.child("posters").child(asset_name(url, &sha256));
+                self.store.put(&location, body).await?;
+                debug!("Stored the poster at {url} as {location}");
+                location.to_string()
+            }
+        };
+        sqlx::query(
+            r#"INSERT INTO image_assets(url, path, sha256) VALUES ($1, $2, $3)
+            ON CONFLICT (url) DO UPDATE SET
+                path = excluded.path,
+                sha256 = excluded.sha256,
+                downloaded_at = current_timestamp"#,
+        )
+        .bind(url)
+        .bind(path)
+        .bind(sha256)
+        .execute(pool)
+        .await?;
+        Ok(new)
+    }
+}
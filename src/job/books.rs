@@ -0,0 +1,282 @@
+//! Bestselling books from the lists of the New York Times Books API.
+
+use std::{collections::HashMap, env, num::NonZeroU32};
+
+use anyhow::{Result, bail};
+use chrono::NaiveDate;
+use reqwest::Url;
+use serde::Deserialize;
+use sqlx::PgPool;
+use sqlx_batch::BatchInserter;
+use tracing::{debug, info};
+
+use super::{
+    RunContext, Runnable,
+    delta::RunDeltas,
+    notify::NotifyConfig,
+    parse::parse_number,
+    util::{Client, Clients},
+};
+
+static BOOKS_API_URL: &str = "https://api.nytimes.com/svc/books/v3/lists/current";
+
+/// Name of the shared client used for the requests to the New York Times, see
+/// `Jobs::with_client`
+pub const NYT_CLIENT: &str = "nytimes";
+
+/// Configuration of the books fetcher. The API key defaults to the `NYT_API_KEY`
+/// environment variable.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BooksConfig {
+    api_key: String,
+    /// Encoded names of the lists to fetch, such as `hardcover-fiction`
+    lists: Vec<String>,
+    /// 5 by default, the quota of the Books API
+    requests_per_minute: Option<NonZeroU32>,
+    /// Alert when the amount of rows written to a table deviates by more than this
+    /// percentage from the previous runs, `None` does not compare
+    max_volume_deviation: Option<f64>,
+    /// Where the anomalous runs are alerted
+    notify: Option<NotifyConfig>,
+    /// Clients shared with other jobs, handed over by `Jobs`
+    #[serde(skip)]
+    clients: Clients,
+}
+
+impl Default for BooksConfig {
+    fn default() -> Self {
+        BooksConfig {
+            api_key: env::var("NYT_API_KEY").unwrap_or_default(),
+            lists: vec![
+                "hardcover-fiction".to_string(),
+                "hardcover-nonfiction".to_string(),
+            ],
+            requests_per_minute: None,
+            max_volume_deviation: None,
+            notify: None,
+            clients: Clients::default(),
+        }
+    }
+}
+
+impl BooksConfig {
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    /// Fetch the lists with the given encoded names instead of the hardcover fiction
+    /// and nonfiction lists
+    pub fn with_lists(mut self, lists: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.lists = lists.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Flag the run as anomalous when the amount of books or ranks deviates by more
+    /// than `percentage` from the previous runs
+    pub fn with_max_volume_deviation(mut self, percentage: f64) -> Self {
+        self.max_volume_deviation = Some(percentage);
+        self
+    }
+
+    pub fn with_notify(mut self, notify: NotifyConfig) -> Self {
+        self.notify = Some(notify);
+        self
+    }
+
+    /// Use the client shared under `NYT_CLIENT` when there is, which keeps its own rate
+    /// limit
+    pub fn with_clients(mut self, clients: Clients) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    /// The Books API allows 5 requests per minute, failed requests are retried after
+    /// backing off
+    fn client(&self) -> Result<Client> {
+        if let Some(client) = self.clients.get(NYT_CLIENT) {
+            return Ok(client);
+        }
+        Ok(Client::new()
+            .with_limit_per_minute(self.requests_per_minute.unwrap_or(5.try_into()?))
+            .with_max_retries(3))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListResponse {
+    results: BestsellerList,
+}
+
+#[derive(Debug, Deserialize)]
+struct BestsellerList {
+    list_name_encoded: String,
+    /// Week of which the list is the bestseller list
+    published_date: NaiveDate,
+    #[serde(default)]
+    books: Vec<NytBook>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NytBook {
+    rank: i32,
+    rank_last_week: Option<i32>,
+    weeks_on_list: Option<i32>,
+    #[serde(default)]
+    primary_isbn13: String,
+    primary_isbn10: Option<String>,
+    title: String,
+    author: String,
+    publisher: Option<String>,
+    description: Option<String>,
+    /// Serialized as a string such as `"27.99"`, `"0.00"` when unknown
+    price: Option<serde_json::Value>,
+    book_image: Option<String>,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "books"]
+struct Book {
+    #[key]
+    isbn13: String,
+    isbn10: Option<String>,
+    title: String,
+    author: String,
+    publisher: Option<String>,
+    description: Option<String>,
+    /// Price in dollar cents
+    price_cents: Option<i32>,
+    image_url: Option<String>,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "bestsellers"]
+struct Bestseller {
+    #[key]
+    list_name: String,
+    #[key]
+    published_date: NaiveDate,
+    #[key]
+    isbn13: String,
+    rank: i32,
+    rank_last_week: Option<i32>,
+    weeks_on_list: Option<i32>,
+}
+
+fn price_cents(price: Option<serde_json::Value>) -> Option<i32> {
+    let price = match price? {
+        serde_json::Value::String(price) => parse_number(&price).ok()?,
+        serde_json::Value::Number(price) => price.as_f64()?,
+        _ => return None,
+    };
+    (price > 0.0).then(|| (price * 100.0).round() as i32)
+}
+
+/// Empty strings are listed for books without e.g. a publisher
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+impl BestsellerList {
+    /// Splits the list in the books and their ranks. Books without an ISBN-13, such
+    /// as some e-books, are left out.
+    fn flatten(self) -> (Vec<Book>, Vec<Bestseller>) {
+        let mut books = vec![];
+        let mut ranks = vec![];
+        for book in self.books {
+            if book.primary_isbn13.trim().is_empty() {
+                debug!("Skipped {} without an ISBN-13", book.title);
+                continue;
+            }
+            ranks.push(Bestseller {
+                list_name: self.list_name_encoded.clone(),
+                published_date: self.published_date,
+                isbn13: book.primary_isbn13.clone(),
+                rank: book.rank,
+                rank_last_week: book.rank_last_week.filter(|rank| *rank > 0),
+                weeks_on_list: book.weeks_on_list,
+            });
+            books.push(Book {
+                isbn13: book.primary_isbn13,
+                isbn10: non_empty(book.primary_isbn10),
+                title: book.title,
+                author: book.author,
+                publisher: non_empty(book.publisher),
+                description: non_empty(book.description),
+                price_cents: price_cents(book.price),
+                image_url: non_empty(book.book_image),
+            });
+        }
+        (books, ranks)
+    }
+}
+
+async fn fetch_list(client: &Client, config: &BooksConfig, list: &str) -> Result<BestsellerList> {
+    let url = Url::parse_with_params(
+        &format!("{BOOKS_API_URL}/{list}.json"),
+        [("api-key", &config.api_key)],
+    )?;
+    let response: ListResponse = client.get_json(url).await?;
+    Ok(response.results)
+}
+
+/// Fetches the current bestseller lists from the New York Times Books API and stores
+/// the books together with their ranks.
+#[derive(Debug)]
+pub struct BookFetcher {
+    pub pool: PgPool,
+    pub config: BooksConfig,
+}
+impl Runnable for BookFetcher {
+    async fn run(&self, context: &RunContext) -> Result<()> {
+        if self.config.api_key.is_empty() {
+            bail!("A New York Times API key is required");
+        }
+        let client = self.config.client()?;
+
+        let mut books = HashMap::new();
+        let mut ranks = vec![];
+        for list in &self.config.lists {
+            let (list_books, mut list_ranks) =
+                fetch_list(&client, &self.config, list).await?.flatten();
+            // A book is often listed on several lists
+            books.extend(
+                list_books
+                    .into_iter()
+                    .map(|book| (book.isbn13.clone(), book)),
+            );
+            ranks.append(&mut list_ranks);
+        }
+        // Ranks are unique per list, but a book can be listed twice under one ISBN
+        ranks.sort_by(|a, b| (&a.list_name, &a.isbn13).cmp(&(&b.list_name, &b.isbn13)));
+        ranks.dedup_by(|a, b| a.list_name == b.list_name && a.isbn13 == b.isbn13);
+
+        let mut tx = self.pool.begin().await?;
+        let mut deltas = RunDeltas::new("bookfetcher");
+        deltas
+            .track(&mut tx, "books", books.len(), async |conn| {
+                BookInserter::from(books.into_values().collect())
+                    .build()
+                    .execute(conn)
+                    .await
+            })
+            .await?;
+        deltas
+            .track(&mut tx, "bestsellers", ranks.len(), async |conn| {
+                BestsellerInserter::from(ranks).build().execute(conn).await
+            })
+            .await?;
+        info!("Requests of the fetcher for books: {}", client.stats());
+        deltas
+            .finish(
+                tx,
+                &self.pool,
+                context,
+                self.config.max_volume_deviation,
+                self.config.notify.as_ref(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use std::env;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use tracing::info;
+
+use super::{RunContext, Runnable, movies::PATHE_TIMEZONE};
+
+static GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+static GOOGLE_CALENDAR_API: &str = "https://www.googleapis.com/calendar/v3";
+
+/// Marks a showtime as planned, such that it is synchronized to the calendar
+pub async fn plan_screening(
+    pool: &PgPool,
+    show_slug: &str,
+    cinema_slug: &str,
+    time: DateTime<FixedOffset>,
+    auditorium_name: &str,
+) -> Result<i64> {
+    let id = sqlx::query_scalar(
+        r#"INSERT INTO planned_screenings(show_slug, cinema_slug, time, auditorium_name)
+        SELECT show_slug, cinema_slug, time, auditorium_name FROM showtimes
+        WHERE show_slug = $1 AND cinema_slug = $2 AND time = $3 AND auditorium_name = $4
+        ON CONFLICT (show_slug, cinema_slug, time, auditorium_name)
+            DO UPDATE SET cancelled = FALSE
+        RETURNING id"#,
+    )
+    .bind(show_slug)
+    .bind(cinema_slug)
+    .bind(time)
+    .bind(auditorium_name)
+    .fetch_optional(pool)
+    .await?;
+    match id {
+        Some(id) => Ok(id),
+        None => bail!("No showtime of {show_slug} at {cinema_slug} on {time} in {auditorium_name}"),
+    }
+}
+
+/// Unmarks a planned screening, its calendar event is removed on the next sync
+pub async fn unplan_screening(pool: &PgPool, id: i64) -> Result<()> {
+    sqlx::query("UPDATE planned_screenings SET cancelled = TRUE WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Configuration of the calendar synchronization. The OAuth client and refresh token
+/// default to the `GOOGLE_CLIENT_ID`, `GOOGLE_CLIENT_SECRET` and
+/// `GOOGLE_REFRESH_TOKEN` environment variables.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    calendar_id: String,
+    client_id: String,
+    client_secret: String,
+    refresh_token: String,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            calendar_id: "primary".to_string(),
+            client_id: env::var("GOOGLE_CLIENT_ID").unwrap_or_default(),
+            client_secret: env::var("GOOGLE_CLIENT_SECRET").unwrap_or_default(),
+            refresh_token: env::var("GOOGLE_REFRESH_TOKEN").unwrap_or_default(),
+        }
+    }
+}
+
+impl CalendarConfig {
+    pub fn with_calendar(mut self, calendar_id: impl Into<String>) -> Self {
+        self.calendar_id = calendar_id.into();
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessToken {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedEvent {
+    id: String,
+}
+
+#[derive(Debug, FromRow)]
+struct PlannedScreening {
+    id: i64,
+    show_slug: String,
+    cinema_slug: String,
+    time: DateTime<Utc>,
+    auditorium_name: String,
+    cancelled: bool,
+    calendar_event_id: Option<String>,
+    calendar_hash: Option<String>,
+    showtime_exists: bool,
+}
+
+#[derive(Debug, FromRow)]
+struct EventDetails {
+    title: String,
+    cinema_name: String,
+    city_name: String,
+    end_time: Option<DateTime<Utc>>,
+    reservation_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct EventTime {
+    #[serde(rename = "dateTime")]
+    date_time: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Event {
+    summary: String,
+    location: String,
+    description: String,
+    start: EventTime,
+    end: EventTime,
+}
+
+impl Event {
+    /// Hash of the event as sent to the calendar, which is stored to skip unchanged
+    /// events and thus has to be stable across builds
+    fn hash(&self) -> Result<String> {
+        Ok(hex::encode(Sha256::digest(serde_json::to_vec(self)?)))
+    }
+}
+
+struct Calendar {
+    client: reqwest::Client,
+    token: String,
+    events_url: String,
+}
+
+impl Calendar {
+    async fn connect(config: &CalendarConfig) -> Result<Self> {
+        if config.client_id.is_empty() || config.refresh_token.is_empty() {
+            bail!("Google client id and refresh token are required");
+        }
+        let client = reqwest::Client::new();
+        let token: AccessToken = client
+            .post(GOOGLE_TOKEN_URL)
+            .form(&[
+                ("client_id", config.client_id.as_str()),
+                ("client_secret", config.client_secret.as_str()),
+                ("refresh_token", config.refresh_token.as_str()),
+                ("grant_type", "refresh_token"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Calendar {
+            client,
+            token: token.access_token,
+            events_url: format!(
+                "{GOOGLE_CALENDAR_API}/calendars/{}/events",
+                config.calendar_id
+            ),
+        })
+    }
+
+    async fn upsert(&self, event_id: Option<&str>, event: &Event) -> Result<String> {
+        let request = match event_id {
+            Some(id) => self.client.put(format!("{}/{id}", self.events_url)),
+            None => self.client.post(&self.events_url),
+        };
+        let created: CreatedEvent = request
+            .bearer_auth(&self.token)
+            .json(event)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(created.id)
+    }
+
+    async fn delete(&self, event_id: &str) -> Result<()> {
+        let response = self
+            .client
+            .delete(format!("{}/{event_id}", self.events_url))
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        // Already removed from the calendar is fine as well
+        if response.status() != reqwest::StatusCode::GONE {
+            response.error_for_status()?;
+        }
+        Ok(())
+    }
+}
+
+/// Synchronizes planned screenings to a Google Calendar.
+///
+/// When a planned showtime disappears upstream, the planned screening follows a
+/// showtime of the same show, cinema and auditorium on the same day (a time shift)
+/// or is cancelled, removing its calendar event.
+#[derive(Debug)]
+pub struct CalendarSync {
+    pub pool: PgPool,
+    pub config: CalendarConfig,
+}
+
+impl CalendarSync {
+    /// Moves planned screenings of which the showtime was shifted, cancelling those
+    /// for which no replacement can be found
+    async fn follow_time_shifts(&self) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE planned_screenings p SET time = (
+                SELECT st.time FROM showtimes st
+                WHERE st.show_slug = p.show_slug
+                    AND st.cinema_slug = p.cinema_slug
+                    AND st.auditorium_name = p.auditorium_name
+                    AND (st.time AT TIME ZONE $1)::date = (p.time AT TIME ZONE $1)::date
+                ORDER BY abs(extract(epoch FROM st.time - p.time))
+                LIMIT 1
+            )
+            WHERE NOT p.cancelled AND NOT EXISTS (
+                SELECT 1 FROM showtimes st
+                WHERE st.show_slug = p.show_slug
+                    AND st.cinema_slug = p.cinema_slug
+                    AND st.time = p.time
+                    AND st.auditorium_name = p.auditorium_name
+            ) AND EXISTS (
+                SELECT 1 FROM showtimes st
+                WHERE st.show_slug = p.show_slug
+                    AND st.cinema_slug = p.cinema_slug
+                    AND st.auditorium_name = p.auditorium_name
+                    AND (st.time AT TIME ZONE $1)::date = (p.time AT TIME ZONE $1)::date
+            )"#,
+        )
+        .bind(PATHE_TIMEZONE.name())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn event_for(&self, screening: &PlannedScreening) -> Result<Event> {
+        let details: EventDetails = sqlx::query_as(
+            r#"SELECT s.title, c.name AS cinema_name, ci.name AS city_name,
+                st.end_time, st.reservation_url
+            FROM showtimes st
+            JOIN shows s ON s.slug = st.show_slug
+            JOIN cinemas c ON c.slug = st.cinema_slug
+            JOIN cities ci ON ci.slug = c.city_slug
+            WHERE st.show_slug = $1 AND st.cinema_slug = $2
+                AND st.time = $3 AND st.auditorium_name = $4"#,
+        )
+        .bind(&screening.show_slug)
+        .bind(&screening.cinema_slug)
+        .bind(screening.time)
+        .bind(&screening.auditorium_name)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Event {
+            summary: details.title,
+            location: format!("{}, {}", details.cinema_name, details.city_name),
+            description: format!(
+                "{}\n{}",
+                screening.auditorium_name,
+                details.reservation_url.unwrap_or_default()
+            ),
+            start: EventTime {
+                date_time: screening.time.to_rfc3339(),
+            },
+            end: EventTime {
+                date_time: details.end_time.unwrap_or(screening.time).to_rfc3339(),
+            },
+        })
+    }
+}
+
+impl Runnable for CalendarSync {
+    async fn run(&self, _context: &RunContext) -> Result<()> {
+        self.follow_time_shifts().await?;
+        let calendar = Calendar::connect(&self.config).await?;
+
+        let screenings: Vec<PlannedScreening> = sqlx::query_as(
+            r#"SELECT p.id, p.show_slug, p.cinema_slug, p.time, p.auditorium_name, p.cancelled,
+                p.calendar_event_id, p.calendar_hash,
+                EXISTS (
+                    SELECT 1 FROM showtimes st
+                    WHERE st.show_slug = p.show_slug AND st.cinema_slug = p.cinema_slug
+                        AND st.time = p.time AND st.auditorium_name = p.auditorium_name
+                ) AS showtime_exists
+            FROM planned_screenings p
+            WHERE NOT p.cancelled OR p.calendar_event_id IS NOT NULL"#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut synced = 0;
+        for screening in screenings {
+            if screening.cancelled || !screening.showtime_exists {
+                if let Some(event_id) = &screening.calendar_event_id {
+                    calendar.delete(event_id).await?;
+                }
+                sqlx::query(
+                    r#"UPDATE planned_screenings
+                    SET cancelled = TRUE, calendar_event_id = NULL, calendar_hash = NULL
+                    WHERE id = $1"#,
+                )
+                .bind(screening.id)
+                .execute(&self.pool)
+                .await?;
+                continue;
+            }
+
+            let event = self.event_for(&screening).await?;
+            let hash = event.hash()?;
+            if screening.calendar_hash.as_deref() == Some(hash.as_str()) {
+                continue;
+            }
+            let event_id = calendar
+                .upsert(screening.calendar_event_id.as_deref(), &event)
+                .await?;
+            sqlx::query(
+                "UPDATE planned_screenings SET calendar_event_id = $2, calendar_hash = $3 WHERE id = $1",
+            )
+            .bind(screening.id)
+            .bind(event_id)
+            .bind(hash)
+            .execute(&self.pool)
+            .await?;
+            synced += 1;
+        }
+
+        sqlx::query("INSERT INTO joblogs(jobname) VALUES ('calendarsync')")
+            .execute(&self.pool)
+            .await?;
+        info!("Synchronized {synced} planned screenings to the calendar");
+        Ok(())
+    }
+}
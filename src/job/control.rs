@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+};
+use tracing::{info, warn};
+
+use super::{JobStatus, JobStatuses};
+
+/// Last line of a successful answer, failed commands are answered with a line
+/// starting with `error:`
+pub const OK: &str = "ok";
+
+/// Applies a single command to the statuses, returning the lines of the answer
+fn apply(statuses: &JobStatuses, command: &str) -> Result<Vec<String>> {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let update = |job: &str, update: fn(&mut JobStatus)| {
+        let mut statuses = statuses.write().unwrap_or_else(|e| e.into_inner());
+        match statuses.get_mut(job) {
+            Some(status) => {
+                update(status);
+                Ok(vec![])
+            }
+            None => bail!("Unknown job {job}"),
+        }
+    };
+    match words.as_slice() {
+        ["pause", job] => update(job, |status| status.paused = true),
+        ["resume", job] => update(job, |status| status.paused = false),
+        ["trigger", job] | ["trigger", job, "now"] => update(job, |status| status.triggered = true),
+        ["status"] => {
+            let statuses = statuses.read().unwrap_or_else(|e| e.into_inner());
+            let mut names: Vec<&String> = statuses.keys().collect();
+            names.sort();
+            Ok(names
+                .into_iter()
+                .map(|name| describe(name, &statuses[name]))
+                .collect())
+        }
+        _ => bail!("Unknown command, expected pause <job>, resume <job>, trigger <job> or status"),
+    }
+}
+
+fn describe(name: &str, status: &JobStatus) -> String {
+    let state = match (status.running_since, status.paused, status.triggered) {
+        (Some(_), _, _) => "running",
+        (None, true, _) => "paused",
+        (None, false, true) => "triggered",
+        (None, false, false) => "idle",
+    };
+    let next_run = status
+        .next_run
+        .map(|time| time.with_timezone(&Local).to_string());
+    format!(
+        "{name} {state} runs={} failures={} next_run={}",
+        status.runs,
+        status.consecutive_failures,
+        next_run.as_deref().unwrap_or("unknown")
+    )
+}
+
+async fn handle(stream: UnixStream, statuses: JobStatuses) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(command) = lines.next_line().await? {
+        if command.trim().is_empty() {
+            continue;
+        }
+        let answer = match apply(&statuses, &command) {
+            Ok(mut lines) => {
+                info!("Control command: {command}");
+                lines.push(OK.to_string());
+                lines
+            }
+            Err(err) => vec![format!("error: {err}")],
+        };
+        writer
+            .write_all((answer.join("\n") + "\n").as_bytes())
+            .await?;
+    }
+    Ok(())
+}
+
+/// Listens on a unix socket for commands which pause, resume or trigger jobs of the
+/// running daemon, or list their status. Every line is a command, e.g. `pause movies`
+/// or `status`, which takes effect on the next poll.
+pub async fn serve_control(path: impl Into<PathBuf>, statuses: JobStatuses) -> Result<()> {
+    let path = path.into();
+    // A socket left behind by a previous run would make binding fail
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("Could not listen on {}", path.display()))?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let statuses = statuses.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle(stream, statuses).await {
+                warn!("Control connection failed: {err:#}");
+            }
+        });
+    }
+}
+
+/// Sends a single command to the control socket of a running daemon, returning the
+/// lines of the answer
+pub async fn send_command(path: &Path, command: &str) -> Result<Vec<String>> {
+    let stream = UnixStream::connect(path)
+        .await
+        .with_context(|| format!("Could not connect to {}", path.display()))?;
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(format!("{command}\n").as_bytes()).await?;
+    let mut lines = BufReader::new(reader).lines();
+    let mut answer = vec![];
+    while let Some(line) = lines.next_line().await? {
+        let done = line == OK || line.starts_with("error:");
+        answer.push(line);
+        if done {
+            break;
+        }
+    }
+    Ok(answer)
+}
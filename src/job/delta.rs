@@ -0,0 +1,150 @@
+use std::fmt::Display;
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction, postgres::PgQueryResult};
+use tracing::{info, warn};
+
+use super::{
+    RunContext,
+    anomaly::{VolumeAnomaly, check_deltas},
+    notify::NotifyConfig,
+};
+
+/// Changes to a single table caused by a batch insert
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct TableDelta {
+    pub table: &'static str,
+    pub submitted: u64,
+    pub inserted: u64,
+    pub updated: u64,
+    pub unchanged: u64,
+}
+
+/// Row changes per table for a single run of a job, stored in the `run_deltas` table.
+///
+/// A run which suddenly adds nothing is the clearest signal that parsing silently
+/// broke upstream.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunDeltas {
+    pub jobname: &'static str,
+    pub tables: Vec<TableDelta>,
+}
+
+impl RunDeltas {
+    pub fn new(jobname: &'static str) -> Self {
+        RunDeltas {
+            jobname,
+            tables: vec![],
+        }
+    }
+
+    /// Executes the batch insert of `submitted` rows into `table`, keeping track of
+    /// the amount of inserted, updated and unchanged rows.
+    ///
+    /// Upserts do not tell inserts and updates apart, so the table is counted before
+    /// and after the insert: growth are inserts, the remaining affected rows updates.
+    /// The insert runs on `conn`, such that it can be part of a transaction.
+    pub async fn track<F>(
+        &mut self,
+        conn: &mut PgConnection,
+        table: &'static str,
+        submitted: usize,
+        insert: F,
+    ) -> Result<()>
+    where
+        F: AsyncFnOnce(&mut PgConnection) -> Result<PgQueryResult, sqlx::Error>,
+    {
+        let count = format!("SELECT count(*) FROM {table}");
+        let before: i64 = sqlx::query_scalar(&count).fetch_one(&mut *conn).await?;
+        let affected = insert(&mut *conn).await?.rows_affected();
+        let after: i64 = sqlx::query_scalar(&count).fetch_one(&mut *conn).await?;
+
+        let submitted = submitted as u64;
+        let inserted = (after - before).max(0) as u64;
+        let updated = affected.saturating_sub(inserted);
+        self.tables.push(TableDelta {
+            table,
+            submitted,
+            inserted,
+            updated,
+            unchanged: submitted.saturating_sub(inserted + updated),
+        });
+        Ok(())
+    }
+
+    /// Ends the run of which the inserts were tracked in `tx`: logs it in `joblogs` and
+    /// commits, or rolls back during a dry run. With a `max_deviation` the volumes are
+    /// compared against the previous runs, and the anomalies alerted on `notify`.
+    pub async fn finish(
+        &self,
+        mut tx: Transaction<'_, Postgres>,
+        pool: &PgPool,
+        context: &RunContext,
+        max_deviation: Option<f64>,
+        notify: Option<&NotifyConfig>,
+    ) -> Result<Vec<VolumeAnomaly>> {
+        sqlx::query("INSERT INTO joblogs(jobname) VALUES ($1)")
+            .bind(self.jobname)
+            .execute(&mut *tx)
+            .await?;
+        if context.dry_run {
+            tx.rollback().await?;
+            info!("Dry run of {}, would have written: {self}", self.jobname);
+            return Ok(vec![]);
+        }
+        tx.commit().await?;
+
+        // The run is compared against the previous runs before it becomes one of them
+        let anomalies = match max_deviation {
+            Some(max_deviation) => {
+                check_deltas(pool, self.jobname, &self.tables, max_deviation, context).await?
+            }
+            None => vec![],
+        };
+        self.store(pool).await?;
+        if anomalies.is_empty() {
+            info!("Ran {}: {self}", self.jobname);
+        } else {
+            warn!("Ran {}, flagged as anomalous: {self}", self.jobname);
+        }
+        if let Some(notify) = notify {
+            notify.alert_anomalies(self.jobname, &anomalies).await;
+        }
+        Ok(anomalies)
+    }
+
+    pub async fn store(&self, pool: &PgPool) -> Result<()> {
+        for delta in &self.tables {
+            sqlx::query(
+                r#"INSERT INTO run_deltas(jobname, table_name, submitted, inserted, updated, unchanged)
+                VALUES ($1, $2, $3, $4, $5, $6)"#,
+            )
+            .bind(self.jobname)
+            .bind(delta.table)
+            .bind(delta.submitted as i64)
+            .bind(delta.inserted as i64)
+            .bind(delta.updated as i64)
+            .bind(delta.unchanged as i64)
+            .execute(pool)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RunDeltas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tables = self
+            .tables
+            .iter()
+            .map(|delta| {
+                format!(
+                    "{} +{} ~{} ={}",
+                    delta.table, delta.inserted, delta.updated, delta.unchanged
+                )
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", tables.join(", "))
+    }
+}
@@ -0,0 +1,438 @@
+//! Concerts and other events at Dutch venues from the Ticketmaster Discovery API.
+
+use std::{collections::HashMap, env, num::NonZeroU32};
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Days, Local, NaiveDate, SecondsFormat, Utc};
+use reqwest::Url;
+use serde::Deserialize;
+use sqlx::PgPool;
+use sqlx_batch::BatchInserter;
+use tracing::{info, warn};
+
+use super::{
+    RunContext, Runnable,
+    delta::RunDeltas,
+    notify::NotifyConfig,
+    util::{Client, Clients},
+};
+
+static DISCOVERY_EVENTS_URL: &str = "https://app.ticketmaster.com/discovery/v2/events.json";
+
+/// Events per page, the maximum the Discovery API allows
+const PAGE_SIZE: usize = 200;
+
+/// Name of the shared client used for the requests to Ticketmaster, see
+/// `Jobs::with_client`
+pub const TICKETMASTER_CLIENT: &str = "ticketmaster";
+
+/// The Discovery API does not page beyond the 1000th event of a search
+const MAX_SEARCH_RESULTS: usize = 1000;
+
+/// Configuration of the events fetcher. The API key defaults to the
+/// `TICKETMASTER_API_KEY` environment variable.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    api_key: String,
+    /// ISO code of the country of which the venues are fetched
+    country_code: String,
+    /// Amount of days ahead of which events are fetched
+    horizon_days: u64,
+    requests_per_second: Option<NonZeroU32>,
+    /// Alert when the amount of rows written to a table deviates by more than this
+    /// percentage from the previous runs, `None` does not compare
+    max_volume_deviation: Option<f64>,
+    /// Where the anomalous runs are alerted
+    notify: Option<NotifyConfig>,
+    /// Clients shared with other jobs, handed over by `Jobs`
+    #[serde(skip)]
+    clients: Clients,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        EventsConfig {
+            api_key: env::var("TICKETMASTER_API_KEY").unwrap_or_default(),
+            country_code: "NL".to_string(),
+            horizon_days: 90,
+            requests_per_second: None,
+            max_volume_deviation: None,
+            notify: None,
+            clients: Clients::default(),
+        }
+    }
+}
+
+impl EventsConfig {
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    pub fn with_country(mut self, country_code: impl Into<String>) -> Self {
+        self.country_code = country_code.into();
+        self
+    }
+
+    pub fn with_horizon(mut self, days: u64) -> Self {
+        self.horizon_days = days;
+        self
+    }
+
+    /// Flag the run as anomalous when the amount of venues, events or ticket links
+    /// deviates by more than `percentage` from the previous runs
+    pub fn with_max_volume_deviation(mut self, percentage: f64) -> Self {
+        self.max_volume_deviation = Some(percentage);
+        self
+    }
+
+    pub fn with_notify(mut self, notify: NotifyConfig) -> Self {
+        self.notify = Some(notify);
+        self
+    }
+
+    /// Use the client shared under `TICKETMASTER_CLIENT` when there is, which keeps its
+    /// own rate limit
+    pub fn with_clients(mut self, clients: Clients) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    /// Ticketmaster allows 5 requests per second on its free tier
+    fn client(&self) -> Result<Client> {
+        if let Some(client) = self.clients.get(TICKETMASTER_CLIENT) {
+            return Ok(client);
+        }
+        Ok(Client::new()
+            .with_limit(self.requests_per_second.unwrap_or(5.try_into()?))
+            .with_max_retries(3))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsPage {
+    #[serde(rename = "_embedded", default)]
+    embedded: EmbeddedEvents,
+    page: PageInfo,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EmbeddedEvents {
+    #[serde(default)]
+    events: Vec<TmEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    total_elements: usize,
+    total_pages: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TmEvent {
+    id: String,
+    name: String,
+    url: Option<String>,
+    dates: EventDates,
+    #[serde(default)]
+    classifications: Vec<Classification>,
+    #[serde(default)]
+    price_ranges: Vec<PriceRange>,
+    #[serde(default)]
+    outlets: Vec<Outlet>,
+    #[serde(rename = "_embedded", default)]
+    embedded: EmbeddedVenues,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventDates {
+    start: EventStart,
+    status: Option<EventStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventStart {
+    local_date: Option<NaiveDate>,
+    date_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventStatus {
+    /// e.g. "onsale", "cancelled" or "postponed"
+    code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Classification {
+    segment: Option<NamedEntity>,
+    genre: Option<NamedEntity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedEntity {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PriceRange {
+    min: Option<f64>,
+    max: Option<f64>,
+    currency: Option<String>,
+}
+
+/// Other places tickets are sold, such as the box office of the venue
+#[derive(Debug, Deserialize)]
+struct Outlet {
+    url: String,
+    #[serde(rename = "type")]
+    outlet_type: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EmbeddedVenues {
+    #[serde(default)]
+    venues: Vec<TmVenue>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TmVenue {
+    id: String,
+    name: Option<String>,
+    city: Option<NamedEntity>,
+    address: Option<Address>,
+    postal_code: Option<String>,
+    location: Option<Location>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Address {
+    line1: Option<String>,
+}
+
+/// Coordinates, which the API serializes as strings
+#[derive(Debug, Deserialize)]
+struct Location {
+    latitude: String,
+    longitude: String,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "venues"]
+struct Venue {
+    #[key]
+    id: String,
+    name: String,
+    city: Option<String>,
+    address: Option<String>,
+    postal_code: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "events"]
+struct Event {
+    #[key]
+    id: String,
+    name: String,
+    venue_id: Option<String>,
+    /// `None` for events of which only the day is known
+    starts_at: Option<DateTime<Utc>>,
+    local_date: Option<NaiveDate>,
+    status: Option<String>,
+    segment: Option<String>,
+    genre: Option<String>,
+    price_min: Option<f64>,
+    price_max: Option<f64>,
+    currency: Option<String>,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "event_ticket_links"]
+struct TicketLink {
+    #[key]
+    event_id: String,
+    #[key]
+    url: String,
+    link_type: String,
+}
+
+impl From<TmVenue> for Venue {
+    fn from(venue: TmVenue) -> Self {
+        let coordinate = |value: &str| value.parse().ok();
+        Venue {
+            name: venue.name.unwrap_or_else(|| venue.id.clone()),
+            id: venue.id,
+            city: venue.city.map(|city| city.name),
+            address: venue.address.and_then(|address| address.line1),
+            postal_code: venue.postal_code,
+            latitude: venue
+                .location
+                .as_ref()
+                .and_then(|l| coordinate(&l.latitude)),
+            longitude: venue
+                .location
+                .as_ref()
+                .and_then(|l| coordinate(&l.longitude)),
+        }
+    }
+}
+
+impl TmEvent {
+    /// Splits the event in its row, its venues and its ticket links. Events list their
+    /// venues, of which the first is where the event takes place.
+    fn flatten(self) -> (Event, Vec<Venue>, Vec<TicketLink>) {
+        let classification = self.classifications.into_iter().next();
+        let prices = self.price_ranges.into_iter().next();
+        let venues: Vec<Venue> = self.embedded.venues.into_iter().map(Venue::from).collect();
+        let links = self
+            .url
+            .map(|url| (url, "ticketmaster".to_string()))
+            .into_iter()
+            .chain(self.outlets.into_iter().map(|outlet| {
+                let link_type = outlet.outlet_type.unwrap_or_else(|| "outlet".to_string());
+                (outlet.url, link_type)
+            }))
+            .map(|(url, link_type)| TicketLink {
+                event_id: self.id.clone(),
+                url,
+                link_type,
+            })
+            .collect();
+        let event = Event {
+            id: self.id,
+            name: self.name,
+            venue_id: venues.first().map(|venue| venue.id.clone()),
+            starts_at: self.dates.start.date_time,
+            local_date: self.dates.start.local_date,
+            status: self.dates.status.and_then(|status| status.code),
+            segment: classification
+                .as_ref()
+                .and_then(|c| c.segment.as_ref())
+                .map(|segment| segment.name.clone()),
+            genre: classification.and_then(|c| c.genre).map(|genre| genre.name),
+            price_min: prices.as_ref().and_then(|prices| prices.min),
+            price_max: prices.as_ref().and_then(|prices| prices.max),
+            currency: prices.and_then(|prices| prices.currency),
+        };
+        (event, venues, links)
+    }
+}
+
+async fn fetch_events_page(
+    client: &Client,
+    config: &EventsConfig,
+    until: DateTime<Utc>,
+    page: usize,
+) -> Result<EventsPage> {
+    let url = Url::parse_with_params(
+        DISCOVERY_EVENTS_URL,
+        [
+            ("apikey", config.api_key.clone()),
+            ("countryCode", config.country_code.clone()),
+            ("locale", "*".to_string()),
+            ("sort", "date,asc".to_string()),
+            ("size", PAGE_SIZE.to_string()),
+            ("page", page.to_string()),
+            (
+                "endDateTime",
+                until.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ),
+        ],
+    )?;
+    Ok(client.get_json(url).await?)
+}
+
+/// Fetches the upcoming events from the Ticketmaster Discovery API and stores them
+/// together with their venues and ticket links.
+#[derive(Debug)]
+pub struct EventFetcher {
+    pub pool: PgPool,
+    pub config: EventsConfig,
+}
+impl Runnable for EventFetcher {
+    async fn run(&self, context: &RunContext) -> Result<()> {
+        if self.config.api_key.is_empty() {
+            bail!("A Ticketmaster API key is required");
+        }
+        let client = self.config.client()?;
+        let until = Local::now()
+            .date_naive()
+            .checked_add_days(Days::new(self.config.horizon_days))
+            .and_then(|day| day.and_hms_opt(23, 59, 59))
+            .map(|time| time.and_utc())
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        let mut events = vec![];
+        let mut venues = HashMap::new();
+        let mut links = vec![];
+        let mut page = 0;
+        loop {
+            let response = fetch_events_page(&client, &self.config, until, page).await?;
+            for event in response.embedded.events {
+                let (event, event_venues, mut event_links) = event.flatten();
+                events.push(event);
+                venues.extend(
+                    event_venues
+                        .into_iter()
+                        .map(|venue| (venue.id.clone(), venue)),
+                );
+                links.append(&mut event_links);
+            }
+            page += 1;
+            if page >= response.page.total_pages {
+                break;
+            }
+            if (page + 1) * PAGE_SIZE > MAX_SEARCH_RESULTS {
+                warn!(
+                    "Only stored the first {} of {} events, shorten the horizon to get all",
+                    page * PAGE_SIZE,
+                    response.page.total_elements
+                );
+                break;
+            }
+        }
+        // An event can be listed twice when it moved between pages during the fetch
+        events.sort_by(|a, b| a.id.cmp(&b.id));
+        events.dedup_by(|a, b| a.id == b.id);
+        links.sort_by(|a, b| (&a.event_id, &a.url).cmp(&(&b.event_id, &b.url)));
+        links.dedup_by(|a, b| a.event_id == b.event_id && a.url == b.url);
+
+        let mut tx = self.pool.begin().await?;
+        let mut deltas = RunDeltas::new("eventfetcher");
+        deltas
+            .track(&mut tx, "venues", venues.len(), async |conn| {
+                VenueInserter::from(venues.into_values().collect())
+                    .build()
+                    .execute(conn)
+                    .await
+            })
+            .await?;
+        deltas
+            .track(&mut tx, "events", events.len(), async |conn| {
+                EventInserter::from(events).build().execute(conn).await
+            })
+            .await?;
+        deltas
+            .track(&mut tx, "event_ticket_links", links.len(), async |conn| {
+                TicketLinkInserter::from(links).build().execute(conn).await
+            })
+            .await?;
+        info!("Requests of the fetcher for events: {}", client.stats());
+        deltas
+            .finish(
+                tx,
+                &self.pool,
+                context,
+                self.config.max_volume_deviation,
+                self.config.notify.as_ref(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use reqwest::Url;
+use serde::{Serialize, de::DeserializeOwned};
+use sqlx::PgPool;
+
+use crate::job::{RunContext, util::redacted};
+
+/// URL under which a failed fetch is stored, without the secrets in its query
+fn stored_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => redacted(&url).to_string(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Fetches of a job which failed after exhausting their retries, stored in the
+/// `failed_fetches` table together with the context needed to retry them. The next
+/// run of the job retries those first, and resolves them once they succeed.
+#[derive(Debug, Clone)]
+pub struct FailedFetches {
+    pool: PgPool,
+    jobname: String,
+    /// Failures are neither recorded nor resolved during a dry run
+    dry_run: bool,
+}
+
+impl FailedFetches {
+    pub fn new(pool: PgPool, jobname: impl Into<String>, context: &RunContext) -> Self {
+        FailedFetches {
+            pool,
+            jobname: jobname.into(),
+            dry_run: context.dry_run,
+        }
+    }
+
+    /// Context of the unresolved failed fetches, oldest failure first
+    pub async fn pending<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        let contexts: Vec<serde_json::Value> = sqlx::query_scalar(
+            "SELECT context FROM failed_fetches WHERE jobname = $1 ORDER BY first_failed_at",
+        )
+        .bind(&self.jobname)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(contexts
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Records a failed fetch, or bumps its attempts when it failed before
+    pub async fn record<T: Serialize>(
+        &self,
+        url: &str,
+        context: &T,
+        error: &anyhow::Error,
+    ) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+        sqlx::query(
+            r#"INSERT INTO failed_fetches(jobname, url, context, error)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (jobname, url) DO UPDATE SET
+                context = excluded.context,
+                error = excluded.error,
+                attempts = failed_fetches.attempts + 1,
+                last_failed_at = current_timestamp"#,
+        )
+        .bind(&self.jobname)
+        .bind(stored_url(url))
+        .bind(serde_json::to_value(context)?)
+        .bind(format!("{error:#}"))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes the given urls, as they have been fetched successfully
+    pub async fn resolve(&self, urls: &[String]) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+        sqlx::query("DELETE FROM failed_fetches WHERE jobname = $1 AND url = ANY($2)")
+            .bind(&self.jobname)
+            .bind(urls.iter().map(|url| stored_url(url)).collect::<Vec<_>>())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,523 @@
+//! Products and their prices at Dutch supermarkets, from the APIs behind the apps of
+//! Albert Heijn and Jumbo. Every run stores the prices it found, such that the price
+//! history of a product can be charted.
+
+use std::{collections::HashMap, num::NonZeroU32};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::{
+    Url,
+    header::{AUTHORIZATION, HeaderName, HeaderValue},
+};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use sqlx_batch::BatchInserter;
+use tracing::{debug, info};
+
+use super::{
+    RunContext, Runnable,
+    delta::RunDeltas,
+    notify::NotifyConfig,
+    util::{Client, Clients},
+};
+
+static AH_TOKEN_URL: &str = "https://api.ah.nl/mobile-auth/v1/auth/token/anonymous";
+static AH_SEARCH_URL: &str = "https://api.ah.nl/mobile-services/product/search/v2";
+static JUMBO_SEARCH_URL: &str = "https://mobileapi.jumbo.com/v17/search";
+
+/// Products per page of search results
+const PAGE_SIZE: usize = 25;
+
+/// Name of the shared client used for the requests to Albert Heijn, see
+/// `Jobs::with_client`
+pub const AH_CLIENT: &str = "albertheijn";
+
+/// Name of the shared client used for the requests to Jumbo
+pub const JUMBO_CLIENT: &str = "jumbo";
+
+/// Supermarket of which the products are fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Store {
+    AlbertHeijn,
+    Jumbo,
+}
+
+impl Store {
+    /// Name of the store in the `store` columns
+    fn name(self) -> &'static str {
+        match self {
+            Store::AlbertHeijn => "ah",
+            Store::Jumbo => "jumbo",
+        }
+    }
+
+    fn client_name(self) -> &'static str {
+        match self {
+            Store::AlbertHeijn => AH_CLIENT,
+            Store::Jumbo => JUMBO_CLIENT,
+        }
+    }
+}
+
+/// Configuration of the groceries fetcher. Neither store lists all of its products,
+/// so the products found by searching for each of the queries are tracked.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GroceriesConfig {
+    stores: Vec<Store>,
+    /// Search terms of which the products are tracked, such as `melk`
+    queries: Vec<String>,
+    /// Pages of results fetched per query and store
+    max_pages: usize,
+    requests_per_second: Option<NonZeroU32>,
+    /// Alert when the amount of rows written to a table deviates by more than this
+    /// percentage from the previous runs, `None` does not compare
+    max_volume_deviation: Option<f64>,
+    /// Where the anomalous runs are alerted
+    notify: Option<NotifyConfig>,
+    /// Clients shared with other jobs, handed over by `Jobs`
+    #[serde(skip)]
+    clients: Clients,
+}
+
+impl Default for GroceriesConfig {
+    fn default() -> Self {
+        GroceriesConfig {
+            stores: vec![Store::AlbertHeijn, Store::Jumbo],
+            queries: ["melk", "brood", "eieren", "kaas", "koffie", "bananen"]
+                .map(String::from)
+                .to_vec(),
+            max_pages: 2,
+            requests_per_second: None,
+            max_volume_deviation: None,
+            notify: None,
+            clients: Clients::default(),
+        }
+    }
+}
+
+impl GroceriesConfig {
+    pub fn with_stores(mut self, stores: impl IntoIterator<Item = Store>) -> Self {
+        self.stores = stores.into_iter().collect();
+        self
+    }
+
+    /// Track the products found by searching for `queries`
+    pub fn with_queries(mut self, queries: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.queries = queries.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_max_pages(mut self, pages: usize) -> Self {
+        self.max_pages = pages;
+        self
+    }
+
+    /// Flag the run as anomalous when the amount of products or prices deviates by
+    /// more than `percentage` from the previous runs
+    pub fn with_max_volume_deviation(mut self, percentage: f64) -> Self {
+        self.max_volume_deviation = Some(percentage);
+        self
+    }
+
+    pub fn with_notify(mut self, notify: NotifyConfig) -> Self {
+        self.notify = Some(notify);
+        self
+    }
+
+    /// Use the clients shared under `AH_CLIENT` and `JUMBO_CLIENT` when there are,
+    /// which keep their own rate limits
+    pub fn with_clients(mut self, clients: Clients) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    fn client(&self, store: Store) -> Result<Client> {
+        if let Some(client) = self.clients.get(store.client_name()) {
+            return Ok(client);
+        }
+        Ok(Client::new()
+            .with_limit(self.requests_per_second.unwrap_or(2.try_into()?))
+            .with_max_retries(3))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AhToken {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AhSearch {
+    #[serde(default)]
+    products: Vec<AhProduct>,
+    page: AhPage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AhPage {
+    total_pages: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AhProduct {
+    webshop_id: i64,
+    title: String,
+    brand: Option<String>,
+    sales_unit_size: Option<String>,
+    main_category: Option<String>,
+    sub_category: Option<String>,
+    /// Price in euros, the bonus price when the product is on bonus
+    current_price: Option<f64>,
+    price_before_bonus: Option<f64>,
+    /// e.g. "2e halve prijs"
+    bonus_mechanism: Option<String>,
+    #[serde(default)]
+    images: Vec<AhImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AhImage {
+    url: String,
+    width: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JumboSearch {
+    products: JumboProducts,
+}
+
+#[derive(Debug, Deserialize)]
+struct JumboProducts {
+    #[serde(default)]
+    data: Vec<JumboProduct>,
+    total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JumboProduct {
+    id: String,
+    title: String,
+    quantity: Option<String>,
+    prices: JumboPrices,
+    image_info: Option<JumboImageInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JumboPrices {
+    price: JumboAmount,
+    promotional_price: Option<JumboAmount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JumboAmount {
+    /// Amount in cents
+    amount: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JumboImageInfo {
+    #[serde(default)]
+    primary_view: Vec<JumboImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JumboImage {
+    url: String,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "grocery_categories"]
+struct GroceryCategory {
+    #[key]
+    store: String,
+    #[key]
+    name: String,
+    parent: Option<String>,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "grocery_products"]
+struct GroceryProduct {
+    #[key]
+    store: String,
+    #[key]
+    product_id: String,
+    title: String,
+    brand: Option<String>,
+    /// e.g. "1 l" or "6 stuks"
+    unit_size: Option<String>,
+    category: Option<String>,
+    image_url: Option<String>,
+}
+
+/// Price of a product as found by a run
+#[derive(Debug, BatchInserter)]
+#[pgtable = "grocery_prices"]
+struct PricePoint {
+    #[key]
+    store: String,
+    #[key]
+    product_id: String,
+    #[key]
+    fetched_at: DateTime<Utc>,
+    price_cents: i32,
+    /// Price without the promotion, `None` when there is no promotion
+    regular_price_cents: Option<i32>,
+    promotion: Option<String>,
+}
+
+/// Products found on a page of search results together with their prices and
+/// categories, and whether there are more pages
+#[derive(Debug, Default)]
+struct Listings {
+    products: Vec<GroceryProduct>,
+    prices: Vec<PricePoint>,
+    categories: Vec<GroceryCategory>,
+    more: bool,
+}
+
+fn cents(euros: f64) -> i32 {
+    (euros * 100.0).round() as i32
+}
+
+impl AhProduct {
+    fn flatten(self, fetched_at: DateTime<Utc>, listings: &mut Listings) {
+        let store = Store::AlbertHeijn.name().to_string();
+        let product_id = self.webshop_id.to_string();
+        let Some(price) = self.current_price.or(self.price_before_bonus) else {
+            debug!("Skipped {} without a price", self.title);
+            return;
+        };
+        let regular_price = self
+            .price_before_bonus
+            .filter(|regular| self.current_price.is_some_and(|current| current < *regular));
+        if let Some(main_category) = &self.main_category {
+            listings.categories.push(GroceryCategory {
+                store: store.clone(),
+                name: main_category.clone(),
+                parent: None,
+            });
+        }
+        if let Some(sub_category) = &self.sub_category {
+            listings.categories.push(GroceryCategory {
+                store: store.clone(),
+                name: sub_category.clone(),
+                parent: self.main_category.clone(),
+            });
+        }
+        listings.prices.push(PricePoint {
+            store: store.clone(),
+            product_id: product_id.clone(),
+            fetched_at,
+            price_cents: cents(price),
+            regular_price_cents: regular_price.map(cents),
+            promotion: self.bonus_mechanism,
+        });
+        // Images are listed in several sizes, of which the largest is kept
+        let image_url = self
+            .images
+            .into_iter()
+            .max_by_key(|image| image.width.unwrap_or_default())
+            .map(|image| image.url);
+        listings.products.push(GroceryProduct {
+            store,
+            product_id,
+            title: self.title,
+            brand: self.brand,
+            unit_size: self.sales_unit_size,
+            category: self.sub_category.or(self.main_category),
+            image_url,
+        });
+    }
+}
+
+impl JumboProduct {
+    fn flatten(self, fetched_at: DateTime<Utc>, listings: &mut Listings) {
+        let store = Store::Jumbo.name().to_string();
+        let (price, regular_price) = match self.prices.promotional_price {
+            Some(promotional) if promotional.amount < self.prices.price.amount => {
+                (promotional.amount, Some(self.prices.price.amount))
+            }
+            _ => (self.prices.price.amount, None),
+        };
+        listings.prices.push(PricePoint {
+            store: store.clone(),
+            product_id: self.id.clone(),
+            fetched_at,
+            price_cents: price,
+            regular_price_cents: regular_price,
+            promotion: None,
+        });
+        listings.products.push(GroceryProduct {
+            store,
+            product_id: self.id,
+            title: self.title,
+            brand: None,
+            unit_size: self.quantity,
+            category: None,
+            image_url: self
+                .image_info
+                .and_then(|info| info.primary_view.into_iter().next())
+                .map(|image| image.url),
+        });
+    }
+}
+
+/// Albert Heijn only answers requests with the token of an (anonymous) app user
+async fn ah_client(client: Client) -> Result<Client> {
+    let token: AhToken = client
+        .get_json_post(AH_TOKEN_URL, json!({ "clientId": "appie" }))
+        .await?;
+    Ok(client
+        .with_header(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token.access_token))?,
+        )
+        .with_header(
+            HeaderName::from_static("x-application"),
+            HeaderValue::from_static("AHWEBSHOP"),
+        ))
+}
+
+async fn search_page(
+    client: &Client,
+    store: Store,
+    query: &str,
+    page: usize,
+    fetched_at: DateTime<Utc>,
+) -> Result<Listings> {
+    let mut listings = Listings::default();
+    match store {
+        Store::AlbertHeijn => {
+            let url = Url::parse_with_params(
+                AH_SEARCH_URL,
+                [
+                    ("query", query.to_string()),
+                    ("page", page.to_string()),
+                    ("size", PAGE_SIZE.to_string()),
+                ],
+            )?;
+            let search: AhSearch = client.get_json(url).await?;
+            listings.more = page + 1 < search.page.total_pages;
+            for product in search.products {
+                product.flatten(fetched_at, &mut listings);
+            }
+        }
+        Store::Jumbo => {
+            let url = Url::parse_with_params(
+                JUMBO_SEARCH_URL,
+                [
+                    ("q", query.to_string()),
+                    ("offset", (page * PAGE_SIZE).to_string()),
+                    ("limit", PAGE_SIZE.to_string()),
+                ],
+            )?;
+            let search: JumboSearch = client.get_json(url).await?;
+            listings.more = (page + 1) * PAGE_SIZE < search.products.total;
+            for product in search.products.data {
+                product.flatten(fetched_at, &mut listings);
+            }
+        }
+    }
+    Ok(listings)
+}
+
+/// Searches the stores for the configured queries and stores the products found,
+/// their categories and the prices of this run.
+#[derive(Debug)]
+pub struct GroceryFetcher {
+    pub pool: PgPool,
+    pub config: GroceriesConfig,
+}
+impl Runnable for GroceryFetcher {
+    async fn run(&self, context: &RunContext) -> Result<()> {
+        // All prices of a run share the same time, which makes them a snapshot
+        let fetched_at = Utc::now();
+        let mut products = HashMap::new();
+        let mut prices = HashMap::new();
+        let mut categories = HashMap::new();
+        for &store in &self.config.stores {
+            let client = match store {
+                Store::AlbertHeijn => ah_client(self.config.client(store)?).await?,
+                Store::Jumbo => self.config.client(store)?,
+            };
+            for query in &self.config.queries {
+                for page in 0..self.config.max_pages {
+                    let listings = search_page(&client, store, query, page, fetched_at).await?;
+                    // Products are often found by several queries
+                    products.extend(listings.products.into_iter().map(|product| {
+                        ((product.store.clone(), product.product_id.clone()), product)
+                    }));
+                    prices.extend(
+                        listings
+                            .prices
+                            .into_iter()
+                            .map(|price| ((price.store.clone(), price.product_id.clone()), price)),
+                    );
+                    categories.extend(listings.categories.into_iter().map(|category| {
+                        ((category.store.clone(), category.name.clone()), category)
+                    }));
+                    if !listings.more {
+                        break;
+                    }
+                }
+            }
+            info!(
+                "Requests of the fetcher for groceries to {}: {}",
+                store.name(),
+                client.stats()
+            );
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let mut deltas = RunDeltas::new("groceryfetcher");
+        deltas
+            .track(
+                &mut tx,
+                "grocery_categories",
+                categories.len(),
+                async |conn| {
+                    GroceryCategoryInserter::from(categories.into_values().collect())
+                        .build()
+                        .execute(conn)
+                        .await
+                },
+            )
+            .await?;
+        deltas
+            .track(&mut tx, "grocery_products", products.len(), async |conn| {
+                GroceryProductInserter::from(products.into_values().collect())
+                    .build()
+                    .execute(conn)
+                    .await
+            })
+            .await?;
+        deltas
+            .track(&mut tx, "grocery_prices", prices.len(), async |conn| {
+                PricePointInserter::from(prices.into_values().collect())
+                    .build()
+                    .execute(conn)
+                    .await
+            })
+            .await?;
+        deltas
+            .finish(
+                tx,
+                &self.pool,
+                context,
+                self.config.max_volume_deviation,
+                self.config.notify.as_ref(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    env, process,
+    time::{Duration, Instant},
+};
+
+use sqlx::{Connection, PgConnection, PgPool};
+use tracing::{info, warn};
+
+/// Advisory lock key used for owning the scheduler
+pub const SCHEDULER_LOCK_KEY: i64 = 0x5343_4852_4150_4552;
+
+/// First half of the (two part) advisory lock keys of jobs, the second half is a
+/// hash of the name of the job
+pub const JOB_LOCK_CLASS: i32 = 0x5343_4852;
+
+/// Leader election based on a Postgres (session level) advisory lock.
+///
+/// The lock is held on a dedicated connection, which acts as the lease: when the
+/// leader dies its connection is closed and the lock is released, such that one of
+/// the standby instances acquires it on its next attempt. Standbys keep their
+/// connection between attempts as well. The connection is checked every heartbeat
+/// interval, so a leader which lost its connection steps down.
+pub struct LeaderElection {
+    pool: PgPool,
+    key: i64,
+    instance: String,
+    /// Connection outside of the pool, such that the lock is released when it is
+    /// dropped instead of lingering on a pooled connection
+    conn: Option<PgConnection>,
+    leader: bool,
+    last_heartbeat: Instant,
+    heartbeat_interval: Duration,
+}
+
+impl LeaderElection {
+    pub fn new(pool: PgPool, key: i64) -> Self {
+        LeaderElection {
+            pool,
+            key,
+            instance: format!(
+                "{}-{}",
+                env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string()),
+                process::id()
+            ),
+            conn: None,
+            leader: false,
+            last_heartbeat: Instant::now(),
+            heartbeat_interval: Duration::from_secs(10),
+        }
+    }
+
+    pub fn with_heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        self.heartbeat_interval = heartbeat_interval;
+        self
+    }
+
+    /// Returns whether this instance is the leader, trying to become the leader when
+    /// it currently is not.
+    pub async fn is_leader(&mut self) -> bool {
+        if self.leader
+            && let Some(conn) = &mut self.conn
+        {
+            if self.last_heartbeat.elapsed() < self.heartbeat_interval {
+                return true;
+            }
+            match conn.ping().await {
+                Ok(()) => {
+                    self.last_heartbeat = Instant::now();
+                    return true;
+                }
+                Err(err) => {
+                    warn!(instance = self.instance, "Lost leadership: {err}");
+                    self.conn = None;
+                    self.leader = false;
+                }
+            }
+        }
+
+        match self.try_acquire().await {
+            Ok(true) => {
+                info!(instance = self.instance, "Became the leader");
+                self.leader = true;
+                self.last_heartbeat = Instant::now();
+                true
+            }
+            Ok(false) => false,
+            Err(err) => {
+                warn!(
+                    instance = self.instance,
+                    "Could not try to become the leader: {err}"
+                );
+                // Reconnect on the next attempt, the connection may be broken
+                self.conn = None;
+                false
+            }
+        }
+    }
+
+    /// Releases the lock (if held), such that a standby instance can take over right
+    /// away instead of waiting for the connection to time out
+    pub async fn step_down(&mut self) {
+        if !std::mem::take(&mut self.leader) {
+            return;
+        }
+        if let Some(conn) = self.conn.take() {
+            if let Err(err) = conn.close().await {
+                warn!(
+                    instance = self.instance,
+                    "Could not step down cleanly: {err}"
+                );
+            }
+            info!(instance = self.instance, "Stepped down as the leader");
+        }
+    }
+
+    /// Tries to take the lock on the dedicated connection, connecting first when
+    /// there is none yet
+    async fn try_acquire(&mut self) -> Result<bool, sqlx::Error> {
+        let conn = match &mut self.conn {
+            Some(conn) => conn,
+            None => self
+                .conn
+                .insert(PgConnection::connect_with(&self.pool.connect_options()).await?),
+        };
+        sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(self.key)
+            .fetch_one(conn)
+            .await
+    }
+}
+
+/// Advisory lock held while a job runs, such that instances which do not use leader
+/// election (or a `run --once` next to the daemon) never run the same job at once.
+///
+/// Like the leader lock it is held on a dedicated connection, so it is released when
+/// the instance dies halfway through a run.
+pub struct JobLock {
+    conn: PgConnection,
+}
+
+impl JobLock {
+    /// Takes the lock of the job with the given name, or returns `None` when another
+    /// instance holds it. The key is hashed by Postgres, such that it is the same for
+    /// every build of the scraper.
+    pub async fn try_acquire(pool: &PgPool, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        let mut conn = PgConnection::connect_with(&pool.connect_options()).await?;
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1, hashtext($2))")
+            .bind(JOB_LOCK_CLASS)
+            .bind(name)
+            .fetch_one(&mut conn)
+            .await?;
+        Ok(acquired.then_some(JobLock { conn }))
+    }
+
+    /// Releases the lock by closing its connection
+    pub async fn release(self) {
+        if let Err(err) = self.conn.close().await {
+            warn!("Could not release a job lock cleanly: {err}");
+        }
+    }
+}
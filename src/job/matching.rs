@@ -1,26 +1,245 @@
+use std::collections::BTreeSet;
+
 use itertools::Itertools;
 use strsim::normalized_levenshtein;
 
 use crate::job::movies::RTHit;
 
-pub fn best_rt_hit(hits: Vec<RTHit>, title: String, year: Option<i32>) -> Option<(RTHit, f64)> {
-    hits.into_iter()
-        .map(|hit| {
-            let mut score = 0f64;
-
-            // If we have year data, the absolute difference is used with a weighting
-            if let Some(rt_year) = hit.release_year
-                && let Some(pathe_year) = year
-            {
-                score += 0.1 * (rt_year as f64 - pathe_year as f64).abs();
-            }
-
-            // Most important for the score is the Levensthein distance between the
-            // pathe title and the RT title
-            score += 1f64 - normalized_levenshtein(&title, &hit.title);
-
-            (hit, score)
+/// Entry of a rating source which a scraped title can be matched against
+pub trait Candidate {
+    fn title(&self) -> &str;
+
+    fn year(&self) -> Option<i32> {
+        None
+    }
+
+    /// Alternative titles under which the entry is known as well
+    fn aliases(&self) -> &[String] {
+        &[]
+    }
+}
+
+impl Candidate for RTHit {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn year(&self) -> Option<i32> {
+        self.release_year
+    }
+}
+
+/// Scraped title (and year, if known) to find the matching candidate for
+#[derive(Debug, Clone, Copy)]
+pub struct Query<'a> {
+    pub title: &'a str,
+    pub year: Option<i32>,
+}
+
+/// Strategy scoring how well a candidate matches a query, 0 being a perfect match
+/// and lower being better
+pub trait Matcher: Send + Sync {
+    fn score(&self, query: Query, candidate: &dyn Candidate) -> f64;
+}
+
+/// Levenshtein distance between the titles, normalized to `0..=1`
+#[derive(Debug, Clone, Copy)]
+pub struct Levenshtein;
+
+impl Matcher for Levenshtein {
+    fn score(&self, query: Query, candidate: &dyn Candidate) -> f64 {
+        1f64 - normalized_levenshtein(
+            &normalize_title(query.title),
+            &normalize_title(candidate.title()),
+        )
+    }
+}
+
+/// Distance between the titles as sets of words, such that word order and words
+/// present in only one of the titles (e.g. a subtitle) matter less
+#[derive(Debug, Clone, Copy)]
+pub struct TokenSetRatio;
+
+impl Matcher for TokenSetRatio {
+    fn score(&self, query: Query, candidate: &dyn Candidate) -> f64 {
+        let (query_title, candidate_title) = (
+            normalize_title(query.title),
+            normalize_title(candidate.title()),
+        );
+        let a: BTreeSet<&str> = query_title.split_whitespace().collect();
+        let b: BTreeSet<&str> = candidate_title.split_whitespace().collect();
+        let common = a.intersection(&b).join(" ");
+        let with = |rest: BTreeSet<&&str>| {
+            let rest = rest.into_iter().join(" ");
+            format!("{common} {rest}").trim().to_string()
+        };
+        let only_a = with(a.difference(&b).collect());
+        let only_b = with(b.difference(&a).collect());
+        let similarity = [
+            normalized_levenshtein(&common, &only_a),
+            normalized_levenshtein(&common, &only_b),
+            normalized_levenshtein(&only_a, &only_b),
+        ]
+        .into_iter()
+        .fold(0f64, f64::max);
+        1f64 - similarity
+    }
+}
+
+/// Absolute difference in years, 0 when either year is unknown
+#[derive(Debug, Clone, Copy)]
+pub struct YearDistance;
+
+impl Matcher for YearDistance {
+    fn score(&self, query: Query, candidate: &dyn Candidate) -> f64 {
+        match (query.year, candidate.year()) {
+            (Some(a), Some(b)) => (a as f64 - b as f64).abs(),
+            _ => 0f64,
+        }
+    }
+}
+
+/// 0 when the title equals the title or one of the aliases of the candidate once
+/// variant markers are ignored, 1 otherwise
+#[derive(Debug, Clone, Copy)]
+pub struct ExactAlias;
+
+impl Matcher for ExactAlias {
+    fn score(&self, query: Query, candidate: &dyn Candidate) -> f64 {
+        let title = normalize_title(query.title);
+        let exact = std::iter::once(candidate.title())
+            .chain(candidate.aliases().iter().map(String::as_str))
+            .any(|other| normalize_title(other) == title);
+        if exact { 0f64 } else { 1f64 }
+    }
+}
+
+/// Weighted sum of the scores of multiple strategies
+#[derive(Default)]
+pub struct Weighted {
+    matchers: Vec<(f64, Box<dyn Matcher>)>,
+}
+
+impl Weighted {
+    pub fn new() -> Self {
+        Weighted::default()
+    }
+
+    pub fn with(mut self, weight: f64, matcher: impl Matcher + 'static) -> Self {
+        self.matchers.push((weight, Box::new(matcher)));
+        self
+    }
+}
+
+impl Matcher for Weighted {
+    fn score(&self, query: Query, candidate: &dyn Candidate) -> f64 {
+        self.matchers
+            .iter()
+            .map(|(weight, matcher)| weight * matcher.score(query, candidate))
+            .sum()
+    }
+}
+
+/// The best scoring candidate, the first one when multiple candidates score equally
+/// well
+pub fn best_match<C: Candidate>(
+    matcher: &dyn Matcher,
+    candidates: Vec<C>,
+    query: Query,
+) -> Option<(C, f64)> {
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let score = matcher.score(query, &candidate);
+            (candidate, score)
         })
-        .sorted_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-        .next()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Matcher used for Rotten Tomatoes: mostly the Levenshtein distance between the
+/// titles, with the difference in years as a tie breaker
+pub fn rt_matcher() -> Weighted {
+    Weighted::new()
+        .with(1.0, Levenshtein)
+        .with(0.1, YearDistance)
+}
+
+/// Score of an RT hit for the given Pathé title and year, lower is better
+pub fn rt_hit_score(hit: &RTHit, title: &str, year: Option<i32>) -> f64 {
+    rt_matcher().score(Query { title, year }, hit)
+}
+
+/// The best scoring hit, the first one when multiple hits score equally well
+pub fn best_rt_hit(hits: Vec<RTHit>, title: String, year: Option<i32>) -> Option<(RTHit, f64)> {
+    let query = Query {
+        title: &title,
+        year,
+    };
+    best_match(&rt_matcher(), hits, query)
+}
+
+/// Lowercased title without bracketed variant markers such as "(OV)" or "(4K)"
+pub fn normalize_title(title: &str) -> String {
+    let mut normalized = String::with_capacity(title.len());
+    let mut depth = 0usize;
+    for c in title.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            c if depth == 0 => normalized.extend(c.to_lowercase()),
+            _ => {}
+        }
+    }
+    normalized.split_whitespace().join(" ")
+}
+
+/// Whether two titles are near-identical once variant markers are ignored
+pub fn similar_titles(a: &str, b: &str) -> bool {
+    normalized_levenshtein(&normalize_title(a), &normalize_title(b)) >= 0.9
+}
+
+/// ISO 639-3 code of the language a title is (reliably) detected to be written in
+pub fn title_language(title: &str) -> Option<&'static str> {
+    whatlang::detect(&normalize_title(title))
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().code())
+}
+
+/// Show types which are never films, such as live broadcasts
+static NON_FILM_TYPES: [&str; 7] = [
+    "event", "opera", "ballet", "concert", "theatre", "theater", "live",
+];
+
+/// Title fragments of live broadcasts which are listed as regular shows
+static BROADCAST_MARKERS: [&str; 9] = [
+    "opera",
+    "ballet",
+    "concert",
+    "nt live",
+    "national theatre",
+    "met live",
+    "bolshoi",
+    "royal opera house",
+    "andré rieu",
+];
+
+/// Reason not to match a show against the (film) rating sources, `None` when the
+/// show appears to be a film. Matching e.g. an opera broadcast would confidently
+/// result in some unrelated film.
+pub fn rating_skip_reason(movie_type: &str, title: &str) -> Option<String> {
+    let movie_type = movie_type.to_lowercase();
+    if NON_FILM_TYPES.contains(&movie_type.as_str()) {
+        return Some(format!("show type {movie_type}"));
+    }
+    // Whole words only, such that e.g. "Operation Fortune" is not an opera
+    let words: String = title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let words = format!(" {} ", words.split_whitespace().join(" "));
+    BROADCAST_MARKERS
+        .iter()
+        .find(|marker| words.contains(&format!(" {marker} ")))
+        .map(|marker| format!("title mentions {marker}"))
 }
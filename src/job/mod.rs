@@ -1,46 +1,170 @@
 use std::{
-    env,
+    collections::HashMap,
+    env, fmt, fs,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
     time::{Duration, Instant},
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local, Utc};
+use croner::Cron;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+pub mod anomaly;
+pub mod archive;
+pub mod assets;
+pub mod books;
+pub mod calendar;
+pub mod control;
+pub mod delta;
+pub mod events;
+pub mod failed;
+pub mod groceries;
+pub mod leader;
 pub mod matching;
 pub mod movies;
+pub mod notify;
+pub mod parse;
+pub mod pool;
+pub mod queue;
+pub mod snapshot;
+pub mod tmdb;
+pub mod trakt;
 pub mod util;
+pub mod validation;
+pub mod weather;
+pub mod webhook;
 
+use books::{BookFetcher, BooksConfig};
+use calendar::{CalendarConfig, CalendarSync};
 use dotenvy::dotenv;
-use movies::MovieFetcher;
+use events::{EventFetcher, EventsConfig};
+use groceries::{GroceriesConfig, GroceryFetcher};
+use leader::{JobLock, LeaderElection, SCHEDULER_LOCK_KEY};
+use movies::{
+    MovieConfig, MovieFetcher, MovieWorker, MovieWorkerConfig, ShowsWatchConfig, ShowsWatcher,
+};
+use pool::PoolOptions;
+use trakt::{TraktConfig, TraktSync};
+use util::{Client, ClientConfig, Clients};
+use weather::{WeatherConfig, WeatherFetcher};
+use webhook::{RunReport, Webhook};
+
+use sqlx::{FromRow, PgPool};
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::Notify,
+    time::MissedTickBehavior,
+};
+use tracing::{Instrument, error, info, info_span, warn};
 
-use sqlx::PgPool;
+/// Kinds of jobs requested to run early, taken into account on the next poll
+type TriggeredKinds = Arc<Mutex<Vec<&'static str>>>;
 
-trait Runnable {
-    async fn run(&self) -> Result<()>;
+/// Handed to every run by `Jobs`, telling the runner how to run
+#[derive(Debug, Clone, Default)]
+pub struct RunContext {
+    /// Roll back everything the run would write and only report it, set by
+    /// `Jobs::with_dry_run` for the runners which support it
+    pub dry_run: bool,
+    /// Shared with the `Jobs` running the job
+    triggered_kinds: TriggeredKinds,
 }
 
-/// Define a job (by name) and it's accompanying 'runner'.
+impl RunContext {
+    /// Requests all (unpaused) jobs of the given kind to run on the next poll,
+    /// regardless of their interval. Used by jobs which notice that another job has
+    /// work to do.
+    pub fn trigger_kind(&self, kind: &'static str) {
+        self.triggered_kinds
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(kind);
+    }
+}
+
+/// Something which runs as a job. Next to the built-in runners of `define_jobs!`,
+/// runners of other crates can be added with `Jobs::add_custom`.
+pub trait Runnable {
+    fn run(&self, context: &RunContext) -> impl Future<Output = Result<()>> + Send;
+}
+
+type RunFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Object safe counterpart of `Runnable`, such that custom runners can be stored
+trait DynRunnable: Send + Sync {
+    fn run_boxed<'a>(&'a self, context: &'a RunContext) -> RunFuture<'a>;
+}
+
+impl<R: Runnable + Send + Sync> DynRunnable for R {
+    fn run_boxed<'a>(&'a self, context: &'a RunContext) -> RunFuture<'a> {
+        Box::pin(self.run(context))
+    }
+}
+
+/// Define a job (by name), it's accompanying 'runner' and the configuration
+/// passed to the runner when the job is added.
 ///
-/// This 'runner' should be some struct which implements the `Runnable` trait
+/// This 'runner' should be some struct which implements the `Runnable` trait and
+/// the configuration should be deserializable, such that jobs can be defined in the
+/// `job_definitions` table.
 macro_rules! define_jobs {
-    ($(($jobname:ident, $runnable:ident)),+) => {
+    ($(($jobname:ident, $runnable:ident, $config:ident)),+) => {
+        // Constructed once per job, so the configurations are not worth boxing
+        #[allow(clippy::large_enum_variant)]
         pub enum JobKind {
-            $($jobname),*
+            $($jobname($config)),*
         }
 
+        impl JobKind {
+            /// Name of the kind of job, as used in the `kind` column of `job_definitions`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(JobKind::$jobname(_) => stringify!($jobname)),*
+                }
+            }
+
+            /// Constructs a job kind from its name (case insensitive) and its parameters
+            pub fn from_definition(kind: &str, params: serde_json::Value) -> Result<JobKind> {
+                $(if kind.eq_ignore_ascii_case(stringify!($jobname)) {
+                    return Ok(JobKind::$jobname(serde_json::from_value(params)?));
+                })*
+                bail!("Unknown job kind {kind}")
+            }
+        }
+
+        #[allow(clippy::large_enum_variant)]
         enum JobRunner {
-            $($jobname($runnable)),*
+            $($jobname($runnable),)*
+            /// Runner added through `Jobs::add_custom`
+            Custom(Box<dyn DynRunnable>),
         }
 
         impl JobRunner {
             fn new(jobkind: JobKind, pool: PgPool) -> JobRunner {
                 match jobkind {
-                    $(JobKind::$jobname => JobRunner::$jobname($runnable{pool})),*
+                    $(JobKind::$jobname(config) => JobRunner::$jobname($runnable{pool, config})),*
+                }
+            }
+
+            fn kind(&self) -> &'static str {
+                match self {
+                    $(JobRunner::$jobname(_) => stringify!($jobname),)*
+                    JobRunner::Custom(_) => "Custom",
                 }
             }
 
-            async fn run(&self) -> Result<()> {
+            async fn run(&self, context: &RunContext) -> Result<()> {
                 match self {
-                    $(JobRunner::$jobname(fetcher) => fetcher.run().await),*
+                    $(JobRunner::$jobname(fetcher) => fetcher.run(context).await,)*
+                    JobRunner::Custom(runner) => runner.run_boxed(context).await,
                 }
             }
         }
@@ -48,71 +172,1092 @@ macro_rules! define_jobs {
 }
 
 define_jobs!(
-    (Movies, MovieFetcher)
+    (Movies, MovieFetcher, MovieConfig),
+    (MovieWorker, MovieWorker, MovieWorkerConfig),
+    (ShowsWatch, ShowsWatcher, ShowsWatchConfig),
+    (Trakt, TraktSync, TraktConfig),
+    (Calendar, CalendarSync, CalendarConfig),
+    (Events, EventFetcher, EventsConfig),
+    (Books, BookFetcher, BooksConfig),
+    (Groceries, GroceryFetcher, GroceriesConfig),
+    (Weather, WeatherFetcher, WeatherConfig)
 );
 
+impl JobKind {
+    /// Hands the shared clients to the configuration of the kinds of jobs which send
+    /// requests through them
+    fn with_clients(self, clients: &Clients) -> Self {
+        match self {
+            JobKind::Movies(config) => JobKind::Movies(config.with_clients(clients.clone())),
+            JobKind::MovieWorker(config) => {
+                JobKind::MovieWorker(config.with_clients(clients.clone()))
+            }
+            JobKind::ShowsWatch(config) => {
+                JobKind::ShowsWatch(config.with_clients(clients.clone()))
+            }
+            JobKind::Events(config) => JobKind::Events(config.with_clients(clients.clone())),
+            JobKind::Books(config) => JobKind::Books(config.with_clients(clients.clone())),
+            JobKind::Groceries(config) => JobKind::Groceries(config.with_clients(clients.clone())),
+            JobKind::Weather(config) => JobKind::Weather(config.with_clients(clients.clone())),
+            kind => kind,
+        }
+    }
+}
+
+impl JobRunner {
+    /// Whether the runner only reports what it would write in a dry run, other jobs
+    /// are skipped during a dry run
+    fn supports_dry_run(&self) -> bool {
+        matches!(
+            self,
+            JobRunner::Movies(_)
+                | JobRunner::Events(_)
+                | JobRunner::Books(_)
+                | JobRunner::Groceries(_)
+                | JobRunner::Weather(_)
+        )
+    }
+}
+
+/// Time running jobs get to finish after a shutdown was requested, after which they
+/// are cancelled
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+/// A job as defined in the `job_definitions` table or a configuration file
+#[derive(Debug, FromRow, Deserialize)]
+struct JobDefinition {
+    name: String,
+    kind: String,
+    interval_secs: Option<i64>,
+    cron: Option<String>,
+    #[serde(default = "no_params")]
+    params: serde_json::Value,
+    tenant: Option<String>,
+    retry_policy: Option<serde_json::Value>,
+    timeout_secs: Option<i64>,
+    offset_secs: Option<i64>,
+    jitter_secs: Option<i64>,
+}
+
+fn no_params() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+/// Contents of a configuration file, see `config.example.toml`
+#[derive(Debug, Deserialize)]
+struct JobsConfig {
+    #[serde(default)]
+    jobs: Vec<JobDefinition>,
+    /// Fired after the runs of every job
+    #[serde(default)]
+    webhooks: Vec<Webhook>,
+    /// Clients shared between the jobs by name, see `Jobs::with_client`
+    #[serde(default)]
+    clients: HashMap<String, ClientConfig>,
+}
+
+impl JobsConfig {
+    fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read configuration {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Invalid configuration {}", path.display()))
+    }
+}
+
+impl JobDefinition {
+    fn schedule(&self) -> Result<Schedule> {
+        match (self.interval_secs, &self.cron) {
+            (Some(secs), None) => Ok(Schedule::Interval(Duration::from_secs(secs.try_into()?))),
+            (None, Some(cron)) => Schedule::cron(cron),
+            _ => bail!(
+                "Job {} needs either an interval or a cron expression",
+                self.name
+            ),
+        }
+    }
+}
+
+/// When a job runs
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    /// Every interval after the previous run, starting right away
+    Interval(Duration),
+    /// At the (local) times matching a cron expression, e.g. `0 3 * * *` for 3 AM
+    Cron(Box<Cron>),
+}
+
+impl From<Duration> for Schedule {
+    fn from(interval: Duration) -> Self {
+        Schedule::Interval(interval)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Interval(interval) => write!(f, "every {}s", interval.as_secs()),
+            Schedule::Cron(cron) => write!(f, "cron {}", cron.as_str()),
+        }
+    }
+}
+
+impl Schedule {
+    pub fn cron(expression: &str) -> Result<Self> {
+        Ok(Schedule::Cron(Box::new(expression.parse()?)))
+    }
+
+    /// First moment the job is due after it ran at `last_ran`
+    fn next_after(&self, last_ran: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Schedule::Interval(interval) => last_ran
+                .checked_add_signed(
+                    chrono::Duration::from_std(*interval).unwrap_or(chrono::Duration::MAX),
+                )
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            Schedule::Cron(cron) => cron
+                .find_next_occurrence(&last_ran.with_timezone(&Local), false)
+                .map(|time| time.with_timezone(&Utc))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+}
+
+/// How a failing job is retried before it waits for its next regular run
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    /// Backoff before the first retry, doubled for every subsequent retry
+    backoff_secs: u64,
+    max_backoff_secs: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            backoff_secs: 30,
+            max_backoff_secs: 600,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, backoff: Duration) -> Self {
+        RetryPolicy {
+            max_retries,
+            backoff_secs: backoff.as_secs(),
+            ..Default::default()
+        }
+    }
+
+    /// Failing jobs are not retried, but simply wait for their next regular run
+    pub fn never() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff_secs = max_backoff.as_secs();
+        self
+    }
+
+    /// Backoff before the given retry (starting at 1)
+    fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u64.saturating_pow(retry.saturating_sub(1));
+        Duration::from_secs(
+            self.backoff_secs
+                .saturating_mul(factor)
+                .min(self.max_backoff_secs),
+        )
+    }
+}
+
 struct Job {
-    last_ran: Option<Instant>,
-    run_interval: Duration,
+    name: String,
+    from_definition: bool,
+    /// Moment the job was added, from which a cron schedule starts
+    added: DateTime<Utc>,
+    last_ran: Option<DateTime<Utc>>,
+    schedule: Schedule,
+    retry_policy: RetryPolicy,
+    /// Amount of retries done since the job last succeeded
+    retries: u32,
+    /// Moment of the next retry, when the last run failed
+    retry_at: Option<DateTime<Utc>>,
+    /// Runs taking longer are cancelled and count as failed
+    timeout: Option<Duration>,
+    /// Fixed delay after the scheduled moments, only of the first run of an interval
+    /// since later runs keep the stagger
+    offset: Duration,
+    /// Upper bound of the random delay of every run
+    max_jitter: Duration,
+    /// Random delay of the next run, drawn again after every run
+    jitter: Duration,
+    /// Pool the runner writes to, of which the run deltas are reported to webhooks
+    pool: PgPool,
     job_runner: JobRunner,
 }
 impl Job {
     fn should_run(&self) -> bool {
-        if let Some(time) = self.last_ran {
-            return (Instant::now() - time) >= self.run_interval;
-        }
-        true
+        Utc::now() >= self.due_at()
+    }
+
+    fn new(
+        name: String,
+        jobkind: JobKind,
+        schedule: Schedule,
+        pool: PgPool,
+        clients: &Clients,
+    ) -> Self {
+        let runner = JobRunner::new(jobkind.with_clients(clients), pool.clone());
+        Job::with_runner(name, runner, schedule, pool)
     }
 
-    fn new(jobkind: JobKind, interval: Duration, pool: PgPool) -> Self {
+    fn with_runner(name: String, job_runner: JobRunner, schedule: Schedule, pool: PgPool) -> Self {
         Job {
+            name,
+            from_definition: false,
+            added: Utc::now(),
             last_ran: None,
-            run_interval: interval,
-            job_runner: JobRunner::new(jobkind, pool),
+            schedule,
+            retry_policy: RetryPolicy::default(),
+            retries: 0,
+            retry_at: None,
+            timeout: None,
+            offset: Duration::ZERO,
+            max_jitter: Duration::ZERO,
+            jitter: Duration::ZERO,
+            job_runner,
+            pool,
         }
     }
 
-    async fn run(&mut self) -> Result<()> {
-        self.job_runner.run().await?;
-        self.last_ran = Some(Instant::now());
-        Ok(())
+    /// Moment at which the job is due, which may lie in the past
+    fn due_at(&self) -> DateTime<Utc> {
+        // Jobs which never run (e.g. with an interval of `Duration::MAX`) are due at
+        // the end of time, instead of overflowing
+        let delayed = |time: DateTime<Utc>, offset: Duration| {
+            let delay = offset
+                .checked_add(self.jitter)
+                .and_then(|delay| chrono::Duration::from_std(delay).ok())
+                .unwrap_or(chrono::Duration::MAX);
+            time.checked_add_signed(delay)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC)
+        };
+        match (self.retry_at, self.last_ran, &self.schedule) {
+            (Some(time), _, _) => time,
+            (None, Some(time), schedule @ Schedule::Interval(_)) => {
+                delayed(schedule.next_after(time), Duration::ZERO)
+            }
+            (None, Some(time), schedule) => delayed(schedule.next_after(time), self.offset),
+            (None, None, Schedule::Interval(_)) => delayed(self.added, self.offset),
+            (None, None, schedule) => delayed(schedule.next_after(self.added), self.offset),
+        }
+    }
+
+    /// Draws the random delay of the next run
+    fn draw_jitter(&mut self) {
+        self.jitter = match self.max_jitter.is_zero() {
+            true => Duration::ZERO,
+            false => rand::rng().random_range(Duration::ZERO..=self.max_jitter),
+        };
+    }
+
+    /// Moment at which the job is due, now when it is overdue
+    fn next_run(&self) -> DateTime<Utc> {
+        self.due_at().max(Utc::now())
+    }
+
+    /// Runs the job, scheduling a retry according to the retry policy when it fails.
+    /// Once the retries are exhausted the job waits for its next regular run. The
+    /// outcome is reported to the webhooks.
+    async fn run(&mut self, webhooks: &[Webhook], context: &RunContext) -> Result<()> {
+        let started_at = Utc::now();
+        let span = info_span!("job", name = self.name, kind = self.job_runner.kind());
+        if context.dry_run && !self.job_runner.supports_dry_run() {
+            span.in_scope(|| info!("Skipped, since this kind of job has no dry run"));
+            self.last_ran = Some(Utc::now());
+            return Ok(());
+        }
+        // The run waits for its next scheduled moment when another instance has it,
+        // but a lock which cannot be taken does not keep the job from running
+        let lock = match JobLock::try_acquire(&self.pool, &self.name).await {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                span.in_scope(|| info!("Skipped, another instance is running this job"));
+                self.last_ran = Some(Utc::now());
+                return Ok(());
+            }
+            Err(err) => {
+                span.in_scope(|| warn!("Running without a lock, could not take it: {err}"));
+                None
+            }
+        };
+        let run = self.job_runner.run(context).instrument(span);
+        let result = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, run)
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("Timed out after {timeout:?}"))),
+            None => run.await,
+        };
+        if let Some(lock) = lock {
+            lock.release().await;
+        }
+        self.retry_at = None;
+        self.draw_jitter();
+        match result {
+            Ok(()) => {
+                self.retries = 0;
+                self.last_ran = Some(Utc::now());
+            }
+            Err(_) if self.retries < self.retry_policy.max_retries => {
+                self.retries += 1;
+                let backoff = self.retry_policy.backoff(self.retries);
+                self.retry_at = Some(Utc::now() + chrono::Duration::from_std(backoff)?);
+            }
+            Err(_) => {
+                self.retries = 0;
+                self.last_ran = Some(Utc::now());
+            }
+        }
+        // Nothing was written during a dry run, so there is nothing to report
+        if !context.dry_run {
+            self.fire_webhooks(webhooks, started_at, &result).await;
+        }
+        result
+    }
+
+    /// Reports the run to the webhooks, which does not fail the run when it fails
+    async fn fire_webhooks(
+        &self,
+        webhooks: &[Webhook],
+        started_at: DateTime<Utc>,
+        result: &Result<()>,
+    ) {
+        if webhooks.is_empty() {
+            return;
+        }
+        let kind = self.job_runner.kind();
+        let mut report =
+            match RunReport::new(&self.pool, &self.name, kind, started_at, result).await {
+                Ok(report) => report,
+                Err(err) => {
+                    warn!(job = self.name, "Could not report the run: {err:#}");
+                    return;
+                }
+            };
+        report.retry_at = self.retry_at;
+        let client = reqwest::Client::new();
+        for webhook in webhooks.iter().filter(|webhook| webhook.fires_for(&report)) {
+            if let Err(err) = webhook.send(&client, &report).await {
+                warn!(job = self.name, "Could not fire a webhook: {err:#}");
+            }
+        }
+    }
+}
+
+/// Outcome of the runs of a job, shared with e.g. the health endpoint
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct JobStatus {
+    pub runs: u64,
+    pub consecutive_failures: u32,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Moment at which the currently running run started
+    pub running_since: Option<DateTime<Utc>>,
+    pub next_run: Option<DateTime<Utc>>,
+    /// Paused jobs are not ran until they are resumed
+    pub paused: bool,
+    /// Run the job on the next poll, regardless of its interval
+    pub triggered: bool,
+}
+
+/// Status of every job by name, updated after each run
+pub type JobStatuses = Arc<RwLock<HashMap<String, JobStatus>>>;
+
+fn record_status(statuses: &JobStatuses, name: &str, result: &Result<()>) {
+    let mut statuses = statuses.write().unwrap_or_else(|e| e.into_inner());
+    let status = statuses.entry(name.to_string()).or_default();
+    status.runs += 1;
+    status.running_since = None;
+    match result {
+        Ok(()) => {
+            status.consecutive_failures = 0;
+            status.last_success = Some(Utc::now());
+        }
+        Err(err) => {
+            status.consecutive_failures += 1;
+            status.last_error = Some(format!("{err:#}"));
+        }
+    }
+}
+
+/// What happened to a single job during a poll
+#[derive(Debug, Clone, Serialize)]
+pub struct JobOutcome {
+    pub name: String,
+    /// Jobs which are not due or paused are skipped
+    pub ran: bool,
+    pub duration: Option<Duration>,
+    /// Rows inserted or updated by the run, as recorded in `run_deltas`
+    pub rows_affected: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Outcome of every job which was considered by `Jobs::poll`. Jobs after a
+/// requested shutdown are not considered, nor are any jobs when this instance is
+/// not the leader.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PollReport {
+    pub jobs: Vec<JobOutcome>,
+}
+
+impl PollReport {
+    /// The jobs which ran during the poll
+    pub fn ran(&self) -> impl Iterator<Item = &JobOutcome> {
+        self.jobs.iter().filter(|job| job.ran)
+    }
+
+    /// The jobs which ran and failed during the poll
+    pub fn failed(&self) -> impl Iterator<Item = &JobOutcome> {
+        self.ran().filter(|job| job.error.is_some())
     }
 }
 
+impl fmt::Display for PollReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ran = self
+            .ran()
+            .map(|job| {
+                let duration = job.duration.unwrap_or_default().as_secs_f64();
+                match (&job.error, job.rows_affected) {
+                    (Some(_), _) => format!("{} failed after {duration:.1}s", job.name),
+                    (None, Some(rows)) => {
+                        format!("{} ran in {duration:.1}s ({rows} rows)", job.name)
+                    }
+                    (None, None) => format!("{} ran in {duration:.1}s", job.name),
+                }
+            })
+            .collect::<Vec<_>>();
+        match ran.is_empty() {
+            true => write!(f, "no jobs ran"),
+            false => write!(f, "{}", ran.join(", ")),
+        }
+    }
+}
+
+/// Rows inserted or updated since the given moment, which can be attributed to a
+/// single run since jobs run one after another
+async fn rows_affected_since(pool: &PgPool, since: DateTime<Utc>) -> Result<u64> {
+    let rows: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(sum(inserted + updated), 0)::BIGINT FROM run_deltas WHERE run_dt >= $1",
+    )
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+    Ok(rows as u64)
+}
+
+/// Handle to request the jobs to shut down, e.g. from a signal handler. Once
+/// requested, running jobs are allowed to finish but no new runs are started.
+#[derive(Debug, Default, Clone)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl Shutdown {
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Completes once a shutdown is requested
+    pub async fn requested(&self) {
+        let notified = self.notify.notified();
+        if self.is_requested() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Overview of a job, as listed by `Jobs::summaries`
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSummary {
+    pub name: String,
+    pub kind: &'static str,
+    pub schedule: String,
+    pub last_ran: Option<DateTime<Utc>>,
+    pub next_run: DateTime<Utc>,
+}
+
+/// Stores when the job last ran, such that its schedule survives a restart
+async fn persist_run(pool: &PgPool, job: &Job, dry_run: bool) -> Result<(), sqlx::Error> {
+    if job.last_ran.is_none() || dry_run {
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO job_schedule (name, last_ran) VALUES ($1, $2)
+        ON CONFLICT (name) DO UPDATE SET last_ran = EXCLUDED.last_ran",
+    )
+    .bind(&job.name)
+    .bind(job.last_ran)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 pub struct Jobs {
     joblist: Vec<Job>,
     pool: PgPool,
+    tenant_pools: HashMap<String, PgPool>,
+    /// Used for the pools of the tenants as well
+    pool_options: PoolOptions,
+    leader: Option<LeaderElection>,
+    statuses: JobStatuses,
+    shutdown: Shutdown,
+    poll_rate: Duration,
+    /// Set by `with_dry_run`, handed to every run
+    dry_run: bool,
+    /// Kinds triggered by the runs, see `RunContext::trigger_kind`
+    triggered_kinds: TriggeredKinds,
+    /// Moment each job last ran according to the `job_schedule` table, as loaded
+    /// during initialization
+    persisted_runs: HashMap<String, DateTime<Utc>>,
+    config_file: Option<PathBuf>,
+    webhooks: Vec<Webhook>,
+    /// Webhooks of the configuration file, replaced when it is re-read
+    config_webhooks: Vec<Webhook>,
+    /// Clients shared with the runners, such that jobs requesting the same hosts share
+    /// their rate limits
+    clients: Clients,
+    /// Clients of the configuration file, which are only rebuilt when they changed
+    config_clients: HashMap<String, ClientConfig>,
 }
 
 impl Jobs {
-    /// Initializes the job queue and creates database connection pool
+    /// Initializes the job queue and creates database connection pool, with the pool
+    /// options read from the environment
     pub async fn init() -> Result<Self> {
+        let _ = dotenv();
+        Self::init_with(PoolOptions::from_env()?).await
+    }
+
+    /// Initializes the job queue and creates database connection pool with the given
+    /// options
+    pub async fn init_with(pool_options: PoolOptions) -> Result<Self> {
         let _ = dotenv();
         let db_url = env::vars()
             .find(|(k, _)| k == "DATABASE_URL")
             .expect("No database URL supplied")
             .1;
-        let pool = PgPool::connect(&db_url).await?;
+        let pool = pool_options.connect(&db_url).await?;
         sqlx::migrate!().run(&pool).await?;
+        let persisted_runs = sqlx::query_as("SELECT name, last_ran FROM job_schedule")
+            .fetch_all(&pool)
+            .await?
+            .into_iter()
+            .collect();
         Ok(Jobs {
             joblist: vec![],
             pool,
+            tenant_pools: HashMap::new(),
+            pool_options,
+            leader: None,
+            statuses: JobStatuses::default(),
+            shutdown: Shutdown::default(),
+            poll_rate: Duration::from_secs(1),
+            dry_run: false,
+            triggered_kinds: TriggeredKinds::default(),
+            persisted_runs,
+            config_file: None,
+            webhooks: vec![],
+            config_webhooks: vec![],
+            clients: Clients::default(),
+            config_clients: HashMap::new(),
         })
     }
 
-    pub fn add(mut self, jobkind: JobKind, interval: Duration) -> Self {
-        self.joblist
-            .push(Job::new(jobkind, interval, self.pool.clone()));
+    /// Initializes the jobs with the ones defined in the given configuration file
+    /// (next to the ones in the `job_definitions` table)
+    pub async fn from_config(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::init()
+            .await?
+            .with_config_file(path)
+            .with_definitions()
+            .await
+    }
+
+    /// Returns the pool used for jobs of the given tenant.
+    ///
+    /// Every tenant gets its own Postgres schema (named after the tenant) in which
+    /// all migrations are ran, such that data of different tenants can never collide.
+    /// The public schema stays on the search path for the installed extensions.
+    async fn tenant_pool(&mut self, tenant: Option<&str>) -> Result<PgPool> {
+        let Some(tenant) = tenant else {
+            return Ok(self.pool.clone());
+        };
+        if let Some(pool) = self.tenant_pools.get(tenant) {
+            return Ok(pool.clone());
+        }
+        if tenant.is_empty()
+            || !tenant
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            bail!("Invalid tenant name {tenant}, only [a-z0-9_] is allowed");
+        }
+
+        sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS {tenant}"))
+            .execute(&self.pool)
+            .await?;
+        let options = (*self.pool.connect_options())
+            .clone()
+            .options([("search_path", format!("{tenant},public"))]);
+        let pool = self.pool_options.connect_with(options).await?;
+        sqlx::migrate!().run(&pool).await?;
+
+        self.tenant_pools.insert(tenant.to_string(), pool.clone());
+        Ok(pool)
+    }
+
+    /// Adds a job which runs at an interval (given as a `Duration`) or on a cron schedule
+    pub fn add(self, jobkind: JobKind, schedule: impl Into<Schedule>) -> Self {
+        self.add_with_retry_policy(jobkind, schedule, RetryPolicy::default())
+    }
+
+    /// Adds a job which is retried according to the given policy when it fails
+    pub fn add_with_retry_policy(
+        mut self,
+        jobkind: JobKind,
+        schedule: impl Into<Schedule>,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        let name = jobkind.name().to_lowercase();
+        let mut job = Job::new(
+            name,
+            jobkind,
+            schedule.into(),
+            self.pool.clone(),
+            &self.clients,
+        );
+        job.retry_policy = retry_policy;
+        job.last_ran = self.persisted_runs.get(&job.name).copied();
+        self.joblist.push(job);
+        self
+    }
+
+    /// Adds a job with a runner defined outside of this crate, which runs at an
+    /// interval or on a cron schedule. Its name has to be unique among the jobs.
+    pub fn add_custom(
+        mut self,
+        name: impl Into<String>,
+        schedule: impl Into<Schedule>,
+        runnable: impl Runnable + Send + Sync + 'static,
+    ) -> Self {
+        let runner = JobRunner::Custom(Box::new(runnable));
+        let mut job = Job::with_runner(name.into(), runner, schedule.into(), self.pool.clone());
+        job.last_ran = self.persisted_runs.get(&job.name).copied();
+        self.joblist.push(job);
+        self
+    }
+
+    /// Adds a job which starts `offset` after the moments of its schedule.
+    ///
+    /// Jobs sharing a schedule otherwise all become due in the same poll. As jobs run
+    /// one after another in the order they were added, giving them offsets of e.g.
+    /// 0, 5 and 10 minutes staggers them deterministically: an interval keeps the
+    /// offset of its first run, a cron schedule is offset on every run.
+    pub fn add_with_offset(
+        self,
+        jobkind: JobKind,
+        schedule: impl Into<Schedule>,
+        offset: Duration,
+    ) -> Self {
+        self.add(jobkind, schedule).with_offset(offset)
+    }
+
+    /// Starts the most recently added job `offset` after the moments of its schedule,
+    /// see `add_with_offset`
+    pub fn with_offset(mut self, offset: Duration) -> Self {
+        if let Some(job) = self.joblist.last_mut() {
+            job.offset = offset;
+        }
+        self
+    }
+
+    /// Delays every run of the most recently added job by a random duration of up to
+    /// `max_jitter`, which spreads jobs sharing a schedule without coordinating their
+    /// offsets. Retries are not delayed.
+    pub fn with_jitter(mut self, max_jitter: Duration) -> Self {
+        if let Some(job) = self.joblist.last_mut() {
+            job.max_jitter = max_jitter;
+            job.draw_jitter();
+        }
+        self
+    }
+
+    /// Cancels runs of the most recently added job which take longer than `timeout`,
+    /// such that e.g. a hanging upstream API cannot stall the scheduler
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        if let Some(job) = self.joblist.last_mut() {
+            job.timeout = Some(timeout);
+        }
+        self
+    }
+
+    /// Adds a job of which all data is stored separately for the given tenant
+    pub async fn add_for_tenant(
+        mut self,
+        jobkind: JobKind,
+        schedule: impl Into<Schedule>,
+        tenant: &str,
+    ) -> Result<Self> {
+        let name = format!("{}_{tenant}", jobkind.name().to_lowercase());
+        let pool = self.tenant_pool(Some(tenant)).await?;
+        let mut job = Job::new(name, jobkind, schedule.into(), pool, &self.clients);
+        job.last_ran = self.persisted_runs.get(&job.name).copied();
+        self.joblist.push(job);
+        Ok(self)
+    }
+
+    /// Only poll jobs when this instance is the elected leader, such that multiple
+    /// instances can be deployed while only one of them runs the jobs. The others
+    /// stand by and take over when the leader dies.
+    pub fn with_leader_election(mut self) -> Self {
+        self.leader = Some(LeaderElection::new(self.pool.clone(), SCHEDULER_LOCK_KEY));
+        self
+    }
+
+    /// Interval at which `run_forever` polls the jobs, every second by default
+    pub fn with_poll_rate(mut self, poll_rate: Duration) -> Self {
+        self.poll_rate = poll_rate;
+        self
+    }
+
+    /// Also read job definitions from the given TOML file whenever the definitions
+    /// are (re)loaded, such that jobs can be changed without recompiling
+    pub fn with_config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// Runs the fetchers fully, but rolls back everything they would write and logs a
+    /// summary of it per table instead. Jobs which cannot do so (such as the syncs,
+    /// which write to other services) are skipped.
+    pub fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Context handed to the runs of the jobs
+    fn context(&self) -> RunContext {
+        RunContext {
+            dry_run: self.dry_run,
+            triggered_kinds: self.triggered_kinds.clone(),
+        }
+    }
+
+    /// Reports every run of every job to the webhook, next to the ones in the
+    /// configuration file
+    pub fn with_webhook(mut self, webhook: Webhook) -> Self {
+        self.webhooks.push(webhook);
+        self
+    }
+
+    /// Shares the client under the name with every job, such that jobs sending requests
+    /// to the same hosts share its rate limits. The fetchers look for a client named
+    /// after their upstream, e.g. `PATHE_CLIENT`, and build their own when there is
+    /// none. Jobs which were added before see the client as well.
+    pub fn with_client(self, name: impl Into<String>, client: Client) -> Self {
+        self.clients.insert(name, client);
         self
     }
 
+    /// The clients shared with the jobs
+    pub fn clients(&self) -> Clients {
+        self.clients.clone()
+    }
+
+    /// Webhooks fired after every run
+    fn all_webhooks(&self) -> Vec<Webhook> {
+        self.webhooks
+            .iter()
+            .chain(&self.config_webhooks)
+            .cloned()
+            .collect()
+    }
+
+    /// Adds all enabled jobs from the `job_definitions` table and configuration file
+    pub async fn with_definitions(mut self) -> Result<Self> {
+        self.reload().await?;
+        Ok(self)
+    }
+
+    /// Replaces the jobs which were read from the `job_definitions` table (and the
+    /// configuration file) with their current contents. Jobs which keep their name
+    /// also keep track of when they last ran, such that a reload does not trigger
+    /// all jobs. When a definition is invalid the current jobs are kept.
+    pub async fn reload(&mut self) -> Result<()> {
+        let mut definitions: Vec<JobDefinition> = sqlx::query_as(
+            r#"SELECT
+                name, kind, interval_secs, cron, params, tenant, retry_policy, timeout_secs,
+                offset_secs, jitter_secs
+            FROM job_definitions WHERE enabled ORDER BY name"#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        if let Some(path) = &self.config_file {
+            let mut config = JobsConfig::read(path)?;
+            definitions.append(&mut config.jobs);
+            self.config_webhooks = config.webhooks;
+            // Rebuilding an unchanged client would reset the limits it enforces
+            for (name, client) in &config.clients {
+                if self.config_clients.get(name) != Some(client) {
+                    self.clients.insert(name, client.build());
+                }
+            }
+            self.config_clients = config.clients;
+        }
+
+        let mut jobs = Vec::with_capacity(definitions.len());
+        for definition in definitions {
+            let schedule = definition.schedule()?;
+            let jobkind = JobKind::from_definition(&definition.kind, definition.params)?;
+            let pool = self.tenant_pool(definition.tenant.as_deref()).await?;
+            let mut job = Job::new(definition.name, jobkind, schedule, pool, &self.clients);
+            job.from_definition = true;
+            if let Some(retry_policy) = definition.retry_policy {
+                job.retry_policy = serde_json::from_value(retry_policy)?;
+            }
+            if let Some(secs) = definition.timeout_secs {
+                job.timeout = Some(Duration::from_secs(secs.try_into()?));
+            }
+            if let Some(secs) = definition.offset_secs {
+                job.offset = Duration::from_secs(secs.try_into()?);
+            }
+            if let Some(secs) = definition.jitter_secs {
+                job.max_jitter = Duration::from_secs(secs.try_into()?);
+                job.draw_jitter();
+            }
+            job.last_ran = self.persisted_runs.get(&job.name).copied();
+            if let Some(old) = self
+                .joblist
+                .iter()
+                .find(|old| old.from_definition && old.name == job.name)
+            {
+                job.added = old.added;
+                job.last_ran = old.last_ran;
+                job.retries = old.retries;
+                job.retry_at = old.retry_at;
+                job.jitter = old.jitter.min(job.max_jitter);
+            }
+            jobs.push(job);
+        }
+
+        info!("Loaded {} job definitions", jobs.len());
+        self.joblist.retain(|job| !job.from_definition);
+        self.joblist.append(&mut jobs);
+        Ok(())
+    }
+
     /// Polls jobs in the defined order. Executing them in said order.
-    pub async fn poll(&mut self) -> Result<()> {
+    ///
+    /// Paused jobs are skipped, while triggered jobs run regardless of their interval.
+    /// After a shutdown is requested no further jobs are started. A failing job is
+    /// recorded in its status and retried later, without affecting the other jobs.
+    /// The returned report tells which jobs ran and how that went.
+    pub async fn poll(&mut self) -> Result<PollReport> {
+        let mut report = PollReport::default();
+        if let Some(leader) = &mut self.leader
+            && !leader.is_leader().await
+        {
+            return Ok(report);
+        }
+        let triggered_kinds = std::mem::take(
+            &mut *self
+                .triggered_kinds
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        );
+        let webhooks = self.all_webhooks();
+        let context = self.context();
         for job in &mut self.joblist {
-            if job.should_run() {
-                job.run().await?;
+            if self.shutdown.is_requested() {
+                break;
+            }
+            let run = {
+                let mut statuses = self.statuses.write().unwrap_or_else(|e| e.into_inner());
+                let status = statuses.entry(job.name.clone()).or_default();
+                let due = job.should_run() || triggered_kinds.contains(&job.job_runner.kind());
+                let run = std::mem::take(&mut status.triggered) || (!status.paused && due);
+                status.running_since = run.then(Utc::now);
+                run
+            };
+            let mut outcome = JobOutcome {
+                name: job.name.clone(),
+                ran: run,
+                duration: None,
+                rows_affected: None,
+                error: None,
+            };
+            if run {
+                let last_ran = job.last_ran;
+                let started = Instant::now();
+                let started_at = Utc::now();
+                let result = job.run(&webhooks, &context).await;
+                outcome.duration = Some(started.elapsed());
+                record_status(&self.statuses, &job.name, &result);
+                match rows_affected_since(&job.pool, started_at).await {
+                    Ok(rows) => outcome.rows_affected = Some(rows),
+                    Err(err) => warn!(job = job.name, "Could not count the affected rows: {err}"),
+                }
+                if let Err(err) = result {
+                    error!(job = job.name, "Job failed: {err:#}");
+                    outcome.error = Some(format!("{err:#}"));
+                }
+                if job.last_ran != last_ran
+                    && let Err(err) = persist_run(&self.pool, job, self.dry_run).await
+                {
+                    warn!(job = job.name, "Could not persist the run: {err}");
+                }
+            }
+            if let Some(status) = self
+                .statuses
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_mut(&job.name)
+            {
+                status.next_run = Some(job.next_run());
             }
+            report.jobs.push(outcome);
         }
-        Ok(())
+        Ok(report)
+    }
+
+    /// Polls the jobs at the poll rate until a shutdown is requested, re-reading the
+    /// job definitions on SIGHUP. Waiting happens on the runtime, such that other
+    /// tasks (e.g. the health endpoint) keep running in between polls.
+    ///
+    /// On shutdown the running job gets some time to finish before it is cancelled.
+    pub async fn run_forever(&mut self) -> Result<()> {
+        let shutdown = self.shutdown.clone();
+        let mut reload = signal(SignalKind::hangup())?;
+        let mut interval = tokio::time::interval(self.poll_rate);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => (),
+                _ = reload.recv() => {
+                    // A broken definition should not take down the running jobs
+                    if let Err(err) = self.reload().await {
+                        error!("Could not reload the jobs, keeping the current ones: {err:#}");
+                    }
+                    continue;
+                }
+                _ = shutdown.requested() => return Ok(()),
+            }
+
+            let polled = {
+                let poll = self.poll();
+                tokio::pin!(poll);
+                tokio::select! {
+                    result = &mut poll => Some(result),
+                    _ = shutdown.requested() => {
+                        info!("Waiting up to {SHUTDOWN_GRACE:?} for running jobs to finish");
+                        tokio::time::timeout(SHUTDOWN_GRACE, poll).await.ok()
+                    }
+                }
+            };
+            match polled {
+                Some(Ok(report)) if report.ran().next().is_some() => info!("Polled: {report}"),
+                Some(Ok(_)) => (),
+                Some(Err(err)) => return Err(err),
+                None => warn!("Running jobs did not finish in time, cancelled them"),
+            }
+        }
+    }
+
+    /// Runs the job with the given name right away, regardless of its schedule. When
+    /// no such job was added, a job of the kind with that name (and its default
+    /// configuration) is ran instead.
+    pub async fn run_once(&mut self, name: &str) -> Result<()> {
+        if !self.joblist.iter().any(|job| job.name == name) {
+            let jobkind = JobKind::from_definition(name, no_params()).with_context(|| {
+                let names: Vec<&str> = self.joblist.iter().map(|job| job.name.as_str()).collect();
+                format!("No job named {name}, known jobs are {}", names.join(", "))
+            })?;
+            self.joblist.push(Job::new(
+                name.to_string(),
+                jobkind,
+                Schedule::Interval(Duration::MAX),
+                self.pool.clone(),
+                &self.clients,
+            ));
+        }
+        let webhooks = self.all_webhooks();
+        let context = self.context();
+        let job = self
+            .joblist
+            .iter_mut()
+            .find(|job| job.name == name)
+            .expect("The job was just added");
+        let result = job.run(&webhooks, &context).await;
+        record_status(&self.statuses, &job.name, &result);
+        if let Err(err) = persist_run(&self.pool, job, self.dry_run).await {
+            warn!(job = job.name, "Could not persist the run: {err}");
+        }
+        result
+    }
+
+    /// Only keep the job with the given name, returning whether it exists
+    pub fn retain_only(&mut self, name: &str) -> bool {
+        self.joblist.retain(|job| job.name == name);
+        !self.joblist.is_empty()
+    }
+
+    pub fn summaries(&self) -> Vec<JobSummary> {
+        self.joblist
+            .iter()
+            .map(|job| JobSummary {
+                name: job.name.clone(),
+                kind: job.job_runner.kind(),
+                schedule: job.schedule.to_string(),
+                last_ran: job.last_ran,
+                next_run: job.next_run(),
+            })
+            .collect()
+    }
+
+    /// Handle to the statuses of the jobs, which stays up to date while polling
+    pub fn statuses(&self) -> JobStatuses {
+        self.statuses.clone()
+    }
+
+    pub fn pool(&self) -> PgPool {
+        self.pool.clone()
+    }
+
+    /// Handle with which a shutdown of the jobs can be requested
+    pub fn shutdown_handle(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    /// Gives up leadership and closes all database connections, waiting for
+    /// connections which are still in use to be returned
+    pub async fn close(mut self) {
+        if let Some(leader) = &mut self.leader {
+            leader.step_down().await;
+        }
+        for pool in self.tenant_pools.values() {
+            pool.close().await;
+        }
+        self.pool.close().await;
     }
 }
@@ -1,27 +1,185 @@
 use std::collections::{HashMap, HashSet};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
-use crate::job::matching::best_rt_hit;
-use crate::job::util::JsonDecodeError;
+use std::time::Duration;
 
-use super::{Runnable, util::Client};
+use crate::job::anomaly::{VolumeAnomaly, check_deltas, check_volume};
+use crate::job::archive::Archive;
+use crate::job::assets::AssetStore;
+use crate::job::delta::RunDeltas;
+use crate::job::failed::FailedFetches;
+use crate::job::matching::{
+    best_rt_hit, normalize_title, rating_skip_reason, rt_hit_score, similar_titles, title_language,
+};
+use crate::job::notify::NotifyConfig;
+use crate::job::parse::parse_price;
+use crate::job::queue::TaskQueue;
+use crate::job::snapshot::finish_run;
+use crate::job::tmdb::{
+    TmdbRating, TmdbRatingInserter, TmdbRatingShow, TmdbRatingShowInserter, fetch_tmdb_rating,
+};
+use crate::job::util::{Clients, JsonDecodeError, VersionedEndpoint};
+use crate::job::validation::{Validation, ValidationConfig};
+
+use super::{RunContext, Runnable, util::Client};
 use anyhow::{Context, Result, bail};
-use chrono::{Datelike, NaiveDate};
-use serde::Deserialize;
-use sqlx::PgPool;
-use tokio::try_join;
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use provider::{CinemaProvider, PatheProvider};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgConnection, PgPool, postgres::PgQueryResult};
+use tokio::{sync::OnceCell, task::JoinSet, try_join};
+use tracing::{info, warn};
 
 use sqlx_batch::BatchInserter;
 
+mod demo;
+mod provider;
+pub use demo::seed_demo;
+
 static PATHE_DATE_FORMAT: &str = "%Y-%m-%d";
+static PATHE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
-#[derive(Deserialize, Debug, BatchInserter)]
+/// Time zone of the times listed by Pathé, which do not carry an offset
+pub const PATHE_TIMEZONE: Tz = chrono_tz::Europe::Amsterdam;
+
+/// Parses a time as listed by Pathé (`2024-03-01 20:15:00`), or one with an offset
+fn parse_pathe_time(time: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time) {
+        return Some(time);
+    }
+    let local = NaiveDateTime::parse_from_str(time, PATHE_TIME_FORMAT).ok()?;
+    PATHE_TIMEZONE
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|time| time.fixed_offset())
+}
+
+fn deserialize_pathe_time<'de, D>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let time = String::deserialize(deserializer)?;
+    parse_pathe_time(&time)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid time {time:?}")))
+}
+
+/// Parses the capacity of an auditorium, which Pathé lists as a string. Empty and
+/// "N/A" capacities are unknown, other values which are not a positive number are
+/// unknown as well but logged.
+fn parse_capacity(capacity: &str) -> Option<i32> {
+    let trimmed = capacity.trim();
+    if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("n/a") || trimmed == "-" {
+        return None;
+    }
+    match trimmed.parse() {
+        Ok(capacity) if capacity > 0 => Some(capacity),
+        _ => {
+            warn!("Ignoring invalid auditorium capacity {capacity:?}");
+            None
+        }
+    }
+}
+
+fn deserialize_capacity<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::String(capacity)) => parse_capacity(&capacity),
+            Some(serde_json::Value::Number(capacity)) => parse_capacity(&capacity.to_string()),
+            _ => None,
+        },
+    )
+}
+
+/// Ticket price in euro cents, listed as a number of euros or as text such as
+/// "€ 12,50". Missing and invalid prices are unknown.
+fn deserialize_price<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let cents = match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::Number(euros)) => {
+            euros.as_f64().map(|euros| (euros * 100.0).round() as i64)
+        }
+        Some(serde_json::Value::String(price)) if !price.trim().is_empty() => {
+            match parse_price(&price) {
+                Ok(price) if price.currency == "EUR" => Some(price.cents),
+                _ => {
+                    warn!("Ignoring invalid ticket price {price:?}");
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    Ok(cents
+        .filter(|cents| *cents >= 0)
+        .and_then(|cents| i32::try_from(cents).ok()))
+}
+
+/// Like `deserialize_pathe_time`, but missing and empty times are `None`
+fn deserialize_optional_pathe_time<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(time) if !time.is_empty() => parse_pathe_time(&time)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid time {time:?}"))),
+        _ => Ok(None),
+    }
+}
+
+#[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
-#[pgtable = "cinemas"]
 struct Cinema {
+    slug: String,
+    city_slug: String,
+    name: String,
+    gps_position: Option<GpsPosition>,
+}
+
+/// Location of a cinema, `x` being the longitude and `y` the latitude
+#[derive(Deserialize, Debug)]
+struct GpsPosition {
+    x: f64,
+    y: f64,
+}
+
+impl Cinema {
+    fn flatten(self) -> FlatCinema {
+        FlatCinema {
+            slug: self.slug,
+            city_slug: self.city_slug,
+            name: self.name,
+            latitude: self.gps_position.as_ref().map(|pos| pos.y),
+            longitude: self.gps_position.map(|pos| pos.x),
+            country_code: None,
+        }
+    }
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "cinemas"]
+struct FlatCinema {
     #[key]
     slug: String,
     city_slug: String,
     name: String,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    /// ISO 3166 code of the country of the site the cinema is listed on
+    country_code: Option<String>,
 }
 
 #[derive(Deserialize, Debug, BatchInserter)]
@@ -30,6 +188,8 @@ struct City {
     #[key]
     slug: String,
     name: String,
+    #[serde(default)]
+    country_code: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -43,32 +203,206 @@ struct Show {
     slug: String,
     title: String,
     release_at: Vec<String>,
-    poster_path: Option<Image>,
+    poster_path: Option<ImageVariants>,
+    #[serde(default)]
+    backdrop_path: Option<ImageVariants>,
+    #[serde(default)]
+    stills: Vec<ImageVariants>,
     #[serde(rename = "type")]
     movie_type: String,
     duration: i32,
     genres: Vec<String>,
+    #[serde(default)]
+    synopsis: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_names")]
+    directors: Vec<String>,
+    #[serde(default, alias = "cast", deserialize_with = "deserialize_names")]
+    actors: Vec<String>,
+    #[serde(
+        default,
+        alias = "kijkwijzer",
+        deserialize_with = "deserialize_lenient"
+    )]
+    content_rating: Option<ContentRating>,
+    #[serde(default, alias = "trailer", deserialize_with = "deserialize_trailers")]
+    trailers: Vec<String>,
+}
+
+/// Names of people, listed either as a comma separated string or as a list of names
+/// or objects with a name. Anything else is ignored.
+fn deserialize_names<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let names = match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::String(names)) => names.split(',').map(str::to_string).collect(),
+        Some(serde_json::Value::Array(names)) => names
+            .iter()
+            .filter_map(|name| {
+                name.as_str()
+                    .or_else(|| name.get("name").and_then(|name| name.as_str()))
+                    .or_else(|| name.get("fullName").and_then(|name| name.as_str()))
+                    .map(str::to_string)
+            })
+            .collect(),
+        _ => vec![],
+    };
+    Ok(names
+        .into_iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect())
+}
+
+/// URLs of trailers, listed either as a single URL or as a list of URLs or objects
+/// with a URL. Anything else is ignored.
+fn deserialize_trailers<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let url = |trailer: &serde_json::Value| {
+        ["url", "mediaUrl", "src"]
+            .iter()
+            .find_map(|key| trailer.get(key).and_then(|url| url.as_str()))
+            .or_else(|| trailer.as_str())
+            .filter(|url| url.starts_with("http"))
+            .map(str::to_string)
+    };
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::Array(trailers)) => trailers.iter().filter_map(url).collect(),
+            Some(trailer) => url(&trailer).into_iter().collect(),
+            None => vec![],
+        },
+    )
+}
+
+/// Decodes the value when it has the expected shape, otherwise it is ignored such
+/// that an unexpected optional field does not fail the whole payload
+fn deserialize_lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    Ok(Option::<serde_json::Value>::deserialize(deserializer)?
+        .and_then(|value| serde_json::from_value(value).ok()))
+}
+
+/// Cast, trailers and content warnings of a show as listed by a provider
+#[derive(Debug, Default)]
+struct ShowMetadata {
+    cast: Vec<CastMember>,
+    trailers: Vec<ShowTrailer>,
+    warnings: Vec<ContentWarning>,
+}
+
+impl ShowMetadata {
+    fn append(&mut self, other: ShowMetadata) {
+        self.cast.extend(other.cast);
+        self.trailers.extend(other.trailers);
+        self.warnings.extend(other.warnings);
+    }
+
+    /// Drops the content warnings listed by both the listing and the details of a
+    /// show, which may only occur once in an upsert
+    fn dedup(&mut self) {
+        self.warnings
+            .sort_by(|a, b| (&a.show_slug, &a.warning).cmp(&(&b.show_slug, &b.warning)));
+        self.warnings
+            .dedup_by(|a, b| (&a.show_slug, &a.warning) == (&b.show_slug, &b.warning));
+    }
 }
 
+/// A show as listed by a provider, together with its images, genres, release dates
+/// and metadata
+type ListedShow = (
+    FlatShow,
+    Vec<ShowImage>,
+    Vec<Genre>,
+    Vec<ShowReleaseDate>,
+    ShowMetadata,
+);
+
 impl Show {
-    fn flatten(self) -> (FlatShow, Poster, Vec<Genre>) {
+    fn flatten(self) -> ListedShow {
+        let images = [
+            ("poster", self.poster_path),
+            ("backdrop", self.backdrop_path),
+        ]
+        .into_iter()
+        .filter_map(|(image_type, variants)| Some((image_type, 0, variants?)))
+        .chain(
+            self.stills
+                .into_iter()
+                .enumerate()
+                .map(|(position, variants)| ("still", position as i32, variants)),
+        )
+        .flat_map(|(image_type, position, variants)| {
+            ShowImage::from_variants(&self.slug, image_type, position, variants)
+        })
+        .collect();
+        let release_dates: Vec<ShowReleaseDate> = self
+            .release_at
+            .iter()
+            .filter_map(
+                |date| match NaiveDate::parse_from_str(date, PATHE_DATE_FORMAT) {
+                    Ok(release_date) => Some(ShowReleaseDate {
+                        show_slug: self.slug.clone(),
+                        release_date,
+                    }),
+                    Err(_) => {
+                        warn!(show = self.slug, "Ignoring invalid release date {date:?}");
+                        None
+                    }
+                },
+            )
+            .collect();
+        let metadata = ShowMetadata {
+            cast: self
+                .actors
+                .into_iter()
+                .enumerate()
+                .map(|(position, name)| CastMember {
+                    show_slug: self.slug.clone(),
+                    position: position as i32,
+                    name,
+                })
+                .collect(),
+            trailers: self
+                .trailers
+                .into_iter()
+                .enumerate()
+                .map(|(position, url)| ShowTrailer {
+                    show_slug: self.slug.clone(),
+                    position: position as i32,
+                    url,
+                })
+                .collect(),
+            warnings: self
+                .content_rating
+                .as_ref()
+                .map(|content_rating| content_rating.warnings(&self.slug))
+                .unwrap_or_default(),
+        };
         (
             FlatShow {
                 slug: self.slug.clone(),
+                title_language: title_language(&self.title).map(str::to_string),
+                rating_skip_reason: rating_skip_reason(&self.movie_type, &self.title),
                 title: self.title,
-                release_at: self.release_at.into_iter().next().map(|date_str| {
-                    NaiveDate::parse_from_str(&date_str, PATHE_DATE_FORMAT).unwrap()
-                }),
+                release_at: release_dates.first().map(|date| date.release_date),
                 duration: self.duration,
                 movie_type: self.movie_type,
                 rating_slug: None,
                 rating_match_score: None,
+                original_title: None,
+                synopsis: self.synopsis.filter(|synopsis| !synopsis.is_empty()),
+                age_rating: self
+                    .content_rating
+                    .and_then(|content_rating| content_rating.age()),
+                directors: (!self.directors.is_empty()).then(|| self.directors.join(", ")),
             },
-            Poster {
-                show_slug: self.slug.clone(),
-                lg: self.poster_path.clone().map(|p| p.lg),
-                md: self.poster_path.map(|p| p.md),
-            },
+            images,
             self.genres.into_iter().fold(Vec::new(), |mut acc, elem| {
                 acc.push(Genre {
                     show_slug: self.slug.clone(),
@@ -76,6 +410,8 @@ impl Show {
                 });
                 acc
             }),
+            release_dates,
+            metadata,
         )
     }
 }
@@ -91,53 +427,396 @@ struct FlatShow {
     duration: i32,
     rating_slug: Option<String>,
     rating_match_score: Option<f64>,
+    original_title: Option<String>,
+    title_language: Option<String>,
+    /// Why the show is not matched against the rating sources
+    rating_skip_reason: Option<String>,
+    synopsis: Option<String>,
+    /// Kijkwijzer age rating, e.g. `AL` or `12`
+    age_rating: Option<String>,
+    /// Comma separated names of the directors
+    directors: Option<String>,
 }
 
 #[derive(Debug, BatchInserter)]
-#[pgtable = "posters"]
-struct Poster {
+#[pgtable = "show_cast"]
+struct CastMember {
     #[key]
     show_slug: String,
-    lg: Option<String>,
-    md: Option<String>,
+    /// Order in which the cast is credited
+    #[key]
+    position: i32,
+    name: String,
 }
 
 #[derive(Debug, BatchInserter)]
-#[pgtable = "genres"]
-struct Genre {
+#[pgtable = "show_trailers"]
+struct ShowTrailer {
+    #[key]
+    show_slug: String,
+    #[key]
+    position: i32,
+    url: String,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "content_warnings"]
+struct ContentWarning {
+    #[key]
+    show_slug: String,
+    #[key]
+    warning: String,
+}
+
+/// Links a show to another slug under which the same film is listed
+#[derive(Debug, BatchInserter)]
+#[pgtable = "show_aliases"]
+struct ShowAlias {
+    #[key]
+    alias_slug: String,
+    canonical_slug: String,
+}
+
+impl FlatShow {
+    /// Whether both shows are (most likely) the same film, e.g. an "(OV)" variant
+    fn is_alias_of(&self, other: &FlatShow) -> bool {
+        let release_gap = match (self.release_at, other.release_at) {
+            (Some(a), Some(b)) => (a - b).num_days().abs(),
+            _ => 0,
+        };
+        (self.duration - other.duration).abs() <= 5
+            && release_gap <= 31
+            && similar_titles(&self.title, &other.title)
+    }
+}
+
+/// Detects shows listed under multiple slugs. The best matched show of every group
+/// becomes the canonical one, of which the rating is propagated to its aliases.
+fn link_aliases(show_map: &mut HashMap<String, FlatShow>) -> Vec<ShowAlias> {
+    let mut shows: Vec<&FlatShow> = show_map.values().collect();
+    shows.sort_by(|a, b| {
+        a.rating_match_score
+            .is_none()
+            .cmp(&b.rating_match_score.is_none())
+            .then(
+                a.rating_match_score
+                    .partial_cmp(&b.rating_match_score)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+            .then((a.title.len(), &a.slug).cmp(&(b.title.len(), &b.slug)))
+    });
+
+    let mut canonicals: Vec<&FlatShow> = vec![];
+    let mut aliases = vec![];
+    for show in shows {
+        match canonicals
+            .iter()
+            .find(|canonical| show.is_alias_of(canonical))
+        {
+            Some(canonical) => aliases.push(ShowAlias {
+                alias_slug: show.slug.clone(),
+                canonical_slug: canonical.slug.clone(),
+            }),
+            None => canonicals.push(show),
+        }
+    }
+
+    for alias in &aliases {
+        let canonical = &show_map[&alias.canonical_slug];
+        if let Some(rating_slug) = canonical.rating_slug.clone() {
+            let score = canonical.rating_match_score;
+            let show = show_map.get_mut(&alias.alias_slug).unwrap();
+            show.rating_slug = Some(rating_slug);
+            show.rating_match_score = score;
+        }
+    }
+    aliases
+}
+
+/// Size variants of an image by name (e.g. `md` or `lg`) as listed by Pathé
+type ImageVariants = HashMap<String, serde_json::Value>;
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "images"]
+struct ShowImage {
     #[key]
     show_slug: String,
+    /// Either `poster`, `backdrop` or `still`
     #[key]
+    image_type: String,
+    /// Order of the image amongst the images of the same type
+    #[key]
+    position: i32,
+    #[key]
+    variant: String,
+    url: String,
+    width: Option<i32>,
+    height: Option<i32>,
+}
+
+impl ShowImage {
+    fn from_variants(
+        show_slug: &str,
+        image_type: &str,
+        position: i32,
+        variants: ImageVariants,
+    ) -> Vec<ShowImage> {
+        variants
+            .into_iter()
+            .filter_map(|(variant, url)| {
+                let url = url.as_str()?.to_string();
+                let (width, height) = image_dimensions(&url).unzip();
+                Some(ShowImage {
+                    show_slug: show_slug.to_string(),
+                    image_type: image_type.to_string(),
+                    position,
+                    variant,
+                    url,
+                    width,
+                    height,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Dimensions of an image when its URL contains them, e.g. `.../300x450/poster.jpg`
+fn image_dimensions(url: &str) -> Option<(i32, i32)> {
+    url.split(['/', '_', '-', '.', '?', '=']).find_map(|part| {
+        let (width, height) = part.split_once('x')?;
+        Some((width.parse().ok()?, height.parse().ok()?))
+    })
+}
+
+/// A genre of a show as listed by the provider, e.g. `Actie`
+#[derive(Debug)]
+struct Genre {
+    show_slug: String,
     genre: String,
 }
 
+/// Links the shows to the canonical genres their listed labels map to through
+/// `genre_mappings`. Labels without a mapping become a genre of their own, which can
+/// be remapped to a canonical genre afterwards.
+async fn store_genres(
+    conn: &mut PgConnection,
+    genres: Vec<Genre>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    let (show_slugs, labels): (Vec<String>, Vec<String>) = genres
+        .into_iter()
+        .map(|genre| (genre.show_slug, genre.genre))
+        .unzip();
+    sqlx::query(
+        r#"INSERT INTO genres (slug, name)
+        SELECT DISTINCT ON (slugify(l.label)) slugify(l.label), trim(l.label)
+        FROM UNNEST($1::text[]) AS l (label)
+        WHERE slugify(l.label) <> ''
+            AND NOT EXISTS (SELECT 1 FROM genre_mappings m WHERE m.label = lower(trim(l.label)))
+        ON CONFLICT (slug) DO NOTHING"#,
+    )
+    .bind(&labels)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO genre_mappings (label, genre_slug)
+        SELECT DISTINCT lower(trim(l.label)), slugify(l.label)
+        FROM UNNEST($1::text[]) AS l (label)
+        WHERE slugify(l.label) <> ''
+        ON CONFLICT (label) DO NOTHING"#,
+    )
+    .bind(&labels)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO show_genres (show_slug, genre_slug)
+        SELECT DISTINCT l.show_slug, m.genre_slug
+        FROM UNNEST($1::text[], $2::text[]) AS l (show_slug, label)
+        JOIN genre_mappings m ON m.label = lower(trim(l.label))
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(show_slugs)
+    .bind(labels)
+    .execute(conn)
+    .await
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "show_release_dates"]
+struct ShowReleaseDate {
+    #[key]
+    show_slug: String,
+    #[key]
+    release_date: NaiveDate,
+}
+
 #[derive(Deserialize, Debug)]
 struct CinemaShows {
     shows: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-struct Image {
-    lg: String,
-    md: String,
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Showtime {
+    show_slug: Option<String>,
+    cinema_slug: Option<String>,
+    #[serde(deserialize_with = "deserialize_pathe_time")]
+    time: DateTime<FixedOffset>,
+    #[serde(rename = "refCmd")]
+    reservation_url: String,
+    auditorium_name: String,
+    #[serde(default, deserialize_with = "deserialize_capacity")]
+    auditorium_capacity: Option<i32>,
+    #[serde(default, deserialize_with = "deserialize_optional_pathe_time")]
+    end_time: Option<DateTime<FixedOffset>>,
+    /// Price of a regular ticket, in euro cents
+    #[serde(default, rename = "price", deserialize_with = "deserialize_price")]
+    price_cents: Option<i32>,
+    /// Surcharge on top of the regular price for 3D, in euro cents
+    #[serde(
+        default,
+        rename = "surcharge3d",
+        deserialize_with = "deserialize_price"
+    )]
+    surcharge_3d_cents: Option<i32>,
+    /// Surcharge on top of the regular price for IMAX, in euro cents
+    #[serde(
+        default,
+        rename = "surchargeImax",
+        deserialize_with = "deserialize_price"
+    )]
+    surcharge_imax_cents: Option<i32>,
+    /// Language versions and formats such as `ov`, `nl` or `imax`, lowercased
+    #[serde(default, alias = "labels", deserialize_with = "deserialize_tags")]
+    tags: Vec<String>,
 }
 
-#[derive(Deserialize, Debug, BatchInserter)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, BatchInserter)]
 #[pgtable = "showtimes"]
-struct Showtime {
+struct FlatShowtime {
     #[key]
     show_slug: Option<String>,
     #[key]
     cinema_slug: Option<String>,
     #[key]
-    time: String,
-    #[serde(rename = "refCmd")]
+    time: DateTime<FixedOffset>,
     reservation_url: String,
     #[key]
     auditorium_name: String,
-    auditorium_capacity: String,
-    end_time: String,
+    auditorium_capacity: Option<i32>,
+    end_time: Option<DateTime<FixedOffset>>,
+    price_cents: Option<i32>,
+    surcharge_3d_cents: Option<i32>,
+    surcharge_imax_cents: Option<i32>,
+}
+
+impl Showtime {
+    /// Columns of the primary key of the showtime
+    fn key(&self) -> (Option<&str>, Option<&str>, DateTime<FixedOffset>, &str) {
+        (
+            self.show_slug.as_deref(),
+            self.cinema_slug.as_deref(),
+            self.time,
+            &self.auditorium_name,
+        )
+    }
+
+    fn flatten(self) -> FlatShowtime {
+        FlatShowtime {
+            show_slug: self.show_slug,
+            cinema_slug: self.cinema_slug,
+            time: self.time,
+            reservation_url: self.reservation_url,
+            auditorium_name: self.auditorium_name,
+            auditorium_capacity: self.auditorium_capacity,
+            end_time: self.end_time,
+            price_cents: self.price_cents,
+            surcharge_3d_cents: self.surcharge_3d_cents,
+            surcharge_imax_cents: self.surcharge_imax_cents,
+        }
+    }
+}
+
+/// Tags of a showtime, listed as strings or objects with a name, label or code.
+/// Anything else is ignored.
+fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let Some(serde_json::Value::Array(tags)) = Option::deserialize(deserializer)? else {
+        return Ok(vec![]);
+    };
+    let mut tags: Vec<String> = tags
+        .iter()
+        .filter_map(|tag| {
+            ["name", "label", "code"]
+                .iter()
+                .find_map(|key| tag.get(key).and_then(|tag| tag.as_str()))
+                .or_else(|| tag.as_str())
+        })
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+/// Upserts the showtimes, replacing the tags they had
+async fn store_showtimes(
+    conn: &mut PgConnection,
+    showtimes: Vec<Showtime>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    // Columns of the keys of the showtimes, and of their tags
+    let mut keys = (vec![], vec![], vec![], vec![]);
+    let mut tags = (vec![], vec![], vec![], vec![], vec![]);
+    let mut flat = Vec::with_capacity(showtimes.len());
+    for showtime in showtimes {
+        for tag in &showtime.tags {
+            tags.0.push(showtime.show_slug.clone());
+            tags.1.push(showtime.cinema_slug.clone());
+            tags.2.push(showtime.time);
+            tags.3.push(showtime.auditorium_name.clone());
+            tags.4.push(tag.clone());
+        }
+        keys.0.push(showtime.show_slug.clone());
+        keys.1.push(showtime.cinema_slug.clone());
+        keys.2.push(showtime.time);
+        keys.3.push(showtime.auditorium_name.clone());
+        flat.push(showtime.flatten());
+    }
+
+    let result = FlatShowtimeInserter::from(flat)
+        .build()
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        r#"DELETE FROM showtime_tags t
+        USING UNNEST($1::text[], $2::text[], $3::timestamptz[], $4::text[])
+            AS fetched(show_slug, cinema_slug, time, auditorium_name)
+        WHERE fetched.show_slug = t.show_slug
+            AND fetched.cinema_slug = t.cinema_slug
+            AND fetched.time = t.time
+            AND fetched.auditorium_name = t.auditorium_name"#,
+    )
+    .bind(keys.0)
+    .bind(keys.1)
+    .bind(keys.2)
+    .bind(keys.3)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO showtime_tags (show_slug, cinema_slug, time, auditorium_name, tag)
+        SELECT * FROM UNNEST($1::text[], $2::text[], $3::timestamptz[], $4::text[], $5::text[])
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(tags.0)
+    .bind(tags.1)
+    .bind(tags.2)
+    .bind(tags.3)
+    .bind(tags.4)
+    .execute(conn)
+    .await?;
+    Ok(result)
 }
 
 #[derive(Debug, Deserialize)]
@@ -150,7 +829,18 @@ struct RTResult {
     hits: Vec<RTHit>,
 }
 
-#[derive(Debug, Deserialize)]
+impl RTResponse {
+    fn into_hits(self) -> Result<Vec<RTHit>> {
+        Ok(self
+            .results
+            .into_iter()
+            .next()
+            .context("RTResponse should always return something")?
+            .hits)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RTHit {
     pub title: String,
@@ -160,7 +850,7 @@ pub struct RTHit {
     pub rotten_tomatoes: Option<RTRating>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RTRating {
     audience_score: Option<i32>,
@@ -185,215 +875,2501 @@ pub struct Rating {
     critics_score: Option<i32>,
     certified_fresh: Option<bool>,
     new_adjusted_tm_score: Option<i32>,
+    fetched_at: DateTime<Utc>,
 }
 
-async fn fetch_cinema_shows(client: Client, cinema_slug: String) -> Result<Vec<String>> {
-    let shows: CinemaShows = client
-        .get_json(format!(
-            "https://www.pathe.nl/api/cinema/{}/shows?language=nl",
-            cinema_slug.clone()
-        ))
-        .await?;
-    Ok(shows.shows.into_keys().collect())
+/// Show for which no rating hit scored within the configured threshold
+#[derive(Debug, BatchInserter)]
+#[pgtable = "unmatched_ratings"]
+pub struct UnmatchedRating {
+    #[key]
+    show_slug: String,
+    /// Title which was searched for
+    title: String,
+    year: Option<i32>,
+    /// Closest hit, `None` when the search had no hits at all
+    best_title: Option<String>,
+    best_score: Option<f64>,
+    checked_at: DateTime<Utc>,
 }
 
-async fn fetch_showtimes(
-    client: Client,
-    show_slug: String,
-    cinema_slug: String,
-) -> Result<Vec<Showtime>> {
-    let request_url =
-        format!("https://www.pathe.nl/api/show/{show_slug}/showtimes/{cinema_slug}?language=nl");
-    let showtimes: HashMap<String, Vec<Showtime>> = match client.get_json(&request_url).await {
-        Ok(res) => res,
-        Err(JsonDecodeError::DecodeError(_)) => HashMap::default(),
-        Err(JsonDecodeError::NetworkError(err)) => bail!(err),
-    };
-    Ok(showtimes
-        .into_values()
-        .flatten()
-        .map(|mut showtime| {
-            showtime.show_slug = Some(show_slug.clone());
-            showtime.cinema_slug = Some(cinema_slug.clone());
-            showtime
-        })
-        .collect())
+impl From<RTHit> for Rating {
+    fn from(hit: RTHit) -> Self {
+        let rt = hit.rotten_tomatoes.as_ref();
+        Rating {
+            slug: hit.vanity,
+            title: hit.title,
+            description: hit.description,
+            release_year: hit.release_year,
+            audience_score: rt.and_then(|rt| rt.audience_score),
+            score_sentiment: rt.and_then(|rt| rt.score_sentiment.clone()),
+            certified_fresh: rt.and_then(|rt| rt.certified_fresh),
+            want_to_see_count: rt.and_then(|rt| rt.want_to_see_count),
+            critics_score: rt.and_then(|rt| rt.critics_score),
+            new_adjusted_tm_score: rt.and_then(|rt| rt.new_adjusted_TM_score),
+            fetched_at: Utc::now(),
+        }
+    }
 }
 
-async fn fetch_showtimes_cinema(client: Client, cinema: String) -> Result<Vec<Showtime>> {
-    let mut handles = vec![];
-    let shows = fetch_cinema_shows(client.clone(), cinema.clone()).await?;
-    for show_slug in shows {
-        handles.push(tokio::spawn(fetch_showtimes(
-            client.clone(),
+impl UnmatchedRating {
+    fn new(
+        show_slug: String,
+        title: String,
+        year: Option<i32>,
+        best: Option<(RTHit, f64)>,
+    ) -> Self {
+        let (best_title, best_score) = best.map(|(hit, score)| (hit.title, score)).unzip();
+        UnmatchedRating {
             show_slug,
-            cinema.clone(),
-        )));
-    }
-    let mut res = vec![];
-    for handle in handles {
-        let mut showtimes = handle.await??;
-        res.append(&mut showtimes);
+            title,
+            year,
+            best_title,
+            best_score,
+            checked_at: Utc::now(),
+        }
     }
-    Ok(res)
 }
 
-async fn fetch_rt_data(client: Client, title: String) -> Result<RTResponse> {
-    Ok(client.get_json_post("https://79frdp12pn-dsn.algolia.net/1/indexes/*/queries?x-algolia-agent=Algolia%20for%20JavaScript%20(4.24.0)%3B%20Browser%20(lite)&x-algolia-api-key=175588f6e5f8319b27702e4cc4013561&x-algolia-application-id=79FRDP12PN", 
-    serde_json::json!({
-		"requests": [
-			{
-				"indexName": "content_rt",
-				"query": title,
-				"params": "filters=isEmsSearchable%20%3D%201&hitsPerPage=5"
-			}
-		]
-	})).await?)
+/// Outcome of matching a show against Rotten Tomatoes
+#[derive(Debug)]
+pub enum RatingMatch {
+    Matched(Rating, f64),
+    Unmatched(UnmatchedRating),
+    /// The show is overridden to never have a rating
+    Suppressed,
 }
 
-pub async fn fetch_show_rating(
-    client: Client,
-    show_slug: String,
-    title: String,
-    year: Option<i32>,
-) -> Result<Option<(Rating, (String, f64))>> {
-    // TODO: NORMALIZE TITLE HERE BY REMOVING EVERYTHING BETWEEN PARENTHESES
-    let rt_response = fetch_rt_data(client.clone(), title.clone()).await?;
-    let best_hit = best_rt_hit(
-        rt_response
-            .results
-            .into_iter()
-            .next()
-            .context("RTResponse should always return something")?
-            .hits,
-        title.clone(),
-        year,
-    );
-
-    Ok(best_hit.map(|(hit, match_score)| {
-        (
-            Rating {
-                slug: hit.vanity.clone(),
-                title: hit.title,
-                description: hit.description,
-                release_year: hit.release_year,
-                audience_score: hit
-                    .rotten_tomatoes
-                    .as_ref()
-                    .and_then(|rt| rt.audience_score),
-                score_sentiment: hit
-                    .rotten_tomatoes
-                    .as_ref()
-                    .and_then(|rt| rt.score_sentiment.clone()),
-                certified_fresh: hit
-                    .rotten_tomatoes
-                    .as_ref()
-                    .and_then(|rt| rt.certified_fresh),
-                want_to_see_count: hit
-                    .rotten_tomatoes
-                    .as_ref()
-                    .and_then(|rt| rt.want_to_see_count),
-                critics_score: hit.rotten_tomatoes.as_ref().and_then(|rt| rt.critics_score),
-                new_adjusted_tm_score: hit
-                    .rotten_tomatoes
-                    .as_ref()
-                    .and_then(|rt| rt.new_adjusted_TM_score),
-            },
-            (show_slug, match_score),
-        )
-    }))
+/// Rating of a show fixed by hand in `rating_overrides`
+#[derive(Debug, Clone, PartialEq)]
+pub enum RatingOverride {
+    Pinned(String),
+    NoMatch,
 }
 
-#[derive(Debug)]
-pub struct MovieFetcher {
-    pub pool: PgPool,
+/// Loads all overrides by show slug, the table is maintained by hand so it is small
+async fn load_rating_overrides(pool: &PgPool) -> Result<HashMap<String, RatingOverride>> {
+    let overrides: Vec<(String, String)> =
+        sqlx::query_as("SELECT show_slug, rating_slug FROM rating_overrides")
+            .fetch_all(pool)
+            .await?;
+    Ok(overrides
+        .into_iter()
+        .map(|(show_slug, rating_slug)| match rating_slug.as_str() {
+            "no_match" => (show_slug, RatingOverride::NoMatch),
+            _ => (show_slug, RatingOverride::Pinned(rating_slug)),
+        })
+        .collect())
 }
-impl Runnable for MovieFetcher {
-    async fn run(&self) -> Result<()> {
-        let client = Client::new().with_limit(10.try_into()?).with_max_retries(3);
-        let rt_client = Client::new().with_limit(10.try_into()?).with_max_retries(3);
-        // Create inserters
-        let mut posterinserter = PosterInserter::new();
-        let mut genreinserter = GenreInserter::new();
-        let mut ratinginserter = RatingInserter::new();
-
-        // Fetch some basic information
-        let (cinemas, cities, shows): (Vec<Cinema>, Vec<City>, Shows) = try_join!(
-            client.get_json("https://www.pathe.nl/api/cinemas?language=nl"),
-            client.get_json("https://www.pathe.nl/api/cities?language=nl"),
-            client.get_json("https://www.pathe.nl/api/shows?language=nl")
-        )?;
 
-        let mut show_map: HashMap<String, FlatShow> = HashMap::new();
-        let mut rating_handles = vec![];
-        for (show, poster, genres) in shows.shows.into_iter().map(|show| show.flatten()) {
-            rating_handles.push(tokio::spawn(fetch_show_rating(
-                rt_client.clone(),
-                show.slug.clone(),
-                show.title.clone(),
-                show.release_at.map(|date| date.year()),
-            )));
-            show_map.insert(show.slug.clone(), show);
-            posterinserter.add(poster);
-            for genre in genres {
-                genreinserter.add(genre);
+/// Ratings matched during earlier runs which are still fresh, by show slug
+#[derive(Debug, Default)]
+struct RatingCache(HashMap<String, (String, String, Option<f64>)>);
+
+impl RatingCache {
+    /// Loads the ratings fetched less than `hours` ago, nothing is cached without
+    /// `hours`. Overridden shows are never cached, such that overrides apply at once.
+    async fn load(pool: &PgPool, hours: Option<u64>) -> Result<Self> {
+        let Some(hours) = hours else {
+            return Ok(RatingCache::default());
+        };
+        let cached: Vec<(String, String, String, Option<f64>)> = sqlx::query_as(
+            r#"SELECT s.slug, s.title, s.rating_slug, s.rating_match_score FROM shows s
+            JOIN ratings r ON r.slug = s.rating_slug
+            WHERE r.fetched_at > current_timestamp - make_interval(hours => $1)
+                AND NOT EXISTS (SELECT 1 FROM rating_overrides o WHERE o.show_slug = s.slug)"#,
+        )
+        .bind(hours as i32)
+        .fetch_all(pool)
+        .await?;
+        Ok(RatingCache(
+            cached
+                .into_iter()
+                .map(|(show_slug, title, rating_slug, score)| {
+                    (show_slug, (title, rating_slug, score))
+                })
+                .collect(),
+        ))
+    }
+
+    /// Links the cached rating when the show was matched under the same title,
+    /// returning whether it did such that the show is not matched again
+    fn link(&self, show: &mut FlatShow) -> bool {
+        match self.0.get(&show.slug) {
+            Some((title, rating_slug, score))
+                if *title == show.title && show.rating_skip_reason.is_none() =>
+            {
+                show.rating_slug = Some(rating_slug.clone());
+                show.rating_match_score = *score;
+                true
             }
+            _ => false,
         }
+    }
+}
 
-        // Fetch showtimes
-        let mut showtimes = vec![];
-        let mut handles = vec![];
-        for cinema in cinemas.iter().map(|cinema| cinema.slug.clone()) {
-            handles.push(tokio::spawn(fetch_showtimes_cinema(client.clone(), cinema)));
-        }
+/// Removes the shows which no longer need matching from `unmatched_ratings` and
+/// stores the ones which could not be matched
+async fn record_rating_matches(
+    conn: &mut PgConnection,
+    resolved: Vec<String>,
+    unmatched: Vec<UnmatchedRating>,
+) -> Result<()> {
+    sqlx::query("DELETE FROM unmatched_ratings WHERE show_slug = ANY($1)")
+        .bind(resolved)
+        .execute(&mut *conn)
+        .await?;
+    UnmatchedRatingInserter::from(unmatched)
+        .build()
+        .execute(conn)
+        .await?;
+    Ok(())
+}
 
-        // Join spawned tasks for showtimes
-        for handle in handles {
-            let mut cinema_showtimes = handle.await??;
-            showtimes.append(&mut cinema_showtimes);
-        }
+/// Upstream payloads decoded by the movie jobs, such that captured payloads can be
+/// validated against the structs they are decoded into
+#[derive(Debug, Clone, Copy)]
+pub enum Payload {
+    Cinemas,
+    Cities,
+    Shows,
+    CinemaShows,
+    Showtimes,
+    RtSearch,
+}
 
-        // Join spawned tasks for ratings
-        let mut inserted_ratings = HashSet::new();
-        for handle in rating_handles {
-            let rating = handle.await??;
-            if let Some((rating, (show_slug, match_score))) = rating {
-                show_map.get_mut(&show_slug).unwrap().rating_slug = Some(rating.slug.clone());
-                show_map.get_mut(&show_slug).unwrap().rating_match_score = Some(match_score);
-                if !inserted_ratings.contains(&rating.slug) {
-                    inserted_ratings.insert(rating.slug.clone());
-                    ratinginserter.add(rating);
-                }
-            }
+impl Payload {
+    /// Payload kind by its name, e.g. the directory captured payloads are stored in
+    pub fn from_name(name: &str) -> Option<Payload> {
+        match name {
+            "cinemas" => Some(Payload::Cinemas),
+            "cities" => Some(Payload::Cities),
+            "shows" => Some(Payload::Shows),
+            "cinema_shows" => Some(Payload::CinemaShows),
+            "showtimes" => Some(Payload::Showtimes),
+            "rt_search" => Some(Payload::RtSearch),
+            _ => None,
         }
+    }
 
-        CityInserter::from(cities)
-            .build()
-            .execute(&self.pool)
-            .await?;
+    /// Decodes and flattens a payload the way a run does, returning the amount of
+    /// records it contained
+    pub fn decode(self, json: &str) -> Result<usize> {
+        Ok(match self {
+            Payload::Cinemas => serde_json::from_str::<Vec<Cinema>>(json)?
+                .into_iter()
+                .map(Cinema::flatten)
+                .count(),
+            Payload::Cities => serde_json::from_str::<Vec<City>>(json)?.len(),
+            Payload::Shows => serde_json::from_str::<Shows>(json)?
+                .shows
+                .into_iter()
+                .map(Show::flatten)
+                .count(),
+            Payload::CinemaShows => serde_json::from_str::<CinemaShows>(json)?.shows.len(),
+            Payload::Showtimes => serde_json::from_str::<HashMap<String, Vec<Showtime>>>(json)?
+                .values()
+                .map(Vec::len)
+                .sum(),
+            Payload::RtSearch => serde_json::from_str::<RTResponse>(json)?
+                .results
+                .into_iter()
+                .map(|result| result.hits.len())
+                .sum(),
+        })
+    }
+}
 
-        CinemaInserter::from(cinemas)
-            .build()
-            .execute(&self.pool)
-            .await?;
+/// Returns whether a date key (as used by the Pathé API listings) falls within
+/// the horizon. Keys which cannot be parsed as a date are always kept.
+fn within_horizon(date_key: &str, until: Option<NaiveDate>) -> bool {
+    match (until, NaiveDate::parse_from_str(date_key, PATHE_DATE_FORMAT)) {
+        (Some(until), Ok(date)) => date <= until,
+        _ => true,
+    }
+}
 
-        ratinginserter.build().execute(&self.pool).await?;
-        FlatShowInserter::from(show_map.into_values().collect())
-            .build()
-            .execute(&self.pool)
-            .await?;
-        posterinserter.build().execute(&self.pool).await?;
-        genreinserter.build().execute(&self.pool).await?;
+/// Country site of Pathé, such as `https://www.pathe.fr`, and the language in which
+/// its API is queried
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatheSite {
+    base_url: String,
+    language: String,
+    country_code: String,
+}
 
-        ShowtimeInserter::from(showtimes)
-            .build()
-            .execute(&self.pool)
+impl PatheSite {
+    /// Site at `base_url`, of which the country and default language follow from the
+    /// domain: pathe.nl and pathe.be are Dutch, pathe.fr is French and pathe.ch is
+    /// German. Unknown domains, e.g. of a mock server, are treated as pathe.nl.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        let host = base_url
+            .split_once("://")
+            .map_or(base_url.as_str(), |(_, rest)| rest)
+            .split(['/', ':'])
+            .next()
+            .unwrap_or_default();
+        let (language, country_code) = match host.rsplit('.').next() {
+            Some("be") => ("nl", "BE"),
+            Some("fr") => ("fr", "FR"),
+            Some("ch") => ("de", "CH"),
+            _ => ("nl", "NL"),
+        };
+        PatheSite {
+            base_url,
+            language: language.to_string(),
+            country_code: country_code.to_string(),
+        }
+    }
+
+    /// Queries the API in `language` instead of the default language of the site,
+    /// e.g. `fr` for the French speaking cinemas on pathe.ch
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = language.into();
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// ISO 3166 code of the country of the site, stored with the cities and cinemas
+    pub fn country_code(&self) -> &str {
+        &self.country_code
+    }
+
+    /// URL of `path` in the localized API
+    pub fn api_url(&self, path: &str) -> String {
+        format!("{}/api/{path}?language={}", self.base_url, self.language)
+    }
+
+    /// Marks the cities and cinemas with the country of the site
+    fn locate(&self, cities: &mut [City], cinemas: &mut [FlatCinema]) {
+        for city in cities {
+            city.country_code = Some(self.country_code.clone());
+        }
+        for cinema in cinemas {
+            cinema.country_code = Some(self.country_code.clone());
+        }
+    }
+}
+
+/// Listing endpoint of the Pathé API. The localized version is preferred, the
+/// unlocalized one serves the same shape and is used when it becomes unavailable.
+fn pathe_endpoint(site: &PatheSite, listing: &str) -> VersionedEndpoint {
+    VersionedEndpoint::new(format!("pathe_{listing}"), site.api_url(listing))
+        .with_fallback(format!("{}/api/{listing}", site.base_url), |value| value)
+}
+
+fn cinema_shows_url(site: &PatheSite, cinema_slug: &str) -> String {
+    site.api_url(&format!("cinema/{cinema_slug}/shows"))
+}
+
+/// Context of a cinema of which the showtimes could not be fetched
+#[derive(Debug, Serialize, Deserialize)]
+struct FailedCinema {
+    cinema_slug: String,
+}
+
+/// Listing of the shows playing at a cinema
+struct CinemaListing {
+    /// Shows playing within the horizon
+    shows: Vec<String>,
+    /// Hash of the complete listing, which changes when shows or the days on which they
+    /// play change
+    hash: String,
+}
+
+async fn fetch_cinema_shows(
+    client: Client,
+    site: PatheSite,
+    cinema_slug: String,
+    until: Option<NaiveDate>,
+) -> Result<CinemaListing> {
+    let listing: CinemaShows = client
+        .get_json(cinema_shows_url(&site, &cinema_slug))
+        .await?;
+    let mut entries: Vec<(&String, String)> = listing
+        .shows
+        .iter()
+        .map(|(slug, show)| (slug, show.to_string()))
+        .collect();
+    entries.sort();
+    let hash = hex::encode(Sha256::digest(serde_json::to_vec(&entries)?));
+
+    let shows = listing
+        .shows
+        .into_iter()
+        .filter(|(_, show)| {
+            // The listing contains the days a show is playing, if it does not we can't
+            // skip the show
+            match show.get("days").and_then(|days| days.as_object()) {
+                Some(days) => days.keys().any(|day| within_horizon(day, until)),
+                None => true,
+            }
+        })
+        .map(|(slug, _)| slug)
+        .collect();
+    Ok(CinemaListing { shows, hash })
+}
+
+async fn fetch_showtimes(
+    client: Client,
+    site: PatheSite,
+    show_slug: String,
+    cinema_slug: String,
+    until: Option<NaiveDate>,
+) -> Result<Vec<Showtime>> {
+    let request_url = site.api_url(&format!("show/{show_slug}/showtimes/{cinema_slug}"));
+    let showtimes: HashMap<String, Vec<Showtime>> = match client.get_json(&request_url).await {
+        Ok(res) => res,
+        Err(JsonDecodeError::DecodeError { .. }) => HashMap::default(),
+        Err(JsonDecodeError::NetworkError(err)) => bail!(err),
+    };
+    Ok(showtimes
+        .into_iter()
+        .filter(|(day, _)| within_horizon(day, until))
+        .flat_map(|(_, showtimes)| showtimes)
+        .map(|mut showtime| {
+            showtime.show_slug = Some(show_slug.clone());
+            showtime.cinema_slug = Some(cinema_slug.clone());
+            showtime
+        })
+        .collect())
+}
+
+/// Deletes the upcoming showtimes of the given cinemas which were not fetched again,
+/// such as cancelled screenings. Showtimes beyond the horizon were not fetched, so
+/// those are kept. Returns the amount of deleted showtimes.
+async fn remove_stale_showtimes(
+    conn: &mut PgConnection,
+    cinema_slugs: &[String],
+    showtimes: &[Showtime],
+    until: Option<NaiveDate>,
+) -> Result<u64> {
+    let column = |f: fn(&Showtime) -> Option<&String>| -> Vec<Option<String>> {
+        showtimes.iter().map(|st| f(st).cloned()).collect()
+    };
+    // The horizon ends with the last day in the time zone of Pathé
+    let horizon_end = until
+        .and_then(|until| until.succ_opt())
+        .and_then(|day| {
+            PATHE_TIMEZONE
+                .from_local_datetime(&day.and_time(NaiveTime::MIN))
+                .earliest()
+        })
+        .map(|end| end.fixed_offset());
+    let times: Vec<DateTime<FixedOffset>> = showtimes.iter().map(|st| st.time).collect();
+    let deleted = sqlx::query(
+        r#"DELETE FROM showtimes st
+        WHERE st.cinema_slug = ANY($1)
+            AND st.time >= current_timestamp
+            AND ($2::timestamptz IS NULL OR st.time < $2)
+            AND NOT EXISTS (
+                SELECT 1 FROM UNNEST($3::text[], $4::text[], $5::timestamptz[], $6::text[])
+                    AS fetched(show_slug, cinema_slug, time, auditorium_name)
+                WHERE fetched.show_slug = st.show_slug
+                    AND fetched.cinema_slug = st.cinema_slug
+                    AND fetched.time = st.time
+                    AND fetched.auditorium_name = st.auditorium_name
+            )"#,
+    )
+    .bind(cinema_slugs)
+    .bind(horizon_end)
+    .bind(column(|st| st.show_slug.as_ref()))
+    .bind(column(|st| st.cinema_slug.as_ref()))
+    .bind(times)
+    .bind(column(|st| Some(&st.auditorium_name)))
+    .execute(conn)
+    .await?
+    .rows_affected();
+    Ok(deleted)
+}
+
+/// Amount of concurrently running tasks of a kind when no limit is configured
+const DEFAULT_CONCURRENCY: usize = 16;
+
+/// Percentage of cinemas which may fail before a run fails, when not configured
+const DEFAULT_MAX_FAILED_CINEMAS: f64 = 50.0;
+
+/// Runs the tasks on a `JoinSet` with at most `limit` of them running at a time, such
+/// that no more tasks exist than are running. Every result is returned (in order of
+/// completion) together with the key of its task, a panic only fails its own task.
+async fn run_bounded<K, T, F>(
+    limit: Option<usize>,
+    tasks: impl IntoIterator<Item = (K, F)>,
+) -> Vec<(K, Result<T>)>
+where
+    F: Future<Output = Result<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let limit = limit.unwrap_or(DEFAULT_CONCURRENCY).max(1);
+    let mut tasks = tasks.into_iter();
+    let mut set = JoinSet::new();
+    let mut keys = HashMap::new();
+    let mut results = vec![];
+    loop {
+        while set.len() < limit
+            && let Some((key, task)) = tasks.next()
+        {
+            keys.insert(set.spawn(task).id(), key);
+        }
+        let (id, result) = match set.join_next_with_id().await {
+            Some(Ok((id, result))) => (id, result),
+            Some(Err(err)) => (err.id(), Err(err.into())),
+            None => break,
+        };
+        results.push((keys.remove(&id).expect("Every task has a key"), result));
+    }
+    results
+}
+
+async fn fetch_showtimes_cinema(
+    client: Client,
+    site: PatheSite,
+    cinema: String,
+    until: Option<NaiveDate>,
+    show_concurrency: Option<usize>,
+) -> Result<Vec<Showtime>> {
+    let fetched =
+        fetch_changed_showtimes_cinema(client, site, cinema, until, show_concurrency, None).await?;
+    Ok(fetched.showtimes.unwrap_or_default())
+}
+
+/// Showtimes of a cinema together with the hash of its listing
+struct CinemaShowtimes {
+    listing_hash: String,
+    /// `None` when the listing still had the known hash, so nothing was fetched
+    showtimes: Option<Vec<Showtime>>,
+}
+
+/// Fetches the showtimes of a cinema, unless the hash of its listing equals
+/// `known_hash`
+async fn fetch_changed_showtimes_cinema(
+    client: Client,
+    site: PatheSite,
+    cinema: String,
+    until: Option<NaiveDate>,
+    show_concurrency: Option<usize>,
+    known_hash: Option<String>,
+) -> Result<CinemaShowtimes> {
+    let listing = fetch_cinema_shows(client.clone(), site.clone(), cinema.clone(), until).await?;
+    if known_hash.as_ref() == Some(&listing.hash) {
+        return Ok(CinemaShowtimes {
+            listing_hash: listing.hash,
+            showtimes: None,
+        });
+    }
+    let fetches = listing.shows.into_iter().map(|show_slug| {
+        let (client, site, cinema) = (client.clone(), site.clone(), cinema.clone());
+        let fetch = fetch_showtimes(client, site, show_slug.clone(), cinema, until);
+        (show_slug, fetch)
+    });
+    let mut res = vec![];
+    for (show_slug, showtimes) in run_bounded(show_concurrency, fetches).await {
+        res.append(&mut showtimes.with_context(|| format!("Showtimes of {show_slug}"))?);
+    }
+    Ok(CinemaShowtimes {
+        listing_hash: listing.hash,
+        showtimes: Some(res),
+    })
+}
+
+/// Name under which the hash of the listing of a cinema is stored in `endpoint_hashes`
+fn cinema_listing_endpoint(cinema_slug: &str) -> String {
+    format!("cinema_shows:{cinema_slug}")
+}
+
+async fn fetch_rt_data(client: Client, title: String) -> Result<RTResponse> {
+    Ok(client.get_json_post("https://79frdp12pn-dsn.algolia.net/1/indexes/*/queries?x-algolia-agent=Algolia%20for%20JavaScript%20(4.24.0)%3B%20Browser%20(lite)&x-algolia-api-key=175588f6e5f8319b27702e4cc4013561&x-algolia-application-id=79FRDP12PN", 
+    serde_json::json!({
+		"requests": [
+			{
+				"indexName": "content_rt",
+				"query": title,
+				"params": "filters=isEmsSearchable%20%3D%201&hitsPerPage=5"
+			}
+		]
+	})).await?)
+}
+
+/// Rotten Tomatoes searches made during a run, such that shows with the same title
+/// (e.g. the original and the dubbed version) share a single request even when
+/// they are looked up concurrently
+#[derive(Clone)]
+pub struct RtSearches {
+    client: Client,
+    searches: Arc<Mutex<HashMap<String, Arc<RtSearch>>>>,
+}
+
+/// Hits of a search, set once the first request for its title finished
+type RtSearch = OnceCell<Vec<RTHit>>;
+
+impl RtSearches {
+    pub fn new(client: Client) -> Self {
+        RtSearches {
+            client,
+            searches: Arc::default(),
+        }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Hits for the title, titles which are equal once normalized are searched once.
+    /// A failed search is retried by the next lookup of the title.
+    async fn search(&self, title: &str) -> Result<Vec<RTHit>> {
+        let search = self
+            .searches
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(normalize_title(title))
+            .or_default()
+            .clone();
+        let hits = search
+            .get_or_try_init(|| async {
+                fetch_rt_data(self.client.clone(), title.to_string())
+                    .await?
+                    .into_hits()
+            })
             .await?;
+        Ok(hits.clone())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ShowDetails {
+    original_title: Option<String>,
+    synopsis: Option<String>,
+    #[serde(alias = "kijkwijzer")]
+    content_rating: Option<ContentRating>,
+}
+
+/// Kijkwijzer classification of a show
+#[derive(Deserialize, Debug)]
+struct ContentRating {
+    #[serde(alias = "ref")]
+    age: Option<serde_json::Value>,
+    /// Content warnings such as "geweld" or "angst", either plain or as objects
+    #[serde(default)]
+    contents: Vec<serde_json::Value>,
+}
+
+impl ContentRating {
+    /// Age rating, e.g. `AL` or `12`
+    fn age(&self) -> Option<String> {
+        match self.age.as_ref()? {
+            serde_json::Value::String(age) => Some(age.clone()),
+            serde_json::Value::Number(age) => Some(age.to_string()),
+            _ => None,
+        }
+    }
+
+    fn warnings(&self, show_slug: &str) -> Vec<ContentWarning> {
+        self.contents
+            .iter()
+            .filter_map(|content| {
+                content
+                    .as_str()
+                    .or_else(|| content.get("name").and_then(|name| name.as_str()))
+                    .or_else(|| content.get("label").and_then(|label| label.as_str()))
+            })
+            .map(|warning| ContentWarning {
+                show_slug: show_slug.to_string(),
+                warning: warning.to_string(),
+            })
+            .collect()
+    }
+}
+
+async fn fetch_show_details(
+    client: Client,
+    site: PatheSite,
+    show_slug: String,
+) -> Result<Option<ShowDetails>> {
+    match client
+        .get_json(site.api_url(&format!("show/{show_slug}")))
+        .await
+    {
+        Ok(details) => Ok(Some(details)),
+        Err(JsonDecodeError::DecodeError { .. }) => Ok(None),
+        Err(JsonDecodeError::NetworkError(err)) => bail!(err),
+    }
+}
+
+/// A show of which the details and/or rating are looked up
+#[derive(Debug, Clone)]
+struct ShowLookup {
+    slug: String,
+    title: String,
+    year: Option<i32>,
+    /// Otherwise the details are only fetched when the original title is needed
+    details: bool,
+    rating: bool,
+    /// Highest match score at which a rating hit is accepted
+    max_score: Option<f64>,
+    rating_override: Option<RatingOverride>,
+    /// TMDb is only consulted when a key is configured
+    tmdb_api_key: Option<String>,
+}
+
+/// Outcome of a `ShowLookup`
+#[derive(Debug, Default)]
+struct ShowInfo {
+    original_title: Option<String>,
+    details: Option<ShowDetails>,
+    rating: Option<RatingMatch>,
+    tmdb: Option<(TmdbRating, f64)>,
+}
+
+impl ShowInfo {
+    /// Takes the TMDb rating out of the info, together with the link to the show
+    fn take_tmdb(&mut self, show_slug: &str) -> Option<(TmdbRating, TmdbRatingShow)> {
+        self.tmdb.take().map(|(rating, match_score)| {
+            let link = TmdbRatingShow {
+                show_slug: show_slug.to_string(),
+                tmdb_id: rating.tmdb_id,
+                match_score,
+            };
+            (rating, link)
+        })
+    }
+}
+
+impl ShowLookup {
+    fn new(
+        show: &FlatShow,
+        config: &MovieConfig,
+        overrides: &HashMap<String, RatingOverride>,
+    ) -> Self {
+        ShowLookup {
+            slug: show.slug.clone(),
+            title: show.title.clone(),
+            year: show.release_at.map(|date| date.year()),
+            details: config.fetch_details,
+            rating: show.rating_skip_reason.is_none(),
+            max_score: config.max_match_score,
+            rating_override: None,
+            tmdb_api_key: config.tmdb_api_key(),
+        }
+        .with_override(overrides.get(&show.slug).cloned())
+    }
+
+    /// A pinned rating is looked up even when the show would otherwise be skipped
+    fn with_override(mut self, rating_override: Option<RatingOverride>) -> Self {
+        if let Some(RatingOverride::Pinned(_)) = rating_override {
+            self.rating = true;
+        }
+        self.rating_override = rating_override;
+        self
+    }
+
+    fn is_needed(&self) -> bool {
+        self.details || self.rating
+    }
+
+    /// Rating sources are English, so the original title (from the details) is
+    /// preferred over a Dutch translation when matching the rating. Without a
+    /// `provider` no details are fetched at all.
+    async fn fetch(
+        self,
+        provider: Option<Arc<dyn CinemaProvider>>,
+        rt_searches: RtSearches,
+    ) -> Result<ShowInfo> {
+        let needs_original = self.rating
+            && self.rating_override != Some(RatingOverride::NoMatch)
+            && title_language(&self.title) != Some("eng");
+        let details = match provider {
+            Some(provider) if self.details || needs_original => {
+                provider.show_details(self.slug.clone()).await?
+            }
+            _ => None,
+        };
+        let original_title = details
+            .as_ref()
+            .and_then(|details| details.original_title.clone())
+            .filter(|original| !original.is_empty() && *original != self.title);
+        let title = original_title.clone().unwrap_or(self.title);
+        let tmdb = match &self.tmdb_api_key {
+            Some(api_key)
+                if self.rating && self.rating_override != Some(RatingOverride::NoMatch) =>
+            {
+                match fetch_tmdb_rating(
+                    rt_searches.client(),
+                    api_key,
+                    &title,
+                    self.year,
+                    self.max_score,
+                )
+                .await
+                {
+                    Ok(tmdb) => tmdb,
+                    Err(err) => {
+                        warn!(show = self.slug, "Failed to fetch the TMDb rating: {err:#}");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let rating = match self.rating {
+            true => {
+                let rating = fetch_show_rating(
+                    &rt_searches,
+                    self.slug.clone(),
+                    title,
+                    self.year,
+                    self.max_score,
+                    self.rating_override,
+                )
+                .await;
+                match rating {
+                    Ok(rating) => Some(rating),
+                    // TMDb is the fallback when Rotten Tomatoes is unavailable
+                    Err(err) if tmdb.is_some() => {
+                        warn!(show = self.slug, "Failed to fetch the RT rating: {err:#}");
+                        None
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            false => None,
+        };
+        Ok(ShowInfo {
+            original_title,
+            details: details.filter(|_| self.details),
+            rating,
+            tmdb,
+        })
+    }
+}
+
+/// Looked up info of a show as stored by an earlier run
+#[derive(Debug, FromRow)]
+struct StoredLookup {
+    slug: String,
+    rating_slug: Option<String>,
+    rating_match_score: Option<f64>,
+    original_title: Option<String>,
+    synopsis: Option<String>,
+    age_rating: Option<String>,
+}
+
+impl FlatShow {
+    /// Keeps the info stored by an earlier run, when looking it up failed this time
+    fn keep(&mut self, stored: StoredLookup) {
+        self.rating_slug = stored.rating_slug;
+        self.rating_match_score = stored.rating_match_score;
+        self.original_title = stored.original_title;
+        self.synopsis = stored.synopsis.or(self.synopsis.take());
+        self.age_rating = stored.age_rating.or(self.age_rating.take());
+    }
+
+    /// Stores the looked up info on the show, returning its content warnings and either
+    /// its rating or why it could not be matched
+    fn apply(
+        &mut self,
+        info: ShowInfo,
+    ) -> (Vec<ContentWarning>, Option<Rating>, Option<UnmatchedRating>) {
+        self.original_title = info.original_title;
+        let mut warnings = vec![];
+        if let Some(details) = info.details {
+            // The details may lack what the listing had
+            self.synopsis = details
+                .synopsis
+                .filter(|synopsis| !synopsis.is_empty())
+                .or(self.synopsis.take());
+            if let Some(content_rating) = details.content_rating {
+                self.age_rating = content_rating.age();
+                warnings = content_rating.warnings(&self.slug);
+            }
+        }
+        match info.rating {
+            Some(RatingMatch::Matched(rating, match_score)) => {
+                self.rating_slug = Some(rating.slug.clone());
+                self.rating_match_score = Some(match_score);
+                (warnings, Some(rating), None)
+            }
+            Some(RatingMatch::Unmatched(unmatched)) => (warnings, None, Some(unmatched)),
+            Some(RatingMatch::Suppressed) | None => (warnings, None, None),
+        }
+    }
+}
+
+/// Matches the show against Rotten Tomatoes, only accepting the best hit when it
+/// scores at most `max_score` (lower being better). An override takes precedence
+/// over matching.
+pub async fn fetch_show_rating(
+    searches: &RtSearches,
+    show_slug: String,
+    title: String,
+    year: Option<i32>,
+    max_score: Option<f64>,
+    rating_override: Option<RatingOverride>,
+) -> Result<RatingMatch> {
+    let (hit, match_score) = match rating_override {
+        Some(RatingOverride::NoMatch) => return Ok(RatingMatch::Suppressed),
+        Some(RatingOverride::Pinned(rating_slug)) => {
+            match find_rt_hit(searches, &title, &rating_slug).await? {
+                Some(hit) => {
+                    let score = rt_hit_score(&hit, &title, year);
+                    (hit, score)
+                }
+                None => {
+                    warn!(
+                        show = show_slug,
+                        "Pinned rating {rating_slug} was not found"
+                    );
+                    let unmatched = UnmatchedRating::new(show_slug, title, year, None);
+                    return Ok(RatingMatch::Unmatched(unmatched));
+                }
+            }
+        }
+        None => {
+            // TODO: NORMALIZE TITLE HERE BY REMOVING EVERYTHING BETWEEN PARENTHESES
+            let hits = searches.search(&title).await?;
+            match best_rt_hit(hits, title.clone(), year) {
+                Some((hit, score)) if max_score.is_none_or(|max| score <= max) => (hit, score),
+                best_hit => {
+                    let unmatched = UnmatchedRating::new(show_slug, title, year, best_hit);
+                    return Ok(RatingMatch::Unmatched(unmatched));
+                }
+            }
+        }
+    };
+    Ok(RatingMatch::Matched(hit.into(), match_score))
+}
+
+/// Hit with the given slug, searched for by title and otherwise by the slug itself
+async fn find_rt_hit(searches: &RtSearches, title: &str, slug: &str) -> Result<Option<RTHit>> {
+    for query in [title.to_string(), slug.replace(['_', '-'], " ")] {
+        let hits = searches.search(&query).await?;
+        if let Some(hit) = hits.into_iter().find(|hit| hit.vanity == slug) {
+            return Ok(Some(hit));
+        }
+    }
+    Ok(None)
+}
+
+static PATHE_BASE_URL: &str = "https://www.pathe.nl";
+
+/// Name of the shared client used for the requests to Pathé, see `Jobs::with_client`
+pub const PATHE_CLIENT: &str = "pathe";
+
+/// Name of the shared client used for the requests to Rotten Tomatoes
+pub const RT_CLIENT: &str = "rottentomatoes";
+static MOVIE_QUEUE: &str = "movies";
+
+/// The configured TMDb API key, or else the `TMDB_API_KEY` environment variable
+fn tmdb_api_key(configured: Option<&String>) -> Option<String> {
+    configured
+        .cloned()
+        .or_else(|| std::env::var("TMDB_API_KEY").ok())
+        .filter(|key| !key.is_empty())
+}
+
+/// Site at `base_url`, queried in `language` or else in the default language of the site
+fn pathe_site(base_url: String, language: Option<String>) -> PatheSite {
+    let site = PatheSite::new(base_url);
+    match language {
+        Some(language) => site.with_language(language),
+        None => site,
+    }
+}
+
+/// Site of the configured base URL and language, which default to the `PATHE_BASE_URL`
+/// and `PATHE_LANGUAGE` environment variables
+fn configured_site(base_url: Option<&String>, language: Option<&String>) -> PatheSite {
+    let base_url = base_url
+        .cloned()
+        .or_else(|| std::env::var("PATHE_BASE_URL").ok())
+        .unwrap_or_else(|| PATHE_BASE_URL.to_string());
+    let language = language
+        .cloned()
+        .or_else(|| std::env::var("PATHE_LANGUAGE").ok());
+    pathe_site(base_url, language)
+}
+
+/// Granular unit of work for running the movies job through the work queue
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum MovieTask {
+    CinemaShowtimes {
+        base_url: String,
+        /// Absent for tasks queued before the language was configurable
+        #[serde(default)]
+        language: Option<String>,
+        cinema_slug: String,
+        until: Option<NaiveDate>,
+        show_concurrency: Option<usize>,
+    },
+    ShowRating {
+        show_slug: String,
+        title: String,
+        year: Option<i32>,
+        /// Used to look up the details, not done when absent
+        #[serde(default)]
+        base_url: Option<String>,
+        #[serde(default)]
+        language: Option<String>,
+        #[serde(default)]
+        details: bool,
+        #[serde(default)]
+        skip_rating: bool,
+        #[serde(default)]
+        max_match_score: Option<f64>,
+    },
+}
+
+impl MovieTask {
+    /// Executes the task, directly writing its results to the database. The TMDb key is
+    /// handed over by the worker, such that it is never stored in the queue.
+    async fn execute(
+        self,
+        client: Client,
+        rt_searches: RtSearches,
+        tmdb_api_key: Option<String>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        match self {
+            MovieTask::CinemaShowtimes {
+                base_url,
+                language,
+                cinema_slug,
+                until,
+                show_concurrency,
+            } => {
+                let showtimes = fetch_showtimes_cinema(
+                    client,
+                    pathe_site(base_url, language),
+                    cinema_slug.clone(),
+                    until,
+                    show_concurrency,
+                )
+                .await?;
+                let mut tx = pool.begin().await?;
+                remove_stale_showtimes(&mut tx, &[cinema_slug], &showtimes, until).await?;
+                store_showtimes(&mut tx, showtimes).await?;
+                tx.commit().await?;
+            }
+            MovieTask::ShowRating {
+                show_slug,
+                title,
+                year,
+                base_url,
+                language,
+                details,
+                skip_rating,
+                max_match_score,
+            } => {
+                let lookup = ShowLookup {
+                    slug: show_slug.clone(),
+                    title,
+                    year,
+                    details,
+                    rating: !skip_rating,
+                    max_score: max_match_score,
+                    rating_override: None,
+                    tmdb_api_key,
+                }
+                .with_override(load_rating_overrides(pool).await?.remove(&show_slug));
+                let provider = base_url.map(|base_url| {
+                    let site = pathe_site(base_url, language);
+                    Arc::new(PatheProvider::new(client, site, None)) as Arc<dyn CinemaProvider>
+                });
+                let mut info = lookup.fetch(provider, rt_searches).await?;
+                let tmdb = info.take_tmdb(&show_slug);
+                let mut show = FlatShow {
+                    slug: show_slug,
+                    title: String::new(),
+                    release_at: None,
+                    movie_type: String::new(),
+                    duration: 0,
+                    rating_slug: None,
+                    rating_match_score: None,
+                    original_title: None,
+                    title_language: None,
+                    rating_skip_reason: None,
+                    synopsis: None,
+                    age_rating: None,
+                    directors: None,
+                };
+                let has_details = info.details.is_some();
+                let rated = info.rating.is_some();
+                let (warnings, rating, unmatched) = show.apply(info);
+                let mut tx = pool.begin().await?;
+                if rated {
+                    let resolved = match unmatched {
+                        Some(_) => vec![],
+                        None => vec![show.slug.clone()],
+                    };
+                    record_rating_matches(&mut tx, resolved, unmatched.into_iter().collect())
+                        .await?;
+                    RatingInserter::from(rating.into_iter().collect())
+                        .build()
+                        .execute(&mut *tx)
+                        .await?;
+                    if let Some((tmdb_rating, link)) = tmdb {
+                        TmdbRatingInserter::from(vec![tmdb_rating])
+                            .build()
+                            .execute(&mut *tx)
+                            .await?;
+                        TmdbRatingShowInserter::from(vec![link])
+                            .build()
+                            .execute(&mut *tx)
+                            .await?;
+                    }
+                    // Unmatched and suppressed shows lose the rating they had
+                    sqlx::query(
+                        "UPDATE shows SET rating_slug = $1, rating_match_score = $2 WHERE slug = $3",
+                    )
+                    .bind(&show.rating_slug)
+                    .bind(show.rating_match_score)
+                    .bind(&show.slug)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                sqlx::query("UPDATE shows SET original_title = $1 WHERE slug = $2")
+                    .bind(&show.original_title)
+                    .bind(&show.slug)
+                    .execute(&mut *tx)
+                    .await?;
+                if has_details {
+                    // The synopsis of the listing is kept when the details lack one
+                    sqlx::query(
+                        r#"UPDATE shows SET synopsis = COALESCE($1, synopsis), age_rating = $2
+                        WHERE slug = $3"#,
+                    )
+                    .bind(&show.synopsis)
+                    .bind(&show.age_rating)
+                    .bind(&show.slug)
+                    .execute(&mut *tx)
+                    .await?;
+                    ContentWarningInserter::from(warnings)
+                        .build()
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                tx.commit().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Configuration of the work queue execution mode
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WorkQueueConfig {
+    /// Amount of tasks processed concurrently by this process
+    pub workers: usize,
+    /// Time after which a claimed, but unfinished, task is handed out again
+    pub visibility_timeout_secs: u64,
+}
+
+impl Default for WorkQueueConfig {
+    fn default() -> Self {
+        WorkQueueConfig {
+            workers: 8,
+            visibility_timeout_secs: 60 * 10,
+        }
+    }
+}
+
+impl WorkQueueConfig {
+    fn queue(&self, pool: PgPool) -> TaskQueue {
+        TaskQueue::new(pool, MOVIE_QUEUE)
+            .with_visibility_timeout(Duration::from_secs(self.visibility_timeout_secs))
+    }
+}
+
+/// Processes tasks from the movie queue until no more visible tasks remain, returning
+/// the amount of shows of which this process gave up looking up the details and rating
+async fn drain_movie_queue(
+    queue: TaskQueue,
+    workers: usize,
+    clients: &Clients,
+    tmdb_api_key: Option<String>,
+) -> Result<usize> {
+    let client = match clients.get(PATHE_CLIENT) {
+        Some(client) => client,
+        None => Client::new().with_limit(10.try_into()?).with_max_retries(3),
+    };
+    let rt_client = match clients.get(RT_CLIENT) {
+        Some(client) => client,
+        None => Client::new().with_limit(10.try_into()?).with_max_retries(3),
+    };
+    let rt_searches = RtSearches::new(rt_client);
+
+    let mut handles = vec![];
+    for _ in 0..workers.max(1) {
+        let (queue, client, rt_searches) = (queue.clone(), client.clone(), rt_searches.clone());
+        let tmdb_api_key = tmdb_api_key.clone();
+        handles.push(tokio::spawn(async move {
+            let mut failed_lookups = 0;
+            while let Some(task) = queue.claim::<MovieTask>().await? {
+                let title = match &task.payload {
+                    MovieTask::ShowRating { title, .. } => Some(title.clone()),
+                    MovieTask::CinemaShowtimes { .. } => None,
+                };
+                match task
+                    .payload
+                    .execute(
+                        client.clone(),
+                        rt_searches.clone(),
+                        tmdb_api_key.clone(),
+                        &queue.pool(),
+                    )
+                    .await
+                {
+                    Ok(()) => queue.complete(task.id).await?,
+                    Err(err) => {
+                        match &title {
+                            Some(title) => warn!(
+                                task = task.id,
+                                attempt = task.attempts,
+                                "Failed to look up the details and rating of {title}: {err:#}"
+                            ),
+                            None => warn!(
+                                task = task.id,
+                                attempt = task.attempts,
+                                "Task failed: {err:#}"
+                            ),
+                        }
+                        if title.is_some() && task.attempts >= queue.max_attempts() {
+                            failed_lookups += 1;
+                        }
+                        queue.fail(task.id, &err).await?
+                    }
+                }
+            }
+            anyhow::Ok(failed_lookups)
+        }));
+    }
+    let mut failed_lookups = 0;
+    for handle in handles {
+        failed_lookups += handle.await??;
+    }
+    let purged = queue.purge().await?;
+    if purged > 0 {
+        info!("Purged {purged} old tasks from the queue");
+    }
+    Ok(failed_lookups)
+}
+
+/// Per-job configuration for the `MovieFetcher`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct MovieConfig {
+    /// Defaults to the `PATHE_BASE_URL` environment variable, or else pathe.nl
+    base_url: Option<String>,
+    /// Language in which Pathé is queried, defaults to the `PATHE_LANGUAGE` environment
+    /// variable, or else the language of the site
+    language: Option<String>,
+    showtime_horizon: Option<u64>,
+    cinema_concurrency: Option<usize>,
+    show_concurrency: Option<usize>,
+    work_queue: Option<WorkQueueConfig>,
+    max_volume_drop: Option<f64>,
+    /// Alert when the amount of rows written to a table deviates by more than this
+    /// percentage from the previous runs, `None` does not compare
+    max_volume_deviation: Option<f64>,
+    hedge_after_ms: Option<u64>,
+    /// Requests per second to Pathé, 10 by default
+    requests_per_second: Option<NonZeroU32>,
+    /// Retries of failed requests to Pathé, 3 by default
+    max_retries: Option<u8>,
+    /// Proxies the requests to Pathé rotate through
+    proxies: Vec<String>,
+    /// Image size variants to store, `None` stores all of them
+    image_variants: Option<Vec<String>>,
+    fetch_details: bool,
+    /// Directory in which all responses are recorded
+    record_dir: Option<PathBuf>,
+    /// Directory from which all responses are replayed, instead of fetching them
+    replay_dir: Option<PathBuf>,
+    /// Directory or bucket URL to which all raw responses are archived
+    archive: Option<String>,
+    /// Directory or bucket URL to which the posters are downloaded
+    posters: Option<String>,
+    /// Directory to which payloads which fail to decode are written
+    decode_dump_dir: Option<PathBuf>,
+    /// Hours after which the showtimes of a cinema are refetched even though its
+    /// listing did not change, `None` always refetches them
+    incremental_hours: Option<u64>,
+    /// Highest score at which a rating hit is accepted, `None` accepts any hit
+    max_match_score: Option<f64>,
+    /// Defaults to the `TMDB_API_KEY` environment variable
+    tmdb_api_key: Option<String>,
+    /// Hours during which a matched rating is reused instead of matching the show again
+    rating_cache_hours: Option<u64>,
+    /// Percentage of cinemas which may fail before the run fails, 50 by default
+    max_failed_cinemas: Option<f64>,
+    /// Where premieres found by a run are announced
+    notify: Option<NotifyConfig>,
+    /// Checks on the fetched data before it is written, `None` skips them
+    validation: Option<ValidationConfig>,
+    /// Clients shared with other jobs, handed over by `Jobs`
+    #[serde(skip)]
+    clients: Clients,
+}
+
+impl MovieConfig {
+    /// Base URL of the Pathé website to fetch from, e.g. `https://www.pathe.be`.
+    ///
+    /// When fetching multiple countries, each job should be added for a different
+    /// tenant using `Jobs::add_for_tenant` to prevent slugs from colliding.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Language in which the shows are fetched, e.g. `fr` for the French speaking
+    /// cinemas on `https://www.pathe.ch`
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Only fetch showtimes for the coming `days` days (including today)
+    pub fn with_showtime_horizon(mut self, days: u64) -> Self {
+        self.showtime_horizon = Some(days);
+        self
+    }
+
+    /// Maximum amount of cinemas for which showtimes are fetched in parallel, 16 by
+    /// default
+    pub fn with_cinema_concurrency(mut self, cinemas: usize) -> Self {
+        self.cinema_concurrency = Some(cinemas);
+        self
+    }
+
+    /// Maximum amount of shows fetched in parallel within a single cinema (16 by
+    /// default), which also bounds the amount of concurrent detail and rating lookups.
+    ///
+    /// The effective concurrency is bounded by the product of this value and the
+    /// cinema concurrency (and of course by the rate limit of the client).
+    pub fn with_show_concurrency(mut self, shows: usize) -> Self {
+        self.show_concurrency = Some(shows);
+        self
+    }
+
+    /// Send a second request to Pathé when a request did not respond within
+    /// `latency`, cutting off the long latency tail of the showtimes endpoint
+    pub fn with_hedging(mut self, latency: Duration) -> Self {
+        self.hedge_after_ms = Some(latency.as_millis() as u64);
+        self
+    }
+
+    pub fn with_rate_limit(mut self, requests_per_second: NonZeroU32) -> Self {
+        self.requests_per_second = Some(requests_per_second);
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u8) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Send the requests to Pathé through the proxy, see `Client::with_proxy`
+    pub fn with_proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxies.push(url.into());
+        self
+    }
+
+    /// Use the clients shared under `PATHE_CLIENT` and `RT_CLIENT` when there are, such
+    /// that their rate limits are shared with the other jobs. The rate limit, retries,
+    /// proxies and hedging of this job are not applied to shared clients.
+    pub fn with_clients(mut self, clients: Clients) -> Self {
+        self.clients = clients;
+        self
+    }
 
-        sqlx::query!(r#"INSERT INTO joblogs(jobname) VALUES ('moviefetcher')"#)
+    /// Client for requests to Pathé
+    fn pathe_client(&self) -> Result<Client> {
+        if let Some(client) = self.clients.get(PATHE_CLIENT) {
+            return self.storage(client);
+        }
+        let client = Client::new()
+            .with_limit(self.requests_per_second.unwrap_or(10.try_into()?))
+            .with_max_retries(self.max_retries.unwrap_or(3))
+            .with_cache();
+        let client = self
+            .proxies
+            .iter()
+            .try_fold(client, |client, proxy| client.with_proxy(proxy))?;
+        let client = match self.hedge_after_ms {
+            Some(ms) => client.with_hedging(Duration::from_millis(ms)),
+            None => client,
+        };
+        self.storage(client)
+    }
+
+    /// Client for requests to Rotten Tomatoes
+    fn rt_client(&self) -> Result<Client> {
+        let client = match self.clients.get(RT_CLIENT) {
+            Some(client) => client,
+            None => Client::new().with_limit(10.try_into()?).with_max_retries(3),
+        };
+        self.storage(client)
+    }
+
+    /// Applies the settings of where responses are recorded to and replayed from
+    fn storage(&self, client: Client) -> Result<Client> {
+        let client = match &self.archive {
+            Some(location) => client.with_archive(Arc::new(Archive::open(location)?)),
+            None => client,
+        };
+        let client = match &self.decode_dump_dir {
+            Some(dir) => client.with_decode_dumps(dir),
+            None => client,
+        };
+        Ok(match (&self.replay_dir, &self.record_dir) {
+            (Some(dir), _) => client.with_replay(dir),
+            (None, Some(dir)) => client.with_recording(dir),
+            (None, None) => client,
+        })
+    }
+
+    /// Only refetch the showtimes of cinemas of which the listing of shows changed since
+    /// the previous run. The listing does not tell when times are added to a day
+    /// which already had showtimes, so cinemas are refetched anyway once their
+    /// showtimes are older than `hours`.
+    pub fn with_incremental(mut self, hours: u64) -> Self {
+        self.incremental_hours = Some(hours);
+        self
+    }
+
+    /// Write the payloads which fail to decode to `dir`, to debug upstream changes
+    pub fn with_decode_dumps(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.decode_dump_dir = Some(dir.into());
+        self
+    }
+
+    /// Archive every raw response from Pathé and Rotten Tomatoes to `location`, see
+    /// `Archive::open`
+    pub fn with_archive(mut self, location: impl Into<String>) -> Self {
+        self.archive = Some(location.into());
+        self
+    }
+
+    /// Download the `lg` and `md` posters of shows to `location` after every run, see
+    /// `AssetStore::open`. Posters which were downloaded before are skipped.
+    pub fn with_posters(mut self, location: impl Into<String>) -> Self {
+        self.posters = Some(location.into());
+        self
+    }
+
+    /// Downloads the new posters when a location for them is configured. Posters
+    /// which fail to download are retried during the next run, so this never fails.
+    async fn download_posters(&self, pool: &PgPool) {
+        let Some(location) = &self.posters else {
+            return;
+        };
+        let downloaded = async {
+            let store = AssetStore::open(location)?;
+            let client = Client::new()
+                .with_limit(self.requests_per_second.unwrap_or(10.try_into()?))
+                .with_max_retries(self.max_retries.unwrap_or(3))
+                .with_timeout(Duration::from_secs(60));
+            store.download_posters(pool, &client).await
+        };
+        match downloaded.await {
+            Ok(downloads) if downloads.failed > 0 => warn!(
+                "Downloaded {} posters, failed to download {}",
+                downloads.downloaded, downloads.failed
+            ),
+            Ok(downloads) => info!("Downloaded {} posters", downloads.downloaded),
+            Err(e) => warn!("Could not download the posters: {e:#}"),
+        }
+    }
+
+    /// Save every response from Pathé and Rotten Tomatoes in `dir`, such that the run
+    /// can be replayed later using `with_replay`
+    pub fn with_recording(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.record_dir = Some(dir.into());
+        self
+    }
+
+    /// Answer all requests with the responses recorded in `dir` by an earlier run
+    /// using `with_recording`, such that a run does not touch the network at all
+    pub fn with_replay(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.replay_dir = Some(dir.into());
+        self
+    }
+
+    /// Only store the given size variants (e.g. `md` and `lg`) of the images of shows
+    pub fn with_image_variants(mut self, variants: &[&str]) -> Self {
+        self.image_variants = Some(variants.iter().map(|variant| variant.to_string()).collect());
+        self
+    }
+
+    /// Fetch the details of every show, storing its synopsis, Kijkwijzer age rating
+    /// and content warnings. Costs a request per show, bounded by the show concurrency.
+    pub fn with_details(mut self) -> Self {
+        self.fetch_details = true;
+        self
+    }
+
+    /// Only link a rating when the best hit scores at most `score`, 0 being a perfect
+    /// match. Shows without such a hit are stored in `unmatched_ratings` instead,
+    /// see `MovieFetcher::rematch`.
+    pub fn with_max_match_score(mut self, score: f64) -> Self {
+        self.max_match_score = Some(score);
+        self
+    }
+
+    /// Look up every rated show on TMDb as well, which also serves as the fallback
+    /// when Rotten Tomatoes cannot be reached. Without a key (here or in the
+    /// `TMDB_API_KEY` environment variable) TMDb is not consulted.
+    pub fn with_tmdb(mut self, api_key: impl Into<String>) -> Self {
+        self.tmdb_api_key = Some(api_key.into());
+        self
+    }
+
+    /// Reuse the rating of a show which was matched less than `hours` ago, instead of
+    /// querying Rotten Tomatoes again. The scores of such ratings are only refreshed
+    /// once they expire.
+    pub fn with_rating_cache(mut self, hours: u64) -> Self {
+        self.rating_cache_hours = Some(hours);
+        self
+    }
+
+    fn tmdb_api_key(&self) -> Option<String> {
+        tmdb_api_key(self.tmdb_api_key.as_ref())
+    }
+
+    fn keeps_image(&self, image: &ShowImage) -> bool {
+        self.image_variants
+            .as_ref()
+            .is_none_or(|variants| variants.contains(&image.variant))
+    }
+
+    /// Flag the run as anomalous when the amount of fetched shows or showtimes drops
+    /// by more than `percentage` compared to the previous runs
+    pub fn with_max_volume_drop(mut self, percentage: f64) -> Self {
+        self.max_volume_drop = Some(percentage);
+        self
+    }
+
+    /// After the run, flag it as anomalous and alert the notification channels when the
+    /// amount of rows written to any table deviates by more than `percentage` from the
+    /// previous runs, which usually means that Pathé changed the shape of its API
+    pub fn with_max_volume_deviation(mut self, percentage: f64) -> Self {
+        self.max_volume_deviation = Some(percentage);
+        self
+    }
+
+    /// Fail the run when fetching the showtimes fails for more than `percentage` of the
+    /// cinemas. Otherwise the showtimes of the other cinemas are stored and the failed
+    /// cinemas are retried during the next run.
+    pub fn with_max_failed_cinemas(mut self, percentage: f64) -> Self {
+        self.max_failed_cinemas = Some(percentage);
+        self
+    }
+
+    /// Announces the shows which got their first showtimes during a run
+    pub fn with_notify(mut self, notify: NotifyConfig) -> Self {
+        self.notify = Some(notify);
+        self
+    }
+
+    /// Checks the fetched shows, showtimes and ratings before writing them, aborting
+    /// the run or only warning when a check fails
+    pub fn with_validation(mut self, validation: ValidationConfig) -> Self {
+        self.validation = Some(validation);
+        self
+    }
+
+    /// Instead of fetching everything in-process, enqueue a task per cinema and per
+    /// rating lookup in the `fetch_tasks` table and process those. Unfinished tasks
+    /// survive crashes and can be shared with other processes running a
+    /// `JobKind::MovieWorker`.
+    ///
+    /// Every task writes its own results, so there is no single write to roll back or
+    /// to check: a run with a work queue fails during a dry run, or when any of the
+    /// volume or failed cinema thresholds is configured.
+    pub fn with_work_queue(mut self, work_queue: WorkQueueConfig) -> Self {
+        self.work_queue = Some(work_queue);
+        self
+    }
+
+    /// Providers of which the cinemas, shows and showtimes are fetched during a run
+    fn providers(&self) -> Result<Vec<Arc<dyn CinemaProvider>>> {
+        Ok(vec![Arc::new(PatheProvider::new(
+            self.pathe_client()?,
+            self.site(),
+            self.show_concurrency,
+        ))])
+    }
+
+    fn site(&self) -> PatheSite {
+        configured_site(self.base_url.as_ref(), self.language.as_ref())
+    }
+
+    /// Last date for which showtimes should be fetched, `None` fetches everything. The
+    /// horizon includes today, in the time zone of the cinemas.
+    fn showtimes_until(&self) -> Option<NaiveDate> {
+        let today = Utc::now().with_timezone(&PATHE_TIMEZONE).date_naive();
+        self.showtime_horizon
+            .and_then(|days| today.checked_add_days(Days::new(days.saturating_sub(1))))
+    }
+}
+
+/// Entity to which a targeted scrape is limited
+#[derive(Debug, Clone)]
+pub enum ScrapeTarget {
+    Cinema(String),
+    Show(String),
+}
+
+/// Name under which a targeted scrape is logged, such that it does not count as a run
+/// of the fetcher when comparing volumes
+static SCRAPE_JOBNAME: &str = "moviescraper";
+
+/// What a run (or a targeted scrape) fetched, written by `MovieFetcher::write`
+#[derive(Default)]
+struct FetchedMovies {
+    cities: Vec<City>,
+    cinemas: Vec<FlatCinema>,
+    shows: HashMap<String, FlatShow>,
+    images: Vec<ShowImage>,
+    genres: Vec<Genre>,
+    release_dates: Vec<ShowReleaseDate>,
+    metadata: ShowMetadata,
+    showtimes: Vec<Showtime>,
+    until: Option<NaiveDate>,
+    /// Cinemas of which every showtime was fetched, such that the stale ones are removed
+    complete_cinemas: Vec<String>,
+    /// Cinemas of which the listing did not change, such that their showtimes are kept
+    unchanged_cinemas: Vec<String>,
+    /// Hashes of the listings of the fetched cinemas, by their endpoint
+    listing_hashes: Vec<(String, String)>,
+}
+
+impl FetchedMovies {
+    /// Adds a listed show, returning the lookup of its details and rating when needed
+    fn add_show(
+        &mut self,
+        listed: ListedShow,
+        config: &MovieConfig,
+        overrides: &HashMap<String, RatingOverride>,
+        rating_cache: &RatingCache,
+    ) -> Option<ShowLookup> {
+        let (mut show, images, mut genres, mut release_dates, metadata) = listed;
+        let cached = rating_cache.link(&mut show);
+        let mut lookup = ShowLookup::new(&show, config, overrides);
+        lookup.rating &= !cached;
+        self.shows.insert(show.slug.clone(), show);
+        self.images
+            .extend(images.into_iter().filter(|image| config.keeps_image(image)));
+        self.genres.append(&mut genres);
+        self.release_dates.append(&mut release_dates);
+        self.metadata.append(metadata);
+        lookup.is_needed().then_some(lookup)
+    }
+}
+
+/// Outcome of `MovieFetcher::write`
+struct WrittenMovies {
+    deltas: RunDeltas,
+    anomalies: Vec<VolumeAnomaly>,
+    /// Amount of removed stale showtimes
+    stale: u64,
+    failed_lookups: usize,
+}
+
+#[derive(Debug)]
+pub struct MovieFetcher {
+    pub pool: PgPool,
+    pub config: MovieConfig,
+}
+impl MovieFetcher {
+    /// Runs the fetch, match and insert steps for a single cinema or show only, which
+    /// is a lot quicker than a full run when debugging the data of said entity.
+    pub async fn scrape(&self, target: &ScrapeTarget, context: &RunContext) -> Result<()> {
+        let site = self.config.site();
+        let client = self.config.pathe_client()?;
+        let rt_searches = RtSearches::new(self.config.rt_client()?);
+        let until = self.config.showtimes_until();
+        let provider: Arc<dyn CinemaProvider> = Arc::new(PatheProvider::new(
+            client.clone(),
+            site.clone(),
+            self.config.show_concurrency,
+        ));
+        let mut fetched = FetchedMovies {
+            until,
+            ..Default::default()
+        };
+
+        let ((cities, mut cinemas), shows) =
+            try_join!(provider.list_cinemas(), provider.list_shows())?;
+        fetched.cities = cities;
+        match target {
+            ScrapeTarget::Cinema(slug) => {
+                cinemas.retain(|cinema| cinema.slug == *slug);
+                if cinemas.is_empty() {
+                    bail!("Unknown cinema {slug}");
+                }
+                let listing = provider.list_showtimes(slug.clone(), until, None).await?;
+                fetched.showtimes = listing.showtimes.unwrap_or_default();
+                fetched.complete_cinemas.push(slug.clone());
+            }
+            ScrapeTarget::Show(slug) => {
+                if !shows.iter().any(|(show, ..)| show.slug == *slug) {
+                    bail!("Unknown show {slug}");
+                }
+                let fetches = cinemas.iter().map(|cinema| {
+                    let (client, site) = (client.clone(), site.clone());
+                    let fetch =
+                        fetch_showtimes(client, site, slug.clone(), cinema.slug.clone(), until);
+                    (cinema.slug.clone(), fetch)
+                });
+                for (_, cinema_showtimes) in
+                    run_bounded(self.config.cinema_concurrency, fetches).await
+                {
+                    fetched.showtimes.append(&mut cinema_showtimes?);
+                }
+            }
+        }
+        fetched.cinemas = cinemas;
+
+        // Only the shows being scraped, which are the ones playing in case of a cinema
+        let scraped: HashSet<String> = match target {
+            ScrapeTarget::Cinema(_) => fetched
+                .showtimes
+                .iter()
+                .filter_map(|showtime| showtime.show_slug.clone())
+                .collect(),
+            ScrapeTarget::Show(slug) => HashSet::from([slug.clone()]),
+        };
+        let overrides = load_rating_overrides(&self.pool).await?;
+        let rating_cache = RatingCache::load(&self.pool, self.config.rating_cache_hours).await?;
+        let mut lookups = vec![];
+        for show in shows {
+            let slug = show.0.slug.clone();
+            if !scraped.contains(&slug) {
+                continue;
+            }
+            if let Some(lookup) = fetched.add_show(show, &self.config, &overrides, &rating_cache) {
+                let fetch = lookup.fetch(Some(provider.clone()), rt_searches.clone());
+                lookups.push((slug, fetch));
+            }
+        }
+        let lookups = run_bounded(self.config.show_concurrency, lookups).await;
+
+        let (show_count, showtime_count) = (fetched.shows.len(), fetched.showtimes.len());
+        let validation = Validation::new(self.config.validation.clone());
+        if let Some(written) = self
+            .write(fetched, lookups, validation, Some(target), context)
+            .await?
+        {
+            info!(
+                "Scraped {target:?}: {show_count} shows and {showtime_count} showtimes, failed \
+                to look up {} shows: {}",
+                written.failed_lookups, written.deltas
+            );
+        }
+        Ok(())
+    }
+
+    /// Matches only the shows in `unmatched_ratings` against Rotten Tomatoes again,
+    /// e.g. after changing the match threshold, without doing a full run
+    pub async fn rematch(&self) -> Result<()> {
+        let site = self.config.site();
+        let client = self.config.pathe_client()?;
+        let rt_searches = RtSearches::new(self.config.rt_client()?);
+        let shows: Vec<(String, String, Option<NaiveDate>)> = sqlx::query_as(
+            r#"SELECT s.slug, s.title, s.release_at FROM unmatched_ratings u
+            JOIN shows s ON s.slug = u.show_slug"#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tasks = vec![];
+        for (show_slug, title, release_at) in shows {
+            let task = MovieTask::ShowRating {
+                show_slug: show_slug.clone(),
+                title,
+                year: release_at.map(|date| date.year()),
+                base_url: Some(site.base_url().to_string()),
+                language: Some(site.language().to_string()),
+                details: false,
+                skip_rating: false,
+                max_match_score: self.config.max_match_score,
+            };
+            let (client, rt_searches, pool) =
+                (client.clone(), rt_searches.clone(), self.pool.clone());
+            let tmdb_api_key = self.config.tmdb_api_key();
+            tasks.push((show_slug, async move {
+                task.execute(client, rt_searches, tmdb_api_key, &pool).await
+            }));
+        }
+        let attempted = tasks.len();
+        let mut failed = 0;
+        for (show_slug, result) in run_bounded(self.config.show_concurrency, tasks).await {
+            if let Err(err) = result {
+                warn!(show = show_slug, "Failed to rematch: {err:#}");
+                failed += 1;
+            }
+        }
+
+        let remaining: i64 = sqlx::query_scalar("SELECT count(*) FROM unmatched_ratings")
+            .fetch_one(&self.pool)
+            .await?;
+        info!("Rematched {attempted} shows ({failed} failed), {remaining} remain unmatched");
+        Ok(())
+    }
+
+    /// Inserts the basic information and enqueues the remaining work as tasks, which
+    /// are subsequently processed (possibly with help from other processes).
+    async fn run_queued(&self, site: PatheSite, work_queue: &WorkQueueConfig) -> Result<()> {
+        let client = self.config.pathe_client()?;
+        let queue = work_queue.queue(self.pool.clone());
+
+        let (cinemas, mut cities, shows): (Vec<Cinema>, Vec<City>, Shows) = try_join!(
+            client.get_json_versioned(pathe_endpoint(&site, "cinemas")),
+            client.get_json_versioned(pathe_endpoint(&site, "cities")),
+            client.get_json_versioned(pathe_endpoint(&site, "shows"))
+        )?;
+
+        let mut tasks = vec![];
+        let mut flatshows = vec![];
+        let mut imageinserter = ShowImageInserter::new();
+        let mut genres = vec![];
+        let mut releasedateinserter = ShowReleaseDateInserter::new();
+        let mut metadata = ShowMetadata::default();
+        let overrides = load_rating_overrides(&self.pool).await?;
+        let rating_cache = RatingCache::load(&self.pool, self.config.rating_cache_hours).await?;
+        for (mut show, images, show_genres, release_dates, show_metadata) in
+            shows.shows.into_iter().map(|show| show.flatten())
+        {
+            let pinned = matches!(overrides.get(&show.slug), Some(RatingOverride::Pinned(_)));
+            let cached = rating_cache.link(&mut show);
+            let skip_rating = (show.rating_skip_reason.is_some() && !pinned) || cached;
+            if !skip_rating || self.config.fetch_details {
+                tasks.push(MovieTask::ShowRating {
+                    show_slug: show.slug.clone(),
+                    title: show.title.clone(),
+                    year: show.release_at.map(|date| date.year()),
+                    base_url: Some(site.base_url().to_string()),
+                    language: Some(site.language().to_string()),
+                    details: self.config.fetch_details,
+                    skip_rating,
+                    max_match_score: self.config.max_match_score,
+                });
+            }
+            flatshows.push(show);
+            for image in images {
+                if self.config.keeps_image(&image) {
+                    imageinserter.add(image);
+                }
+            }
+            genres.extend(show_genres);
+            for release_date in release_dates {
+                releasedateinserter.add(release_date);
+            }
+            metadata.append(show_metadata);
+        }
+        for cinema in &cinemas {
+            tasks.push(MovieTask::CinemaShowtimes {
+                base_url: site.base_url().to_string(),
+                language: Some(site.language().to_string()),
+                cinema_slug: cinema.slug.clone(),
+                until: self.config.showtimes_until(),
+                show_concurrency: self.config.show_concurrency,
+            });
+        }
+
+        let mut validation = Validation::new(self.config.validation.clone());
+        validation.titles("shows", flatshows.iter().map(|show| show.title.as_str()));
+        validation.durations(flatshows.iter().map(|show| show.duration));
+        validation.unique("shows", flatshows.iter().map(|show| show.slug.as_str()));
+        validation.unique("cinemas", cinemas.iter().map(|cinema| cinema.slug.as_str()));
+        validation
+            .count_since_previous_run(&self.pool, "moviefetcher", "shows", flatshows.len())
+            .await?;
+        validation.finish("moviefetcher")?;
+
+        // Everything the tasks refer to has to exist before they are enqueued
+        let mut cinemas: Vec<FlatCinema> = cinemas.into_iter().map(Cinema::flatten).collect();
+        site.locate(&mut cities, &mut cinemas);
+        CityInserter::from(cities).build().execute(&self.pool).await?;
+        FlatCinemaInserter::from(cinemas)
+            .build()
             .execute(&self.pool)
             .await?;
-        println!("Ran the fetcher for movies");
+        FlatShowInserter::from(flatshows)
+            .build()
+            .execute(&self.pool)
+            .await?;
+        imageinserter.build().execute(&self.pool).await?;
+        store_genres(&mut *self.pool.acquire().await?, genres).await?;
+        releasedateinserter.build().execute(&self.pool).await?;
+        ContentWarningInserter::from(metadata.warnings)
+            .build()
+            .execute(&self.pool)
+            .await?;
+        CastMemberInserter::from(metadata.cast)
+            .build()
+            .execute(&self.pool)
+            .await?;
+        ShowTrailerInserter::from(metadata.trailers)
+            .build()
+            .execute(&self.pool)
+            .await?;
+
+        queue.enqueue(&tasks).await?;
+        let failed_lookups = drain_movie_queue(
+            queue,
+            work_queue.workers,
+            &self.config.clients,
+            self.config.tmdb_api_key(),
+        )
+        .await?;
+
+        finish_run(&self.pool, "moviefetcher", failed_lookups).await?;
+        if let Some(notify) = &self.config.notify {
+            notify.notify_run(&self.pool, "moviefetcher").await;
+        }
+        self.config.download_posters(&self.pool).await;
+        info!(
+            "Ran the fetcher for movies through the work queue, failed to look up \
+            {failed_lookups} shows"
+        );
+        Ok(())
+    }
+
+    /// Writes what a run fetched in a single transaction, such that a failure halfway
+    /// does not leave the tables partially updated. A targeted scrape only fetched part
+    /// of the shows, so it neither reconciles the aliases nor is compared against the
+    /// previous runs. Returns `None` during a dry run.
+    async fn write(
+        &self,
+        mut fetched: FetchedMovies,
+        lookups: Vec<(String, Result<ShowInfo>)>,
+        mut validation: Validation,
+        target: Option<&ScrapeTarget>,
+        context: &RunContext,
+    ) -> Result<Option<WrittenMovies>> {
+        let full = target.is_none();
+        let jobname = if full { "moviefetcher" } else { SCRAPE_JOBNAME };
+        let pool = &self.pool;
+        let shows = &mut fetched.shows;
+
+        // A show of which the lookup failed keeps the details and rating it had
+        let mut ratings = vec![];
+        let mut inserted_ratings = HashSet::new();
+        let mut resolved = vec![];
+        let mut unmatched = vec![];
+        let mut tmdb_ratings = HashMap::new();
+        let mut tmdb_links = vec![];
+        let mut failed_lookups = vec![];
+        for (slug, info) in lookups {
+            let mut info = match info {
+                Ok(info) => info,
+                Err(err) => {
+                    // The show keeps what it had, see below
+                    let title = shows.get(&slug).map(|show| show.title.as_str());
+                    warn!(
+                        show = slug,
+                        "Failed to look up the details and rating of {}: {err:#}",
+                        title.unwrap_or_default()
+                    );
+                    failed_lookups.push(slug);
+                    continue;
+                }
+            };
+            if let Some((tmdb_rating, link)) = info.take_tmdb(&slug) {
+                tmdb_ratings.insert(tmdb_rating.tmdb_id, tmdb_rating);
+                tmdb_links.push(link);
+            }
+            let rated = info.rating.is_some();
+            let (show_warnings, rating, show_unmatched) = shows.get_mut(&slug).unwrap().apply(info);
+            fetched.metadata.warnings.extend(show_warnings);
+            match show_unmatched {
+                Some(show_unmatched) => unmatched.push(show_unmatched),
+                None if rated => resolved.push(slug),
+                None => {}
+            }
+            if let Some(rating) = rating
+                && inserted_ratings.insert(rating.slug.clone())
+            {
+                ratings.push(rating);
+            }
+        }
+        let stored: Vec<StoredLookup> = sqlx::query_as(
+            r#"SELECT slug, rating_slug, rating_match_score, original_title, synopsis, age_rating
+            FROM shows WHERE slug = ANY($1)"#,
+        )
+        .bind(&failed_lookups)
+        .fetch_all(pool)
+        .await?;
+        for stored in stored {
+            if let Some(show) = shows.get_mut(&stored.slug) {
+                show.keep(stored);
+            }
+        }
+
+        let aliases = full.then(|| link_aliases(shows));
+
+        let mut anomalies = vec![];
+        if full && let Some(max_drop) = self.config.max_volume_drop {
+            // The showtimes of unchanged cinemas are kept, so they count as submitted
+            let unchanged: i64 = sqlx::query_scalar(
+                r#"SELECT count(*) FROM showtimes
+                WHERE cinema_slug = ANY($1) AND time >= current_timestamp"#,
+            )
+            .bind(&fetched.unchanged_cinemas)
+            .fetch_one(pool)
+            .await?;
+            let showtime_count = fetched.showtimes.len() + unchanged as usize;
+            for (table, submitted) in [("shows", shows.len()), ("showtimes", showtime_count)] {
+                anomalies.extend(
+                    check_volume(pool, jobname, table, submitted, max_drop, context).await?,
+                );
+            }
+        }
+
+        validation.titles("shows", shows.values().map(|show| show.title.as_str()));
+        validation.titles(
+            "ratings",
+            ratings.iter().map(|rating| rating.title.as_str()),
+        );
+        validation.durations(shows.values().map(|show| show.duration));
+        validation.unique(
+            "cinemas",
+            fetched.cinemas.iter().map(|cinema| cinema.slug.as_str()),
+        );
+        validation.unique("showtimes", fetched.showtimes.iter().map(Showtime::key));
+        if full {
+            validation
+                .count_since_previous_run(pool, jobname, "shows", shows.len())
+                .await?;
+        }
+        validation.finish("moviefetcher")?;
+
+        let FetchedMovies {
+            cities,
+            cinemas,
+            shows,
+            images,
+            genres,
+            release_dates,
+            mut metadata,
+            showtimes,
+            until,
+            complete_cinemas,
+            unchanged_cinemas: _,
+            listing_hashes,
+        } = fetched;
+        metadata.dedup();
+        let mut tx = pool.begin().await?;
+        let mut deltas = RunDeltas::new(jobname);
+        deltas
+            .track(&mut tx, "cities", cities.len(), async |conn| {
+                CityInserter::from(cities).build().execute(conn).await
+            })
+            .await?;
+        deltas
+            .track(&mut tx, "cinemas", cinemas.len(), async |conn| {
+                FlatCinemaInserter::from(cinemas)
+                    .build()
+                    .execute(conn)
+                    .await
+            })
+            .await?;
+        deltas
+            .track(&mut tx, "ratings", ratings.len(), async |conn| {
+                RatingInserter::from(ratings).build().execute(conn).await
+            })
+            .await?;
+        deltas
+            .track(&mut tx, "shows", shows.len(), async |conn| {
+                FlatShowInserter::from(shows.into_values().collect())
+                    .build()
+                    .execute(conn)
+                    .await
+            })
+            .await?;
+        record_rating_matches(&mut tx, resolved, unmatched).await?;
+        deltas
+            .track(&mut tx, "tmdb_ratings", tmdb_ratings.len(), async |conn| {
+                TmdbRatingInserter::from(tmdb_ratings.into_values().collect())
+                    .build()
+                    .execute(conn)
+                    .await
+            })
+            .await?;
+        TmdbRatingShowInserter::from(tmdb_links)
+            .build()
+            .execute(&mut *tx)
+            .await?;
+        if let Some(aliases) = aliases {
+            let alias_slugs: Vec<String> = aliases.iter().map(|a| a.alias_slug.clone()).collect();
+            sqlx::query("DELETE FROM show_aliases WHERE alias_slug <> ALL($1)")
+                .bind(alias_slugs)
+                .execute(&mut *tx)
+                .await?;
+            ShowAliasInserter::from(aliases)
+                .build()
+                .execute(&mut *tx)
+                .await?;
+        }
+        deltas
+            .track(&mut tx, "images", images.len(), async |conn| {
+                ShowImageInserter::from(images).build().execute(conn).await
+            })
+            .await?;
+        deltas
+            .track(
+                &mut tx,
+                "content_warnings",
+                metadata.warnings.len(),
+                async |conn| {
+                    ContentWarningInserter::from(metadata.warnings)
+                        .build()
+                        .execute(conn)
+                        .await
+                },
+            )
+            .await?;
+        deltas
+            .track(&mut tx, "show_cast", metadata.cast.len(), async |conn| {
+                CastMemberInserter::from(metadata.cast)
+                    .build()
+                    .execute(conn)
+                    .await
+            })
+            .await?;
+        deltas
+            .track(
+                &mut tx,
+                "show_trailers",
+                metadata.trailers.len(),
+                async |conn| {
+                    ShowTrailerInserter::from(metadata.trailers)
+                        .build()
+                        .execute(conn)
+                        .await
+                },
+            )
+            .await?;
+        deltas
+            .track(&mut tx, "show_genres", genres.len(), async |conn| {
+                store_genres(conn, genres).await
+            })
+            .await?;
+        deltas
+            .track(
+                &mut tx,
+                "show_release_dates",
+                release_dates.len(),
+                async |conn| {
+                    ShowReleaseDateInserter::from(release_dates)
+                        .build()
+                        .execute(conn)
+                        .await
+                },
+            )
+            .await?;
+        // Only cinemas which were fetched completely are reconciled, and nothing is
+        // removed when the fetched volume looks off
+        let stale = match anomalies.is_empty() {
+            true => remove_stale_showtimes(&mut tx, &complete_cinemas, &showtimes, until).await?,
+            false => 0,
+        };
+        deltas
+            .track(&mut tx, "showtimes", showtimes.len(), async |conn| {
+                store_showtimes(conn, showtimes).await
+            })
+            .await?;
+        if self.config.incremental_hours.is_some() {
+            let (endpoints, hashes): (Vec<String>, Vec<String>) =
+                listing_hashes.into_iter().unzip();
+            sqlx::query(
+                r#"INSERT INTO endpoint_hashes(endpoint, hash)
+                SELECT * FROM UNNEST($1::text[], $2::text[])
+                ON CONFLICT (endpoint) DO UPDATE SET
+                    hash = excluded.hash,
+                    changed_at = current_timestamp"#,
+            )
+            .bind(endpoints)
+            .bind(hashes)
+            .execute(&mut *tx)
+            .await?;
+        }
+        if context.dry_run {
+            tx.rollback().await?;
+            info!("Dry run of {jobname}, would have written: {deltas}");
+            return Ok(None);
+        }
+        tx.commit().await?;
+
+        finish_run(pool, jobname, failed_lookups.len()).await?;
+        // The run is compared against the previous runs before it becomes one of them,
+        // skipping the tables which were flagged before writing already
+        if full && let Some(max_deviation) = self.config.max_volume_deviation {
+            let unflagged: Vec<_> = deltas
+                .tables
+                .iter()
+                .filter(|delta| !anomalies.iter().any(|anomaly| anomaly.table == delta.table))
+                .collect();
+            let deviations = check_deltas(pool, jobname, unflagged, max_deviation, context).await?;
+            anomalies.extend(deviations);
+        }
+        deltas.store(pool).await?;
+        Ok(Some(WrittenMovies {
+            deltas,
+            anomalies,
+            stale,
+            failed_lookups: failed_lookups.len(),
+        }))
+    }
+}
+
+impl Runnable for MovieFetcher {
+    async fn run(&self, context: &RunContext) -> Result<()> {
+        if let Some(work_queue) = &self.config.work_queue {
+            // The workers write what they fetch, which skips the checks of a single write
+            if context.dry_run {
+                bail!("The movie fetcher cannot do a dry run through the work queue");
+            }
+            if self.config.max_volume_drop.is_some()
+                || self.config.max_volume_deviation.is_some()
+                || self.config.max_failed_cinemas.is_some()
+            {
+                bail!(
+                    "The volume and failed cinema thresholds of the movie fetcher cannot be \
+                    checked through the work queue"
+                );
+            }
+            return self.run_queued(self.config.site(), work_queue).await;
+        }
+
+        let providers = self.config.providers()?;
+        let rt_searches = RtSearches::new(self.config.rt_client()?);
+        let until = self.config.showtimes_until();
+        let mut fetched = FetchedMovies {
+            until,
+            ..Default::default()
+        };
+
+        // Fetch some basic information of every provider
+        let mut cinemas = vec![];
+        let mut shows = vec![];
+        for provider in &providers {
+            let ((mut provider_cities, provider_cinemas), provider_shows) =
+                try_join!(provider.list_cinemas(), provider.list_shows())
+                    .with_context(|| format!("Listing of {}", provider.name()))?;
+            fetched.cities.append(&mut provider_cities);
+            cinemas.extend(
+                provider_cinemas
+                    .into_iter()
+                    .map(|cinema| (provider.clone(), cinema)),
+            );
+            shows.extend(
+                provider_shows
+                    .into_iter()
+                    .map(|show| (provider.clone(), show)),
+            );
+        }
+
+        // Listing a show twice would silently keep only one of them
+        let mut validation = Validation::new(self.config.validation.clone());
+        validation.unique(
+            "shows",
+            shows.iter().map(|(_, (show, ..))| show.slug.as_str()),
+        );
+
+        let overrides = load_rating_overrides(&self.pool).await?;
+        let rating_cache = RatingCache::load(&self.pool, self.config.rating_cache_hours).await?;
+        let mut lookups = vec![];
+        for (provider, show) in shows {
+            let slug = show.0.slug.clone();
+            if let Some(lookup) = fetched.add_show(show, &self.config, &overrides, &rating_cache) {
+                lookups.push((slug, lookup.fetch(Some(provider), rt_searches.clone())));
+            }
+        }
+
+        // Fetch showtimes
+        let mut cinema_fetches = vec![];
+        let failed = FailedFetches::new(self.pool.clone(), "moviefetcher", context);
+        let retries: HashSet<String> = failed
+            .pending::<FailedCinema>()
+            .await?
+            .into_iter()
+            .map(|failure| failure.cinema_slug)
+            .collect();
+        let mut cinema_slugs: Vec<(String, Arc<dyn CinemaProvider>)> = cinemas
+            .iter()
+            .map(|(provider, cinema)| (cinema.slug.clone(), provider.clone()))
+            .collect();
+        // Cinemas which failed during a previous run are retried first
+        cinema_slugs.sort_by_key(|(slug, _)| !retries.contains(slug));
+        let mut known_hashes: HashMap<String, String> = match self.config.incremental_hours {
+            Some(hours) => sqlx::query_as(
+                r#"SELECT endpoint, hash FROM endpoint_hashes
+                WHERE endpoint LIKE 'cinema_shows:%'
+                    AND changed_at > current_timestamp - make_interval(hours => $1)"#,
+            )
+            .bind(hours as i32)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .collect(),
+            None => HashMap::new(),
+        };
+        for (cinema, provider) in cinema_slugs {
+            let known_hash = known_hashes.remove(&cinema_listing_endpoint(&cinema));
+            let fetch = provider.list_showtimes(cinema.clone(), until, known_hash);
+            cinema_fetches.push(((cinema, provider), fetch));
+        }
+
+        // The lookups and the cinemas are fetched at the same time, each kind bounded
+        // by its own concurrency limit
+        let (lookup_results, cinema_results) = tokio::join!(
+            run_bounded(self.config.show_concurrency, lookups),
+            run_bounded(self.config.cinema_concurrency, cinema_fetches)
+        );
+
+        // A cinema of which the showtimes could not be fetched is recorded such that it
+        // is retried during the next run
+        let mut fetched_urls = vec![];
+        let mut failed_cinemas = vec![];
+        for ((cinema_slug, provider), result) in cinema_results {
+            let url = provider.cinema_url(&cinema_slug);
+            match result {
+                Ok(CinemaShowtimes {
+                    listing_hash,
+                    showtimes: Some(mut cinema_showtimes),
+                }) => {
+                    fetched.showtimes.append(&mut cinema_showtimes);
+                    fetched_urls.push(url);
+                    fetched
+                        .listing_hashes
+                        .push((cinema_listing_endpoint(&cinema_slug), listing_hash));
+                    fetched.complete_cinemas.push(cinema_slug);
+                }
+                Ok(CinemaShowtimes {
+                    showtimes: None, ..
+                }) => {
+                    fetched_urls.push(url);
+                    fetched.unchanged_cinemas.push(cinema_slug);
+                }
+                Err(err) => {
+                    warn!(cinema = cinema_slug, "Failed to fetch showtimes: {err:#}");
+                    failed_cinemas.push(cinema_slug.clone());
+                    failed
+                        .record(&url, &FailedCinema { cinema_slug }, &err)
+                        .await?;
+                }
+            }
+        }
+        failed.resolve(&fetched_urls).await?;
+        // A few failing cinemas are retried next run, but when many fail something is
+        // off upstream and storing the rest would only hide that
+        let max_failed = self
+            .config
+            .max_failed_cinemas
+            .unwrap_or(DEFAULT_MAX_FAILED_CINEMAS);
+        if 100.0 * failed_cinemas.len() as f64 > max_failed * cinemas.len() as f64 {
+            bail!(
+                "Fetching the showtimes failed for {} of {} cinemas: {}",
+                failed_cinemas.len(),
+                cinemas.len(),
+                failed_cinemas.join(", ")
+            );
+        }
+
+        for client in providers
+            .iter()
+            .filter_map(|provider| provider.client())
+            .chain([rt_searches.client()])
+        {
+            info!("Requests of the fetcher for movies: {}", client.stats());
+        }
+        let unchanged_cinemas = fetched.unchanged_cinemas.len();
+        fetched.cinemas = cinemas.into_iter().map(|(_, cinema)| cinema).collect();
+        let Some(written) = self
+            .write(fetched, lookup_results, validation, None, context)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let pool = &self.pool;
+        if let Some(notify) = &self.config.notify {
+            notify.notify_run(pool, "moviefetcher").await;
+            notify
+                .alert_anomalies("moviefetcher", &written.anomalies)
+                .await;
+        }
+        self.config.download_posters(pool).await;
+        if written.anomalies.is_empty() {
+            info!(
+                "Ran the fetcher for movies, skipped {unchanged_cinemas} unchanged cinemas, \
+                failed to fetch {} cinemas and look up {} shows and removed {} stale \
+                showtimes: {}",
+                failed_cinemas.len(),
+                written.failed_lookups,
+                written.stale,
+                written.deltas
+            );
+        } else {
+            warn!(
+                "Ran the fetcher for movies, flagged as anomalous: {}",
+                written.deltas
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Configuration for a `ShowsWatcher`, of which the site should match the one of the
+/// movie jobs it triggers
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShowsWatchConfig {
+    /// Defaults to the `PATHE_BASE_URL` environment variable, or else pathe.nl
+    pub base_url: Option<String>,
+    /// Defaults to the `PATHE_LANGUAGE` environment variable, or else the language of
+    /// the site
+    language: Option<String>,
+    /// Requests per second to Pathé, 10 by default
+    requests_per_second: Option<NonZeroU32>,
+    #[serde(skip)]
+    clients: Clients,
+}
+
+impl ShowsWatchConfig {
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    pub fn with_rate_limit(mut self, requests_per_second: NonZeroU32) -> Self {
+        self.requests_per_second = Some(requests_per_second);
+        self
+    }
+
+    /// Use the client shared under `PATHE_CLIENT` when there is
+    pub fn with_clients(mut self, clients: Clients) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    fn client(&self) -> Result<Client> {
+        if let Some(client) = self.clients.get(PATHE_CLIENT) {
+            return Ok(client);
+        }
+        Ok(Client::new()
+            .with_limit(self.requests_per_second.unwrap_or(10.try_into()?))
+            .with_max_retries(3))
+    }
+}
+
+/// Lightweight companion of the `MovieFetcher`, which only polls the shows list and
+/// triggers the movie jobs early when the listed shows changed. Meant to run every
+/// few minutes, such that newly announced films are picked up quickly without
+/// running the heavy fetches more often.
+#[derive(Debug)]
+pub struct ShowsWatcher {
+    pub pool: PgPool,
+    pub config: ShowsWatchConfig,
+}
+impl Runnable for ShowsWatcher {
+    async fn run(&self, context: &RunContext) -> Result<()> {
+        let site = configured_site(self.config.base_url.as_ref(), self.config.language.as_ref());
+        let endpoint = pathe_endpoint(&site, "shows");
+        let client = self.config.client()?;
+        let shows: Shows = client.get_json_versioned(endpoint.clone()).await?;
+
+        // Only the listed slugs are hashed, other fields change without news
+        let mut slugs: Vec<String> = shows.shows.into_iter().map(|show| show.slug).collect();
+        slugs.sort();
+        let hash = hex::encode(Sha256::digest(serde_json::to_vec(&slugs)?));
+
+        let previous: Option<String> =
+            sqlx::query_scalar("SELECT hash FROM endpoint_hashes WHERE endpoint = $1")
+                .bind(endpoint.name())
+                .fetch_optional(&self.pool)
+                .await?;
+        if previous.as_ref() == Some(&hash) {
+            return Ok(());
+        }
+        sqlx::query(
+            r#"INSERT INTO endpoint_hashes(endpoint, hash) VALUES ($1, $2)
+            ON CONFLICT (endpoint) DO UPDATE SET
+                hash = excluded.hash,
+                changed_at = current_timestamp"#,
+        )
+        .bind(endpoint.name())
+        .bind(&hash)
+        .execute(&self.pool)
+        .await?;
+
+        // Nothing to compare against on the very first poll
+        if previous.is_some() {
+            info!("The shows list changed, triggering the movie jobs");
+            context.trigger_kind("Movies");
+        }
+        Ok(())
+    }
+}
+
+/// Configuration for a `MovieWorker`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct MovieWorkerConfig {
+    pub work_queue: WorkQueueConfig,
+    /// Defaults to the `TMDB_API_KEY` environment variable
+    tmdb_api_key: Option<String>,
+    #[serde(skip)]
+    clients: Clients,
+}
+
+impl MovieWorkerConfig {
+    /// Use the clients shared under `PATHE_CLIENT` and `RT_CLIENT` when there are
+    pub fn with_clients(mut self, clients: Clients) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    /// Look up the shows of rating tasks on TMDb as well, see `MovieConfig::with_tmdb`
+    pub fn with_tmdb(mut self, api_key: impl Into<String>) -> Self {
+        self.tmdb_api_key = Some(api_key.into());
+        self
+    }
+}
+
+/// Helps processing the tasks enqueued by a `MovieFetcher` running with a work queue,
+/// typically in another process than the one running the `MovieFetcher` itself.
+#[derive(Debug)]
+pub struct MovieWorker {
+    pub pool: PgPool,
+    pub config: MovieWorkerConfig,
+}
+impl Runnable for MovieWorker {
+    async fn run(&self, _context: &RunContext) -> Result<()> {
+        let queue = self.config.work_queue.queue(self.pool.clone());
+        let failed_lookups = drain_movie_queue(
+            queue,
+            self.config.work_queue.workers,
+            &self.config.clients,
+            tmdb_api_key(self.config.tmdb_api_key.as_ref()),
+        )
+        .await?;
+        if failed_lookups > 0 {
+            warn!("Gave up looking up the details and rating of {failed_lookups} shows");
+        }
         Ok(())
     }
 }
@@ -0,0 +1,244 @@
+//! Synthetic, but realistic looking, data for the movie tables.
+
+use anyhow::Result;
+use chrono::{Datelike, Days, Local, NaiveTime, TimeZone, Utc};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use sqlx::PgPool;
+use tracing::info;
+
+use super::{
+    City, CityInserter, FlatCinema, FlatCinemaInserter, FlatShow, FlatShowInserter, Genre, Rating,
+    RatingInserter, ShowImage, ShowImageInserter, Showtime, store_genres, store_showtimes,
+};
+
+/// Cities with their coordinates, used to place the cinemas
+static CITIES: [(&str, f64, f64); 8] = [
+    ("Amsterdam", 52.3676, 4.9041),
+    ("Rotterdam", 51.9244, 4.4777),
+    ("Den Haag", 52.0705, 4.3007),
+    ("Utrecht", 52.0907, 5.1214),
+    ("Eindhoven", 51.4416, 5.4697),
+    ("Groningen", 53.2194, 6.5665),
+    ("Nijmegen", 51.8126, 5.8372),
+    ("Zwolle", 52.5168, 6.0830),
+];
+
+static CINEMA_NAMES: [&str; 4] = ["Centrum", "Arena", "De Munt", "Stadion"];
+
+static TITLE_ADJECTIVES: [&str; 10] = [
+    "Silent", "Last", "Crimson", "Hidden", "Broken", "Golden", "Frozen", "Wild", "Lost", "Eternal",
+];
+
+static TITLE_NOUNS: [&str; 10] = [
+    "Horizon", "Empire", "Harbor", "Orchard", "Signal", "Kingdom", "Voyage", "Garden", "Frontier",
+    "Echo",
+];
+
+static GENRES: [&str; 8] = [
+    "Actie",
+    "Animatie",
+    "Comedy",
+    "Drama",
+    "Familie",
+    "Horror",
+    "Romantiek",
+    "Thriller",
+];
+
+static AUDITORIUMS: [(&str, i32); 4] = [
+    ("Zaal 1", 340),
+    ("Zaal 2", 210),
+    ("Zaal 3", 120),
+    ("IMAX", 450),
+];
+
+fn slugify(name: &str) -> String {
+    format!("demo-{}", name.to_lowercase().replace(' ', "-"))
+}
+
+/// Populates the movie tables with synthetic cities, cinemas, shows, showtimes and
+/// ratings, without touching the network. Generation is seeded, so repeated runs
+/// result in the same data. All slugs start with `demo-`, such that the data never
+/// collides with scraped data.
+pub async fn seed_demo(pool: &PgPool) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(0x5c4a9e7);
+    let today = Local::now().date_naive();
+
+    let mut cities = vec![];
+    let mut cinemas = vec![];
+    for (city, lat, lon) in CITIES {
+        let city_slug = slugify(city);
+        let amount = rng.random_range(1..=3);
+        for name in CINEMA_NAMES.choose_multiple(&mut rng, amount) {
+            let name = format!("Pathé {name} {city}");
+            cinemas.push(FlatCinema {
+                slug: slugify(&name),
+                city_slug: city_slug.clone(),
+                name,
+                latitude: Some(lat + rng.random_range(-0.03..0.03)),
+                longitude: Some(lon + rng.random_range(-0.03..0.03)),
+                country_code: Some("NL".to_string()),
+            });
+        }
+        cities.push(City {
+            slug: city_slug,
+            name: city.to_string(),
+            country_code: Some("NL".to_string()),
+        });
+    }
+
+    let mut shows = vec![];
+    let mut images = vec![];
+    let mut genres = vec![];
+    let mut ratings = vec![];
+    for (idx, adjective) in TITLE_ADJECTIVES.iter().enumerate() {
+        for noun in TITLE_NOUNS.iter().skip(idx % 3).step_by(3) {
+            let title = format!("The {adjective} {noun}");
+            let slug = slugify(&title);
+            let release_at = today.checked_sub_days(Days::new(rng.random_range(0..60)));
+            let rating_slug = rng.random_bool(0.8).then(|| format!("{slug}-rt"));
+            if let Some(rating_slug) = &rating_slug {
+                let critics_score = rng.random_range(15..100);
+                ratings.push(Rating {
+                    slug: rating_slug.clone(),
+                    title: title.clone(),
+                    description: Some(format!(
+                        "A film about a {} {}.",
+                        adjective.to_lowercase(),
+                        noun.to_lowercase()
+                    )),
+                    release_year: release_at.map(|date| date.year()),
+                    audience_score: Some(rng.random_range(30..100)),
+                    score_sentiment: Some(
+                        if critics_score >= 60 {
+                            "POSITIVE"
+                        } else {
+                            "NEGATIVE"
+                        }
+                        .to_string(),
+                    ),
+                    want_to_see_count: Some(rng.random_range(100..50_000)),
+                    critics_score: Some(critics_score),
+                    certified_fresh: Some(critics_score >= 75),
+                    new_adjusted_tm_score: Some(critics_score),
+                    fetched_at: Utc::now(),
+                });
+            }
+            let amount = rng.random_range(1..=2);
+            for genre in GENRES.choose_multiple(&mut rng, amount) {
+                genres.push(Genre {
+                    show_slug: slug.clone(),
+                    genre: genre.to_string(),
+                });
+            }
+            for (variant, width, height) in [("md", 300, 450), ("lg", 600, 900)] {
+                images.push(ShowImage {
+                    show_slug: slug.clone(),
+                    image_type: "poster".to_string(),
+                    position: 0,
+                    variant: variant.to_string(),
+                    url: format!("https://picsum.photos/seed/{slug}/{width}/{height}"),
+                    width: Some(width),
+                    height: Some(height),
+                });
+            }
+            shows.push(FlatShow {
+                slug,
+                title_language: Some("eng".to_string()),
+                title,
+                release_at,
+                movie_type: "movie".to_string(),
+                duration: rng.random_range(85..175),
+                rating_match_score: rating_slug.as_ref().map(|_| rng.random_range(0.0..0.2)),
+                rating_slug,
+                original_title: None,
+                synopsis: None,
+                directors: None,
+                age_rating: None,
+                rating_skip_reason: None,
+            });
+        }
+    }
+
+    let mut showtimes = vec![];
+    for cinema in &cinemas {
+        for show in shows.choose_multiple(&mut rng, 12) {
+            for day in 0..7 {
+                let date = today + Days::new(day);
+                for _ in 0..rng.random_range(0..=2) {
+                    let start = NaiveTime::from_hms_opt(
+                        rng.random_range(13..23),
+                        15 * rng.random_range(0..4),
+                        0,
+                    )
+                    .unwrap_or_default();
+                    let Some(start) = Local.from_local_datetime(&date.and_time(start)).single()
+                    else {
+                        continue;
+                    };
+                    let end = start + chrono::Duration::minutes(show.duration as i64 + 15);
+                    let (auditorium, capacity) = AUDITORIUMS.choose(&mut rng).unwrap();
+                    showtimes.push(Showtime {
+                        show_slug: Some(show.slug.clone()),
+                        cinema_slug: Some(cinema.slug.clone()),
+                        time: start.fixed_offset(),
+                        reservation_url: format!(
+                            "https://example.com/{}/{}",
+                            cinema.slug,
+                            start.timestamp()
+                        ),
+                        auditorium_name: auditorium.to_string(),
+                        auditorium_capacity: Some(*capacity),
+                        end_time: Some(end.fixed_offset()),
+                        price_cents: None,
+                        surcharge_3d_cents: None,
+                        surcharge_imax_cents: None,
+                        tags: vec![],
+                    });
+                }
+            }
+        }
+    }
+    // The same start time in the same auditorium may only occur once
+    showtimes.sort_by(|a, b| {
+        (&a.show_slug, &a.cinema_slug, &a.time, &a.auditorium_name).cmp(&(
+            &b.show_slug,
+            &b.cinema_slug,
+            &b.time,
+            &b.auditorium_name,
+        ))
+    });
+    showtimes.dedup_by(|a, b| {
+        (&a.show_slug, &a.cinema_slug, &a.time, &a.auditorium_name)
+            == (&b.show_slug, &b.cinema_slug, &b.time, &b.auditorium_name)
+    });
+
+    let counts = (cities.len(), cinemas.len(), shows.len(), showtimes.len());
+    let mut tx = pool.begin().await?;
+    CityInserter::from(cities).build().execute(&mut *tx).await?;
+    FlatCinemaInserter::from(cinemas)
+        .build()
+        .execute(&mut *tx)
+        .await?;
+    RatingInserter::from(ratings)
+        .build()
+        .execute(&mut *tx)
+        .await?;
+    FlatShowInserter::from(shows)
+        .build()
+        .execute(&mut *tx)
+        .await?;
+    ShowImageInserter::from(images)
+        .build()
+        .execute(&mut *tx)
+        .await?;
+    store_genres(&mut tx, genres).await?;
+    store_showtimes(&mut tx, showtimes).await?;
+    tx.commit().await?;
+
+    info!(
+        "Seeded {} cities, {} cinemas, {} shows and {} showtimes of demo data",
+        counts.0, counts.1, counts.2, counts.3
+    );
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Sources of cinemas, shows and showtimes, of which a `MovieFetcher` stores the
+//! combined listings.
+
+use std::pin::Pin;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use tokio::try_join;
+
+use super::{
+    Cinema, CinemaShowtimes, City, FlatCinema, ListedShow, PatheSite, ShowDetails, Shows,
+    cinema_shows_url, fetch_changed_showtimes_cinema, fetch_show_details, pathe_endpoint,
+};
+use crate::job::util::Client;
+
+/// Future returned by a `CinemaProvider`, owning everything it needs such that it can
+/// be spawned
+pub(super) type ProviderFuture<T> = Pin<Box<dyn Future<Output = Result<T>> + Send>>;
+
+/// A cinema chain (or a single cinema). Slugs of cinemas and shows end up in the same
+/// tables for every provider, so providers other than Pathé prefix them with their
+/// name.
+pub(super) trait CinemaProvider: Send + Sync {
+    /// Name of the provider in logs
+    fn name(&self) -> &'static str;
+
+    /// All cinemas together with the cities they are located in
+    fn list_cinemas(&self) -> ProviderFuture<(Vec<City>, Vec<FlatCinema>)>;
+
+    /// All shows playing (or about to play) at any of the cinemas
+    fn list_shows(&self) -> ProviderFuture<Vec<ListedShow>>;
+
+    /// Showtimes of a cinema up to `until`. Providers which can tell that the listing
+    /// of the cinema still has `known_hash` skip fetching its showtimes.
+    fn list_showtimes(
+        &self,
+        cinema_slug: String,
+        until: Option<NaiveDate>,
+        known_hash: Option<String>,
+    ) -> ProviderFuture<CinemaShowtimes>;
+
+    /// URL of the listing of a cinema, under which a failed fetch is recorded
+    fn cinema_url(&self, cinema_slug: &str) -> String;
+
+    /// Details of a show, such as its original title. `None` when the provider has no
+    /// details of the show.
+    fn show_details(&self, _show_slug: String) -> ProviderFuture<Option<ShowDetails>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Client the requests are sent through, of which the statistics are logged
+    fn client(&self) -> Option<&Client> {
+        None
+    }
+}
+
+/// Pathé, the country of which follows from its site
+pub(super) struct PatheProvider {
+    client: Client,
+    site: PatheSite,
+    /// Amount of shows of a cinema of which the showtimes are fetched concurrently
+    show_concurrency: Option<usize>,
+}
+
+impl PatheProvider {
+    pub(super) fn new(client: Client, site: PatheSite, show_concurrency: Option<usize>) -> Self {
+        PatheProvider {
+            client,
+            site,
+            show_concurrency,
+        }
+    }
+}
+
+impl CinemaProvider for PatheProvider {
+    fn name(&self) -> &'static str {
+        "pathe"
+    }
+
+    fn client(&self) -> Option<&Client> {
+        Some(&self.client)
+    }
+
+    fn list_cinemas(&self) -> ProviderFuture<(Vec<City>, Vec<FlatCinema>)> {
+        let (client, site) = (self.client.clone(), self.site.clone());
+        Box::pin(async move {
+            let (cinemas, mut cities): (Vec<Cinema>, Vec<City>) = try_join!(
+                client.get_json_versioned(pathe_endpoint(&site, "cinemas")),
+                client.get_json_versioned(pathe_endpoint(&site, "cities"))
+            )?;
+            let mut cinemas: Vec<FlatCinema> = cinemas.into_iter().map(Cinema::flatten).collect();
+            site.locate(&mut cities, &mut cinemas);
+            Ok((cities, cinemas))
+        })
+    }
+
+    fn list_shows(&self) -> ProviderFuture<Vec<ListedShow>> {
+        let (client, site) = (self.client.clone(), self.site.clone());
+        Box::pin(async move {
+            let shows: Shows = client
+                .get_json_versioned(pathe_endpoint(&site, "shows"))
+                .await?;
+            Ok(shows.shows.into_iter().map(|show| show.flatten()).collect())
+        })
+    }
+
+    fn list_showtimes(
+        &self,
+        cinema_slug: String,
+        until: Option<NaiveDate>,
+        known_hash: Option<String>,
+    ) -> ProviderFuture<CinemaShowtimes> {
+        Box::pin(fetch_changed_showtimes_cinema(
+            self.client.clone(),
+            self.site.clone(),
+            cinema_slug,
+            until,
+            self.show_concurrency,
+            known_hash,
+        ))
+    }
+
+    fn cinema_url(&self, cinema_slug: &str) -> String {
+        cinema_shows_url(&self.site, cinema_slug)
+    }
+
+    fn show_details(&self, show_slug: String) -> ProviderFuture<Option<ShowDetails>> {
+        Box::pin(fetch_show_details(
+            self.client.clone(),
+            self.site.clone(),
+            show_slug,
+        ))
+    }
+}
@@ -0,0 +1,307 @@
+//! Notifications of what a run added, such as a new film premiering at a cinema, or of
+//! anomalous runs, sent to a webhook, Discord, Telegram and/or by email.
+
+use std::{env, fmt};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    transport::smtp::authentication::Credentials,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{FromRow, PgPool};
+use tracing::{info, warn};
+
+use super::{anomaly::VolumeAnomaly, movies::PATHE_TIMEZONE, util::Client};
+
+/// Discord rejects messages longer than this
+const DISCORD_MAX_LENGTH: usize = 2000;
+
+/// Telegram rejects messages longer than this
+const TELEGRAM_MAX_LENGTH: usize = 4096;
+
+/// Where the notifications of a job are sent, every configured channel receives all
+/// of them
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// URL to which the notifications are POSTed as JSON
+    webhook: Option<String>,
+    /// URL of a Discord webhook
+    discord: Option<String>,
+    telegram: Option<TelegramConfig>,
+    email: Option<EmailConfig>,
+}
+
+/// The bot token defaults to the `TELEGRAM_BOT_TOKEN` environment variable
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramConfig {
+    chat_id: String,
+    bot_token: Option<String>,
+}
+
+/// The SMTP credentials default to the `SMTP_USERNAME` and `SMTP_PASSWORD`
+/// environment variables
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    smtp_host: String,
+    from: String,
+    to: Vec<String>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+/// A show which got its first showtimes during the latest run
+#[derive(Debug, FromRow, Serialize)]
+pub struct Premiere {
+    pub show_slug: String,
+    pub title: String,
+    /// Name of the cinema of the first showtime
+    pub cinema: String,
+    pub time: DateTime<Utc>,
+}
+
+impl fmt::Display for Premiere {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "New movie {} premieres at {} on {}",
+            self.title,
+            self.cinema,
+            self.time
+                .with_timezone(&PATHE_TIMEZONE)
+                .format("%d-%m-%Y %H:%M")
+        )
+    }
+}
+
+/// Shows with upcoming showtimes in the latest run of the job which had none in the
+/// run before it, together with their first showtime. Nothing is new when the job
+/// ran only once, such that the first run does not notify about every show.
+pub async fn latest_premieres(pool: &PgPool, jobname: &str) -> Result<Vec<Premiere>> {
+    let runs: Vec<i64> =
+        sqlx::query_scalar("SELECT id FROM joblogs WHERE jobname = $1 ORDER BY id DESC LIMIT 2")
+            .bind(jobname)
+            .fetch_all(pool)
+            .await?;
+    let [run_b, run_a] = runs[..] else {
+        return Ok(vec![]);
+    };
+    Ok(sqlx::query_as(
+        r#"SELECT DISTINCT ON (b.show_slug)
+            b.show_slug, s.title, c.name AS cinema, b.time
+        FROM snapshot_showtimes b
+        JOIN shows s ON s.slug = b.show_slug
+        JOIN cinemas c ON c.slug = b.cinema_slug
+        WHERE b.run_id = $2 AND NOT EXISTS (
+            SELECT 1 FROM snapshot_showtimes a
+            WHERE a.run_id = $1 AND a.show_slug = b.show_slug
+        )
+        ORDER BY b.show_slug, b.time"#,
+    )
+    .bind(run_a)
+    .bind(run_b)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Joins the lines into as few messages as possible of at most `max_length` bytes
+fn pack_messages(lines: &[String], max_length: usize) -> Vec<String> {
+    let mut messages: Vec<String> = vec![];
+    for line in lines {
+        match messages.last_mut() {
+            Some(message) if message.len() + 1 + line.len() <= max_length => {
+                message.push('\n');
+                message.push_str(line);
+            }
+            _ => messages.push(line.clone()),
+        }
+    }
+    messages
+}
+
+impl NotifyConfig {
+    pub fn with_webhook(mut self, url: impl Into<String>) -> Self {
+        self.webhook = Some(url.into());
+        self
+    }
+
+    pub fn with_discord(mut self, url: impl Into<String>) -> Self {
+        self.discord = Some(url.into());
+        self
+    }
+
+    pub fn with_telegram(mut self, chat_id: impl Into<String>) -> Self {
+        self.telegram = Some(TelegramConfig {
+            chat_id: chat_id.into(),
+            bot_token: None,
+        });
+        self
+    }
+
+    pub fn with_email(
+        mut self,
+        smtp_host: impl Into<String>,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        self.email = Some(EmailConfig {
+            smtp_host: smtp_host.into(),
+            from: from.into(),
+            to: vec![to.into()],
+            username: None,
+            password: None,
+        });
+        self
+    }
+
+    /// Sends the premieres of the latest run of the job to every configured channel.
+    /// Notifying does not fail the run, so failures are only logged.
+    pub async fn notify_run(&self, pool: &PgPool, jobname: &str) {
+        let premieres = match latest_premieres(pool, jobname).await {
+            Ok(premieres) if premieres.is_empty() => return,
+            Ok(premieres) => premieres,
+            Err(err) => {
+                warn!("Could not determine what to notify about: {err:#}");
+                return;
+            }
+        };
+        let lines: Vec<String> = premieres.iter().map(Premiere::to_string).collect();
+        let notifications = premieres
+            .iter()
+            .map(|premiere| json!({ "message": premiere.to_string(), "premiere": premiere }))
+            .collect();
+        let subject = format!("New premieres found by {jobname}");
+        self.send(jobname, &subject, &lines, notifications).await;
+        info!("Notified about {} premieres", premieres.len());
+    }
+
+    /// Alerts every configured channel about the tables of which the latest run of the
+    /// job wrote a lot more or less rows than usual. Like notifying, alerting does not
+    /// fail the run.
+    pub async fn alert_anomalies(&self, jobname: &str, anomalies: &[VolumeAnomaly]) {
+        if anomalies.is_empty() {
+            return;
+        }
+        let lines: Vec<String> = anomalies
+            .iter()
+            .map(|anomaly| format!("{jobname} wrote {anomaly}"))
+            .collect();
+        let notifications = anomalies
+            .iter()
+            .zip(&lines)
+            .map(|(anomaly, line)| {
+                json!({
+                    "message": line,
+                    "anomaly": {
+                        "table": anomaly.table,
+                        "submitted": anomaly.submitted,
+                        "baseline": anomaly.baseline,
+                    },
+                })
+            })
+            .collect();
+        let subject = format!("Anomalous run of {jobname}");
+        self.send(jobname, &subject, &lines, notifications).await;
+        info!("Alerted about {} anomalous tables", anomalies.len());
+    }
+
+    /// Sends the lines to every configured channel, the webhook receives the
+    /// `notifications` instead
+    async fn send(
+        &self,
+        jobname: &str,
+        subject: &str,
+        lines: &[String],
+        notifications: Vec<Value>,
+    ) {
+        let client = Client::new().with_max_retries(3);
+        let sent = [
+            (
+                "webhook",
+                self.send_webhook(&client, jobname, notifications).await,
+            ),
+            ("Discord", self.send_discord(&client, lines).await),
+            ("Telegram", self.send_telegram(&client, lines).await),
+            ("email", self.send_email(subject, lines).await),
+        ];
+        for (channel, result) in sent {
+            if let Err(err) = result {
+                warn!("Could not send notifications by {channel}: {err:#}");
+            }
+        }
+    }
+
+    async fn send_webhook(
+        &self,
+        client: &Client,
+        jobname: &str,
+        notifications: Vec<Value>,
+    ) -> Result<()> {
+        let Some(url) = &self.webhook else {
+            return Ok(());
+        };
+        client
+            .post(
+                url,
+                json!({ "job": jobname, "notifications": notifications }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn send_discord(&self, client: &Client, lines: &[String]) -> Result<()> {
+        let Some(url) = &self.discord else {
+            return Ok(());
+        };
+        for message in pack_messages(lines, DISCORD_MAX_LENGTH) {
+            client.post(url, json!({ "content": message })).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_telegram(&self, client: &Client, lines: &[String]) -> Result<()> {
+        let Some(telegram) = &self.telegram else {
+            return Ok(());
+        };
+        let token = match &telegram.bot_token {
+            Some(token) => token.clone(),
+            None => env::var("TELEGRAM_BOT_TOKEN").context("No Telegram bot token")?,
+        };
+        let url = format!("https://api.telegram.org/bot{token}/sendMessage");
+        for message in pack_messages(lines, TELEGRAM_MAX_LENGTH) {
+            client
+                .post(
+                    &url,
+                    json!({ "chat_id": telegram.chat_id, "text": message }),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn send_email(&self, subject: &str, lines: &[String]) -> Result<()> {
+        let Some(email) = &self.email else {
+            return Ok(());
+        };
+        let mut transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&email.smtp_host)?;
+        let username = email.username.clone().or(env::var("SMTP_USERNAME").ok());
+        let password = email.password.clone().or(env::var("SMTP_PASSWORD").ok());
+        if let (Some(username), Some(password)) = (username, password) {
+            transport = transport.credentials(Credentials::new(username, password));
+        }
+        let mut message = Message::builder()
+            .from(email.from.parse()?)
+            .subject(subject);
+        for to in &email.to {
+            message = message.to(to.parse()?);
+        }
+        transport
+            .build()
+            .send(message.body(lines.join("\n"))?)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ParseError {
+    #[error("Invalid number {0:?}")]
+    Number(String),
+    #[error("Invalid price {0:?}")]
+    Price(String),
+    #[error("Invalid date {0:?}")]
+    Date(String),
+    #[error("Invalid volume {0:?}")]
+    Volume(String),
+    #[error("Invalid alcohol percentage {0:?}")]
+    Abv(String),
+}
+
+/// A price in cents, together with its ISO 4217 currency code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Price {
+    pub cents: i64,
+    pub currency: &'static str,
+}
+
+static CURRENCIES: [(&str, &str); 6] = [
+    ("€", "EUR"),
+    ("eur", "EUR"),
+    ("$", "USD"),
+    ("usd", "USD"),
+    ("£", "GBP"),
+    ("gbp", "GBP"),
+];
+
+static MONTHS: [&str; 12] = [
+    "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+];
+
+static WEEKDAYS: [(&str, Weekday); 7] = [
+    ("ma", Weekday::Mon),
+    ("di", Weekday::Tue),
+    ("wo", Weekday::Wed),
+    ("do", Weekday::Thu),
+    ("vr", Weekday::Fri),
+    ("za", Weekday::Sat),
+    ("zo", Weekday::Sun),
+];
+
+/// Parses a number which may be formatted the Dutch way ("1.299,95"). When both
+/// separators occur, the last one is the decimal separator. A lone comma is always
+/// decimal, lone dots only count as thousands separators when every group after
+/// them has exactly three digits, such that "5.2" is still five point two.
+pub fn parse_number(input: &str) -> Result<f64, ParseError> {
+    let error = || ParseError::Number(input.to_string());
+    let trimmed: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\u{a0}')
+        .collect();
+    // "12,-" and "12,--" are common notations for a round amount
+    let trimmed = trimmed.trim_end_matches(['-', '\u{2013}']);
+    let trimmed = trimmed.strip_suffix(',').unwrap_or(trimmed);
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, trimmed),
+    };
+    if digits.is_empty()
+        || !digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    {
+        return Err(error());
+    }
+
+    let decimal = match (digits.rfind(','), digits.rfind('.')) {
+        (Some(comma), Some(dot)) => Some(comma.max(dot)),
+        (Some(comma), None) => Some(comma),
+        (None, Some(dot)) => {
+            let thousands = digits.split('.').skip(1).all(|group| group.len() == 3);
+            (!thousands).then_some(dot)
+        }
+        (None, None) => None,
+    };
+    let (integer, fraction) = match decimal {
+        Some(idx) => (&digits[..idx], &digits[idx + 1..]),
+        None => (digits, ""),
+    };
+    // Thousands separators have to separate groups of exactly three digits
+    if fraction.contains(['.', ','])
+        || !integer
+            .split(['.', ','])
+            .skip(1)
+            .all(|group| group.len() == 3)
+    {
+        return Err(error());
+    }
+    let integer: String = integer.chars().filter(|c| c.is_ascii_digit()).collect();
+    let value: f64 = format!(
+        "{}.{fraction}",
+        if integer.is_empty() { "0" } else { &integer }
+    )
+    .parse()
+    .map_err(|_| error())?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Parses a price such as "€ 1.299,95", "EUR 3,50" or "12,-". Euro is assumed when
+/// no currency is given.
+pub fn parse_price(input: &str) -> Result<Price, ParseError> {
+    let lowercase = input.trim().to_lowercase();
+    let (currency, amount) = CURRENCIES
+        .iter()
+        .find_map(|(symbol, code)| {
+            lowercase
+                .strip_prefix(symbol)
+                .or_else(|| lowercase.strip_suffix(symbol))
+                .map(|amount| (*code, amount))
+        })
+        .unwrap_or(("EUR", &lowercase));
+    let amount = parse_number(amount).map_err(|_| ParseError::Price(input.to_string()))?;
+    Ok(Price {
+        cents: (amount * 100.0).round() as i64,
+        currency,
+    })
+}
+
+/// Parses a date such as "za 14 jun", "14 juni 2025" or "14-06-2025". Without a year
+/// the occurrence closest to `reference` is taken, matching the weekday if given.
+pub fn parse_date(input: &str, reference: NaiveDate) -> Result<NaiveDate, ParseError> {
+    let error = || ParseError::Date(input.to_string());
+    let lowercase = input.trim().to_lowercase();
+
+    let numeric: Vec<&str> = lowercase.split(['-', '/']).collect();
+    if let [day, month, year] = numeric[..] {
+        return NaiveDate::from_ymd_opt(
+            year.parse().map_err(|_| error())?,
+            month.parse().map_err(|_| error())?,
+            day.parse().map_err(|_| error())?,
+        )
+        .ok_or_else(error);
+    }
+
+    let mut words = lowercase
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '.')
+        .filter(|word| !word.is_empty())
+        .peekable();
+    let weekday = words.peek().and_then(|word| {
+        WEEKDAYS
+            .iter()
+            .find(|(prefix, _)| {
+                word.starts_with(prefix) && !word.starts_with(|c: char| c.is_ascii_digit())
+            })
+            .map(|(_, weekday)| *weekday)
+    });
+    if weekday.is_some() {
+        words.next();
+    }
+    let day: u32 = words
+        .next()
+        .ok_or_else(error)?
+        .parse()
+        .map_err(|_| error())?;
+    let month_word = words.next().ok_or_else(error)?;
+    let month = MONTHS
+        .iter()
+        .position(|month| month_word.starts_with(month))
+        // "maart" is abbreviated as "mrt"
+        .or_else(|| month_word.starts_with("maa").then_some(2))
+        .ok_or_else(error)? as u32
+        + 1;
+    if let Some(year) = words.next() {
+        let date = NaiveDate::from_ymd_opt(year.parse().map_err(|_| error())?, month, day)
+            .ok_or_else(error)?;
+        return match weekday {
+            Some(weekday) if date.weekday() != weekday => Err(error()),
+            _ => Ok(date),
+        };
+    }
+
+    [reference.year() - 1, reference.year(), reference.year() + 1]
+        .into_iter()
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .filter(|date| weekday.is_none_or(|weekday| date.weekday() == weekday))
+        .min_by_key(|date| (*date - reference).num_days().abs())
+        .ok_or_else(error)
+}
+
+/// Parses a volume such as "33cl", "0,5 l" or "6 x 33 cl" to the total amount of
+/// millilitres
+pub fn parse_volume(input: &str) -> Result<f64, ParseError> {
+    let error = || ParseError::Volume(input.to_string());
+    let lowercase = input.trim().to_lowercase().replace(' ', "");
+    let (count, volume) = match lowercase.split_once(['x', '×']) {
+        Some((count, volume)) => (count.parse::<u32>().map_err(|_| error())?, volume),
+        None => (1, lowercase.as_str()),
+    };
+    let unit_start = volume.find(|c: char| c.is_alphabetic()).ok_or_else(error)?;
+    let (amount, unit) = volume.split_at(unit_start);
+    let factor = match unit.trim_end_matches('.') {
+        "ml" => 1.0,
+        "cl" => 10.0,
+        "dl" => 100.0,
+        "l" | "ltr" | "liter" | "litre" => 1000.0,
+        _ => return Err(error()),
+    };
+    let amount = parse_number(amount).map_err(|_| error())?;
+    Ok(count as f64 * amount * factor)
+}
+
+/// Parses an alcohol percentage such as "5,2% vol" or "alc. 5.2%"
+pub fn parse_abv(input: &str) -> Result<f64, ParseError> {
+    let error = || ParseError::Abv(input.to_string());
+    let lowercase = input.trim().to_lowercase();
+    let (amount, _) = lowercase.split_once('%').ok_or_else(error)?;
+    let amount = amount
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .trim();
+    let abv = parse_number(amount).map_err(|_| error())?;
+    if !(0.0..=100.0).contains(&abv) {
+        return Err(error());
+    }
+    Ok(abv)
+}
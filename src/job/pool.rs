@@ -0,0 +1,105 @@
+use std::{env, str::FromStr, time::Duration};
+
+use anyhow::{Context, Result};
+use sqlx::{
+    PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+
+/// Options of the database connection pools, the defaults of which can be overridden
+/// through the environment
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    max_connections: u32,
+    acquire_timeout: Duration,
+    statement_timeout: Option<Duration>,
+    application_name: String,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptions {
+            max_connections: 10,
+            acquire_timeout: Duration::from_secs(30),
+            statement_timeout: None,
+            application_name: "schraper".to_string(),
+        }
+    }
+}
+
+fn env_var<T: FromStr>(key: &str) -> Result<Option<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    env::var(key)
+        .ok()
+        .map(|value| value.parse().with_context(|| format!("Invalid {key}")))
+        .transpose()
+}
+
+impl PoolOptions {
+    /// The defaults, overridden by `DATABASE_MAX_CONNECTIONS`,
+    /// `DATABASE_ACQUIRE_TIMEOUT` and `DATABASE_STATEMENT_TIMEOUT` (in seconds) and
+    /// `DATABASE_APPLICATION_NAME` when set
+    pub fn from_env() -> Result<Self> {
+        let mut options = PoolOptions::default();
+        if let Some(max_connections) = env_var("DATABASE_MAX_CONNECTIONS")? {
+            options = options.with_max_connections(max_connections);
+        }
+        if let Some(secs) = env_var("DATABASE_ACQUIRE_TIMEOUT")? {
+            options = options.with_acquire_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = env_var("DATABASE_STATEMENT_TIMEOUT")? {
+            options = options.with_statement_timeout(Duration::from_secs(secs));
+        }
+        if let Ok(application_name) = env::var("DATABASE_APPLICATION_NAME") {
+            options = options.with_application_name(application_name);
+        }
+        Ok(options)
+    }
+
+    /// Most connections a pool opens, 10 by default
+    pub fn with_max_connections(mut self, max_connections: u32) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// How long to wait for a free connection before failing, 30 seconds by default
+    pub fn with_acquire_timeout(mut self, acquire_timeout: Duration) -> Self {
+        self.acquire_timeout = acquire_timeout;
+        self
+    }
+
+    /// Statements running longer are cancelled by Postgres, there is no limit by
+    /// default
+    pub fn with_statement_timeout(mut self, statement_timeout: Duration) -> Self {
+        self.statement_timeout = Some(statement_timeout);
+        self
+    }
+
+    /// Name the connections show up with in `pg_stat_activity`
+    pub fn with_application_name(mut self, application_name: impl Into<String>) -> Self {
+        self.application_name = application_name.into();
+        self
+    }
+
+    /// Connects a pool to the database at the URL
+    pub(super) async fn connect(&self, url: &str) -> Result<PgPool> {
+        let mut options: PgConnectOptions = url.parse()?;
+        options = options.application_name(&self.application_name);
+        if let Some(timeout) = self.statement_timeout {
+            options =
+                options.options([("statement_timeout", format!("{}ms", timeout.as_millis()))]);
+        }
+        self.connect_with(options).await
+    }
+
+    /// Connects a pool with the given connection options, e.g. those of another pool
+    pub(super) async fn connect_with(&self, options: PgConnectOptions) -> Result<PgPool> {
+        Ok(PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .connect_with(options)
+            .await?)
+    }
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Serialize, de::DeserializeOwned};
+use sqlx::{FromRow, PgPool};
+
+/// Upper bound of the delay before a failed task is retried
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// A Postgres backed queue of tasks, stored in the `fetch_tasks` table.
+///
+/// Claimed tasks become invisible for the duration of the visibility timeout. When
+/// a worker crashes (or the process is killed) before completing the task, it will
+/// become visible again after said timeout and will be picked up by another worker,
+/// possibly running in a different process.
+#[derive(Debug, Clone)]
+pub struct TaskQueue {
+    pool: PgPool,
+    name: String,
+    visibility_timeout: Duration,
+    max_attempts: i32,
+    /// Delay before the first retry of a failed task, doubled for every next attempt
+    retry_backoff: Duration,
+    /// Time for which finished and given up tasks are kept, see `purge`
+    retention: Duration,
+}
+
+#[derive(Debug)]
+pub struct Task<T> {
+    pub id: i64,
+    pub attempts: i32,
+    pub payload: T,
+}
+
+#[derive(FromRow)]
+struct TaskRow {
+    id: i64,
+    attempts: i32,
+    payload: serde_json::Value,
+}
+
+impl TaskQueue {
+    pub fn new(pool: PgPool, name: impl Into<String>) -> Self {
+        TaskQueue {
+            pool,
+            name: name.into(),
+            visibility_timeout: Duration::from_secs(60 * 10),
+            max_attempts: 3,
+            retry_backoff: Duration::from_secs(30),
+            retention: Duration::from_secs(60 * 60 * 24 * 7),
+        }
+    }
+
+    pub fn with_visibility_timeout(mut self, visibility_timeout: Duration) -> Self {
+        self.visibility_timeout = visibility_timeout;
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Attempts after which a failing task is given up on
+    pub fn max_attempts(&self) -> i32 {
+        self.max_attempts
+    }
+
+    pub fn pool(&self) -> PgPool {
+        self.pool.clone()
+    }
+
+    pub async fn enqueue<T: Serialize>(&self, tasks: &[T]) -> Result<()> {
+        let payloads = tasks
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        sqlx::query("INSERT INTO fetch_tasks(queue, payload) SELECT $1, * FROM UNNEST($2::jsonb[])")
+            .bind(&self.name)
+            .bind(payloads)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Claims the oldest visible task, returns `None` when there is nothing to do
+    pub async fn claim<T: DeserializeOwned>(&self) -> Result<Option<Task<T>>> {
+        let row: Option<TaskRow> = sqlx::query_as(
+            r#"UPDATE fetch_tasks
+            SET attempts = attempts + 1,
+                visible_at = current_timestamp + make_interval(secs => $2)
+            WHERE id = (
+                SELECT id FROM fetch_tasks
+                WHERE queue = $1
+                  AND finished_at IS NULL
+                  AND visible_at <= current_timestamp
+                  AND attempts < $3
+                ORDER BY id
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, attempts, payload"#,
+        )
+        .bind(&self.name)
+        .bind(self.visibility_timeout.as_secs_f64())
+        .bind(self.max_attempts)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(Task {
+                id: row.id,
+                attempts: row.attempts,
+                payload: serde_json::from_value(row.payload)?,
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn complete(&self, id: i64) -> Result<()> {
+        sqlx::query("UPDATE fetch_tasks SET finished_at = current_timestamp WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the tasks which finished, or were given up on, longer than the retention
+    /// ago. Returns the amount of deleted tasks.
+    pub async fn purge(&self) -> Result<u64> {
+        let deleted = sqlx::query(
+            r#"DELETE FROM fetch_tasks
+            WHERE queue = $1
+              AND (finished_at IS NOT NULL OR attempts >= $2)
+              AND coalesce(finished_at, visible_at) < current_timestamp - make_interval(secs => $3)"#,
+        )
+        .bind(&self.name)
+        .bind(self.max_attempts)
+        .bind(self.retention.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+        Ok(deleted.rows_affected())
+    }
+
+    /// Records the error and makes the task visible again for a retry after backing
+    /// off, until it was attempted `max_attempts` times
+    pub async fn fail(&self, id: i64, error: &anyhow::Error) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE fetch_tasks SET
+                last_error = $2,
+                visible_at = current_timestamp
+                    + make_interval(secs => least($3 * power(2, attempts - 1), $4))
+            WHERE id = $1"#,
+        )
+        .bind(id)
+        .bind(format!("{error:#}"))
+        .bind(self.retry_backoff.as_secs_f64())
+        .bind(MAX_RETRY_BACKOFF.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
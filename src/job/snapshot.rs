@@ -0,0 +1,40 @@
+use anyhow::Result;
+use sqlx::PgPool;
+
+/// Logs the run of a job in `joblogs`, together with the amount of shows of which the
+/// lookup failed, and snapshots the current shows, upcoming showtimes and ratings under
+/// the id of the run, such that runs can be diffed.
+pub async fn finish_run(pool: &PgPool, jobname: &str, failed_lookups: usize) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+    let run_id: i64 = sqlx::query_scalar(
+        "INSERT INTO joblogs(jobname, failed_lookups) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(jobname)
+    .bind(failed_lookups as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("INSERT INTO snapshot_shows SELECT $1, slug, title FROM shows")
+        .bind(run_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"INSERT INTO snapshot_showtimes
+        SELECT $1, show_slug, cinema_slug, time, auditorium_name FROM showtimes
+        WHERE time >= current_timestamp"#,
+    )
+    .bind(run_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO snapshot_ratings
+        SELECT $1, s.slug, r.slug, r.critics_score, r.audience_score
+        FROM shows s JOIN ratings r ON r.slug = s.rating_slug"#,
+    )
+    .bind(run_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(run_id)
+}
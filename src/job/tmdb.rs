@@ -0,0 +1,127 @@
+//! The Movie Database (TMDb) as a rating source next to Rotten Tomatoes, of which
+//! the Algolia search endpoint is not an official API.
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use reqwest::Url;
+use serde::Deserialize;
+use sqlx_batch::BatchInserter;
+
+use crate::job::{
+    matching::{Candidate, ExactAlias, Levenshtein, Query, Weighted, YearDistance, best_match},
+    util::Client,
+};
+
+static TMDB_SEARCH_URL: &str = "https://api.themoviedb.org/3/search/movie";
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    results: Vec<TmdbMovie>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbMovie {
+    id: i32,
+    title: String,
+    original_title: Option<String>,
+    /// Empty for unreleased films
+    release_date: Option<String>,
+    vote_average: Option<f64>,
+    vote_count: Option<i32>,
+    popularity: Option<f64>,
+}
+
+impl TmdbMovie {
+    fn release_year(&self) -> Option<i32> {
+        self.release_date
+            .as_deref()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .map(|date| date.year())
+    }
+}
+
+impl Candidate for TmdbMovie {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn year(&self) -> Option<i32> {
+        self.release_year()
+    }
+
+    fn aliases(&self) -> &[String] {
+        self.original_title.as_slice()
+    }
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "tmdb_ratings"]
+pub struct TmdbRating {
+    #[key]
+    pub tmdb_id: i32,
+    title: String,
+    original_title: Option<String>,
+    release_year: Option<i32>,
+    /// Average vote on a scale of 0 to 10
+    vote_average: Option<f64>,
+    vote_count: Option<i32>,
+    popularity: Option<f64>,
+}
+
+/// Link of a show to its TMDb entry, like `shows.rating_slug` for Rotten Tomatoes
+#[derive(Debug, BatchInserter)]
+#[pgtable = "tmdb_rating_shows"]
+pub struct TmdbRatingShow {
+    #[key]
+    pub show_slug: String,
+    pub tmdb_id: i32,
+    pub match_score: f64,
+}
+
+impl From<TmdbMovie> for TmdbRating {
+    fn from(movie: TmdbMovie) -> Self {
+        TmdbRating {
+            release_year: movie.release_year(),
+            tmdb_id: movie.id,
+            title: movie.title,
+            original_title: movie.original_title,
+            vote_average: movie.vote_average,
+            vote_count: movie.vote_count,
+            popularity: movie.popularity,
+        }
+    }
+}
+
+/// TMDb lists both the translated and original title, so matching either exactly
+/// counts for as much as the edit distance between the titles
+fn tmdb_matcher() -> Weighted {
+    Weighted::new()
+        .with(0.5, Levenshtein)
+        .with(0.5, ExactAlias)
+        .with(0.1, YearDistance)
+}
+
+/// Searches TMDb for the title, returning the best hit when it scores at most
+/// `max_score` together with its score
+pub async fn fetch_tmdb_rating(
+    client: &Client,
+    api_key: &str,
+    title: &str,
+    year: Option<i32>,
+    max_score: Option<f64>,
+) -> Result<Option<(TmdbRating, f64)>> {
+    let url = Url::parse_with_params(
+        TMDB_SEARCH_URL,
+        [
+            ("api_key", api_key),
+            ("query", title),
+            ("include_adult", "false"),
+        ],
+    )?;
+    let response: SearchResponse = client.get_json(url).await?;
+    Ok(
+        best_match(&tmdb_matcher(), response.results, Query { title, year })
+            .filter(|(_, score)| max_score.is_none_or(|max| *score <= max))
+            .map(|(movie, score)| (movie.into(), score)),
+    )
+}
@@ -0,0 +1,371 @@
+use std::{env, time::Duration};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::{Deserialize, de::DeserializeOwned};
+use sqlx::{FromRow, PgPool};
+use sqlx_batch::BatchInserter;
+use tracing::info;
+
+use super::{RunContext, Runnable};
+
+static TRAKT_API: &str = "https://api.trakt.tv";
+static TRAKT_PROVIDER: &str = "trakt";
+
+/// Configuration of the Trakt synchronization. The client id and secret default to
+/// the `TRAKT_CLIENT_ID` and `TRAKT_CLIENT_SECRET` environment variables.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TraktConfig {
+    client_id: String,
+    client_secret: String,
+    /// Slug of the list of the authorized user to which new releases are pushed
+    list: Option<String>,
+    /// Shows released at most this many days ago count as newly released
+    release_window_days: i32,
+}
+
+impl Default for TraktConfig {
+    fn default() -> Self {
+        TraktConfig {
+            client_id: env::var("TRAKT_CLIENT_ID").unwrap_or_default(),
+            client_secret: env::var("TRAKT_CLIENT_SECRET").unwrap_or_default(),
+            list: None,
+            release_window_days: 7,
+        }
+    }
+}
+
+impl TraktConfig {
+    pub fn with_list(mut self, list: impl Into<String>) -> Self {
+        self.list = Some(list.into());
+        self
+    }
+
+    pub fn with_release_window(mut self, days: i32) -> Self {
+        self.release_window_days = days;
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_url: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: i64,
+    created_at: i64,
+}
+
+#[derive(Debug, FromRow)]
+struct StoredToken {
+    access_token: String,
+    refresh_token: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TraktIds {
+    trakt: i64,
+    imdb: Option<String>,
+    tmdb: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TraktMovie {
+    title: String,
+    year: Option<i32>,
+    ids: TraktIds,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResult {
+    movie: TraktMovie,
+}
+
+#[derive(Debug, Deserialize)]
+struct WatchlistEntry {
+    listed_at: Option<String>,
+    movie: TraktMovie,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "watchlist"]
+struct WatchlistItem {
+    #[key]
+    source: String,
+    #[key]
+    external_id: String,
+    title: String,
+    year: Option<i32>,
+    imdb_id: Option<String>,
+    tmdb_id: Option<i32>,
+    listed_at: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct ReleasedShow {
+    title: String,
+    year: Option<i32>,
+}
+
+/// Thin wrapper around the Trakt API. Not built on `util::Client`, since the OAuth
+/// device flow relies on status codes which that client treats as errors to retry.
+struct Trakt {
+    client: reqwest::Client,
+    config: TraktConfig,
+    pool: PgPool,
+}
+
+impl Trakt {
+    fn new(config: TraktConfig, pool: PgPool) -> Result<Self> {
+        if config.client_id.is_empty() || config.client_secret.is_empty() {
+            bail!("Trakt client id and secret are required");
+        }
+        Ok(Trakt {
+            client: reqwest::Client::new(),
+            config,
+            pool,
+        })
+    }
+
+    async fn store_token(&self, token: TokenResponse) -> Result<String> {
+        let expires_at = DateTime::from_timestamp(token.created_at + token.expires_in, 0)
+            .context("Invalid token expiry")?;
+        sqlx::query(
+            r#"INSERT INTO oauth_tokens(provider, access_token, refresh_token, expires_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (provider) DO UPDATE SET
+                access_token = excluded.access_token,
+                refresh_token = excluded.refresh_token,
+                expires_at = excluded.expires_at"#,
+        )
+        .bind(TRAKT_PROVIDER)
+        .bind(&token.access_token)
+        .bind(&token.refresh_token)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(token.access_token)
+    }
+
+    /// Runs the OAuth device flow, the user has to enter the printed code on the
+    /// Trakt website after which the obtained tokens are persisted.
+    async fn authorize(&self) -> Result<String> {
+        let code: DeviceCode = self
+            .client
+            .post(format!("{TRAKT_API}/oauth/device/code"))
+            .json(&serde_json::json!({ "client_id": self.config.client_id }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        println!(
+            "Authorize schraper on Trakt by entering code {} at {}",
+            code.user_code, code.verification_url
+        );
+
+        let mut waited = 0;
+        while waited < code.expires_in {
+            tokio::time::sleep(Duration::from_secs(code.interval)).await;
+            waited += code.interval;
+
+            let response = self
+                .client
+                .post(format!("{TRAKT_API}/oauth/device/token"))
+                .json(&serde_json::json!({
+                    "code": code.device_code,
+                    "client_id": self.config.client_id,
+                    "client_secret": self.config.client_secret,
+                }))
+                .send()
+                .await?;
+            match response.status() {
+                StatusCode::OK => return self.store_token(response.json().await?).await,
+                // Pending or polling too quickly, keep waiting
+                StatusCode::BAD_REQUEST | StatusCode::TOO_MANY_REQUESTS => continue,
+                status => bail!("Trakt authorization failed with status {status}"),
+            }
+        }
+        bail!("Trakt authorization code expired")
+    }
+
+    /// Returns a valid access token, refreshing it when needed. Authorizing requires the
+    /// user, so it is left to `TraktSync::authorize` instead of blocking the other jobs.
+    async fn access_token(&self) -> Result<String> {
+        let stored: Option<StoredToken> = sqlx::query_as(
+            "SELECT access_token, refresh_token, expires_at FROM oauth_tokens WHERE provider = $1",
+        )
+        .bind(TRAKT_PROVIDER)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match stored {
+            None => bail!("Trakt is not authorized yet, run `schraper trakt-auth` first"),
+            Some(token) if token.expires_at > Utc::now() + chrono::Duration::days(1) => {
+                Ok(token.access_token)
+            }
+            Some(token) => {
+                let refreshed: TokenResponse = self
+                    .client
+                    .post(format!("{TRAKT_API}/oauth/token"))
+                    .json(&serde_json::json!({
+                        "refresh_token": token.refresh_token,
+                        "client_id": self.config.client_id,
+                        "client_secret": self.config.client_secret,
+                        "redirect_uri": "urn:ietf:wg:oauth:2.0:oob",
+                        "grant_type": "refresh_token",
+                    }))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                self.store_token(refreshed).await
+            }
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str, token: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{TRAKT_API}{path}"))
+            .header("trakt-api-version", "2")
+            .header("trakt-api-key", &self.config.client_id)
+            .bearer_auth(token)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, token: &str) -> Result<T> {
+        Ok(self
+            .request(reqwest::Method::GET, path, token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Adds the shows released within the release window to the configured list
+    async fn push_releases(&self, list: &str, token: &str) -> Result<usize> {
+        let released: Vec<ReleasedShow> = sqlx::query_as(
+            r#"SELECT
+                COALESCE(r.title, s.title) AS title,
+                COALESCE(r.release_year, EXTRACT(YEAR FROM s.release_at::date)::integer) AS year
+            FROM shows s
+            LEFT JOIN ratings r ON r.slug = s.rating_slug
+            WHERE s.release_at::date BETWEEN current_date - $1 AND current_date"#,
+        )
+        .bind(self.config.release_window_days)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut ids = vec![];
+        for show in released {
+            let mut query = vec![("query", show.title)];
+            if let Some(year) = show.year {
+                query.push(("years", year.to_string()));
+            }
+            let results: Vec<SearchResult> = self
+                .request(reqwest::Method::GET, "/search/movie", token)
+                .query(&query)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            if let Some(result) = results.into_iter().next() {
+                ids.push(serde_json::json!({ "ids": { "trakt": result.movie.ids.trakt } }));
+            }
+        }
+
+        if !ids.is_empty() {
+            self.request(
+                reqwest::Method::POST,
+                &format!("/users/me/lists/{list}/items"),
+                token,
+            )
+            .json(&serde_json::json!({ "movies": ids }))
+            .send()
+            .await?
+            .error_for_status()?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Replaces the Trakt entries in the `watchlist` table by the current watchlist
+    async fn pull_watchlist(&self, token: &str) -> Result<usize> {
+        let entries: Vec<WatchlistEntry> = self.get("/users/me/watchlist/movies", token).await?;
+        let items: Vec<WatchlistItem> = entries
+            .into_iter()
+            .map(|entry| WatchlistItem {
+                source: TRAKT_PROVIDER.to_string(),
+                external_id: entry.movie.ids.trakt.to_string(),
+                title: entry.movie.title,
+                year: entry.movie.year,
+                imdb_id: entry.movie.ids.imdb,
+                tmdb_id: entry.movie.ids.tmdb,
+                listed_at: entry.listed_at,
+            })
+            .collect();
+        let external_ids: Vec<String> = items.iter().map(|item| item.external_id.clone()).collect();
+        let count = items.len();
+
+        let mut tx = self.pool.begin().await?;
+        WatchlistItemInserter::from(items)
+            .build()
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM watchlist WHERE source = $1 AND external_id <> ALL($2)")
+            .bind(TRAKT_PROVIDER)
+            .bind(external_ids)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(count)
+    }
+}
+
+/// Synchronizes with Trakt: newly released films are pushed to a Trakt list and the
+/// Trakt watchlist of the user is read back into the `watchlist` table.
+#[derive(Debug)]
+pub struct TraktSync {
+    pub pool: PgPool,
+    pub config: TraktConfig,
+}
+impl TraktSync {
+    /// Authorizes the synchronization through the OAuth device flow, waiting until the
+    /// printed code is entered on the Trakt website
+    pub async fn authorize(&self) -> Result<()> {
+        let trakt = Trakt::new(self.config.clone(), self.pool.clone())?;
+        trakt.authorize().await?;
+        info!("Authorized the Trakt synchronization");
+        Ok(())
+    }
+}
+impl Runnable for TraktSync {
+    async fn run(&self, _context: &RunContext) -> Result<()> {
+        let trakt = Trakt::new(self.config.clone(), self.pool.clone())?;
+        let token = trakt.access_token().await?;
+
+        if let Some(list) = &self.config.list {
+            let pushed = trakt.push_releases(list, &token).await?;
+            info!("Pushed {pushed} new releases to Trakt list {list}");
+        }
+        let pulled = trakt.pull_watchlist(&token).await?;
+
+        sqlx::query("INSERT INTO joblogs(jobname) VALUES ('traktsync')")
+            .execute(&self.pool)
+            .await?;
+        info!("Synchronized {pulled} Trakt watchlist items");
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+//! Checks on the fetched data which run before anything is written, such that a broken
+//! upstream response does not overwrite the dataset.
+
+use std::{collections::HashSet, fmt::Debug, hash::Hash};
+
+use anyhow::{Result, bail};
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::warn;
+
+/// What happens to a run of which a check failed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    /// Fail the run before anything is written
+    #[default]
+    Abort,
+    /// Log the failed checks and write the data anyway
+    Warn,
+}
+
+/// Checks which run on the data fetched by a job
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ValidationConfig {
+    pub on_failure: OnFailure,
+    /// Shows and ratings should have a title
+    pub non_empty_titles: bool,
+    /// Shows should have a positive duration
+    pub positive_durations: bool,
+    /// Cinemas, shows and showtimes should be listed only once
+    pub unique_keys: bool,
+    /// Percentage of records which may fail the title and duration checks, since a
+    /// few shows without a duration are normal
+    pub max_invalid_percentage: f64,
+    /// Largest change in the amount of shows compared to the previous run, as a
+    /// percentage, `None` does not compare
+    pub max_count_change: Option<f64>,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig {
+            on_failure: OnFailure::Abort,
+            non_empty_titles: true,
+            positive_durations: true,
+            unique_keys: true,
+            max_invalid_percentage: 10.0,
+            max_count_change: Some(50.0),
+        }
+    }
+}
+
+/// Collects the failed checks of a run, without a config every check passes
+#[derive(Debug, Default)]
+pub struct Validation {
+    config: Option<ValidationConfig>,
+    failures: Vec<String>,
+}
+
+impl Validation {
+    pub fn new(config: Option<ValidationConfig>) -> Self {
+        Validation {
+            config,
+            failures: vec![],
+        }
+    }
+
+    /// Descriptions of the checks which failed so far
+    pub fn failures(&self) -> &[String] {
+        &self.failures
+    }
+
+    fn check_share(&mut self, kind: &str, problem: &str, invalid: usize, total: usize) {
+        let Some(config) = &self.config else {
+            return;
+        };
+        if total > 0 && 100.0 * invalid as f64 > config.max_invalid_percentage * total as f64 {
+            self.failures
+                .push(format!("{invalid} of {total} {kind} have {problem}"));
+        }
+    }
+
+    /// Checks that the titles of `kind` are not empty
+    pub fn titles<'a>(&mut self, kind: &str, titles: impl IntoIterator<Item = &'a str>) {
+        if !self.config.as_ref().is_some_and(|c| c.non_empty_titles) {
+            return;
+        }
+        let (mut invalid, mut total) = (0, 0);
+        for title in titles {
+            invalid += usize::from(title.trim().is_empty());
+            total += 1;
+        }
+        self.check_share(kind, "an empty title", invalid, total);
+    }
+
+    /// Checks that the durations of the shows are positive
+    pub fn durations(&mut self, durations: impl IntoIterator<Item = i32>) {
+        if !self.config.as_ref().is_some_and(|c| c.positive_durations) {
+            return;
+        }
+        let (mut invalid, mut total) = (0, 0);
+        for duration in durations {
+            invalid += usize::from(duration <= 0);
+            total += 1;
+        }
+        self.check_share("shows", "no positive duration", invalid, total);
+    }
+
+    /// Checks that none of the keys of `kind` occurs more than once
+    pub fn unique<K: Eq + Hash + Debug>(&mut self, kind: &str, keys: impl IntoIterator<Item = K>) {
+        if !self.config.as_ref().is_some_and(|c| c.unique_keys) {
+            return;
+        }
+        let mut seen = HashSet::new();
+        let mut duplicates = vec![];
+        for key in keys {
+            if let Some(key) = seen.replace(key) {
+                duplicates.push(key);
+            }
+        }
+        if let Some(first) = duplicates.first() {
+            self.failures.push(format!(
+                "{} duplicate {kind}, such as {first:?}",
+                duplicates.len()
+            ));
+        }
+    }
+
+    /// Checks that `count` is within the allowed change of the `previous` count
+    pub fn count(&mut self, table: &str, previous: u64, count: usize) {
+        let Some(max_change) = self.config.as_ref().and_then(|c| c.max_count_change) else {
+            return;
+        };
+        if previous == 0 {
+            return;
+        }
+        let change = 100.0 * (count as f64 - previous as f64) / previous as f64;
+        if change.abs() > max_change {
+            self.failures.push(format!(
+                "{count} {table} against {previous} during the previous run ({change:+.0}%)"
+            ));
+        }
+    }
+
+    /// Checks `count` against the amount of rows of `table` submitted by the previous
+    /// run of the job, as recorded in `run_deltas`
+    pub async fn count_since_previous_run(
+        &mut self,
+        pool: &PgPool,
+        jobname: &str,
+        table: &str,
+        count: usize,
+    ) -> Result<()> {
+        if self
+            .config
+            .as_ref()
+            .is_none_or(|c| c.max_count_change.is_none())
+        {
+            return Ok(());
+        }
+        let previous: Option<i64> = sqlx::query_scalar(
+            r#"SELECT submitted FROM run_deltas
+            WHERE jobname = $1 AND table_name = $2
+            ORDER BY run_dt DESC
+            LIMIT 1"#,
+        )
+        .bind(jobname)
+        .bind(table)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(previous) = previous {
+            self.count(table, previous.max(0) as u64, count);
+        }
+        Ok(())
+    }
+
+    /// Logs the failed checks, failing when the run should be aborted
+    pub fn finish(self, jobname: &str) -> Result<()> {
+        let Some(config) = self.config else {
+            return Ok(());
+        };
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+        let failures = self.failures.join("; ");
+        match config.on_failure {
+            OnFailure::Abort => {
+                bail!("Validation of {jobname} failed, nothing is written: {failures}")
+            }
+            OnFailure::Warn => {
+                warn!("Validation of {jobname} failed, writing anyway: {failures}");
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Hourly weather forecasts for the cities of the cinemas from the OpenWeather One Call
+//! API, such that e.g. outdoor screenings can be combined with the weather.
+
+use std::{env, num::NonZeroU32};
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde::Deserialize;
+use sqlx::{FromRow, PgPool};
+use sqlx_batch::BatchInserter;
+use tracing::{info, warn};
+
+use super::{
+    RunContext, Runnable,
+    delta::RunDeltas,
+    notify::NotifyConfig,
+    util::{Client, Clients},
+};
+
+static ONE_CALL_URL: &str = "https://api.openweathermap.org/data/3.0/onecall";
+
+/// Name of the shared client used for the requests to OpenWeather, see
+/// `Jobs::with_client`
+pub const OPENWEATHER_CLIENT: &str = "openweather";
+
+/// Configuration of the weather fetcher. The API key defaults to the
+/// `OPENWEATHER_API_KEY` environment variable.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WeatherConfig {
+    api_key: String,
+    /// Language of the descriptions of the weather, such as `nl`
+    language: String,
+    requests_per_second: Option<NonZeroU32>,
+    /// Alert when the amount of rows written to a table deviates by more than this
+    /// percentage from the previous runs, `None` does not compare
+    max_volume_deviation: Option<f64>,
+    /// Where the anomalous runs are alerted
+    notify: Option<NotifyConfig>,
+    /// Clients shared with other jobs, handed over by `Jobs`
+    #[serde(skip)]
+    clients: Clients,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        WeatherConfig {
+            api_key: env::var("OPENWEATHER_API_KEY").unwrap_or_default(),
+            language: "nl".to_string(),
+            requests_per_second: None,
+            max_volume_deviation: None,
+            notify: None,
+            clients: Clients::default(),
+        }
+    }
+}
+
+impl WeatherConfig {
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = language.into();
+        self
+    }
+
+    /// Flag the run as anomalous when the amount of forecasts deviates by more than
+    /// `percentage` from the previous runs
+    pub fn with_max_volume_deviation(mut self, percentage: f64) -> Self {
+        self.max_volume_deviation = Some(percentage);
+        self
+    }
+
+    pub fn with_notify(mut self, notify: NotifyConfig) -> Self {
+        self.notify = Some(notify);
+        self
+    }
+
+    /// Use the client shared under `OPENWEATHER_CLIENT` when there is, which keeps its
+    /// own rate limit
+    pub fn with_clients(mut self, clients: Clients) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    fn client(&self) -> Result<Client> {
+        if let Some(client) = self.clients.get(OPENWEATHER_CLIENT) {
+            return Ok(client);
+        }
+        Ok(Client::new()
+            .with_limit(self.requests_per_second.unwrap_or(5.try_into()?))
+            .with_max_retries(3))
+    }
+}
+
+/// City with the average location of its cinemas
+#[derive(Debug, FromRow)]
+struct CityLocation {
+    slug: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OneCall {
+    #[serde(default)]
+    hourly: Vec<HourlyWeather>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HourlyWeather {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    dt: DateTime<Utc>,
+    temp: f64,
+    feels_like: Option<f64>,
+    humidity: Option<i32>,
+    /// Percentage of the sky covered by clouds
+    clouds: Option<i32>,
+    wind_speed: Option<f64>,
+    /// Probability of precipitation, between 0 and 1
+    pop: Option<f64>,
+    rain: Option<Precipitation>,
+    #[serde(default)]
+    weather: Vec<Condition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Precipitation {
+    #[serde(rename = "1h")]
+    one_hour: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Condition {
+    /// e.g. "Rain" or "Clear"
+    main: String,
+    description: String,
+}
+
+#[derive(Debug, BatchInserter)]
+#[pgtable = "weather_forecasts"]
+struct Forecast {
+    #[key]
+    city_slug: String,
+    #[key]
+    time: DateTime<Utc>,
+    /// Degrees Celsius
+    temperature: f64,
+    feels_like: Option<f64>,
+    humidity: Option<i32>,
+    cloudiness: Option<i32>,
+    /// Meters per second
+    wind_speed: Option<f64>,
+    precipitation_probability: Option<f64>,
+    /// Millimeters of rain during the hour
+    rain_mm: Option<f64>,
+    condition: Option<String>,
+    description: Option<String>,
+    fetched_at: DateTime<Utc>,
+}
+
+impl HourlyWeather {
+    fn flatten(self, city_slug: &str, fetched_at: DateTime<Utc>) -> Forecast {
+        let condition = self.weather.into_iter().next();
+        Forecast {
+            city_slug: city_slug.to_string(),
+            time: self.dt,
+            temperature: self.temp,
+            feels_like: self.feels_like,
+            humidity: self.humidity,
+            cloudiness: self.clouds,
+            wind_speed: self.wind_speed,
+            precipitation_probability: self.pop,
+            rain_mm: self.rain.and_then(|rain| rain.one_hour),
+            condition: condition.as_ref().map(|c| c.main.clone()),
+            description: condition.map(|c| c.description),
+            fetched_at,
+        }
+    }
+}
+
+async fn fetch_forecast(
+    client: &Client,
+    config: &WeatherConfig,
+    city: &CityLocation,
+) -> Result<OneCall> {
+    let url = Url::parse_with_params(
+        ONE_CALL_URL,
+        [
+            ("lat", city.latitude.to_string()),
+            ("lon", city.longitude.to_string()),
+            ("exclude", "current,minutely,daily,alerts".to_string()),
+            ("units", "metric".to_string()),
+            ("lang", config.language.clone()),
+            ("appid", config.api_key.clone()),
+        ],
+    )?;
+    Ok(client.get_json(url).await?)
+}
+
+/// Fetches the hourly forecasts of the coming two days for every city with a cinema
+/// of which the location is known. Newer forecasts of an hour replace older ones.
+#[derive(Debug)]
+pub struct WeatherFetcher {
+    pub pool: PgPool,
+    pub config: WeatherConfig,
+}
+impl Runnable for WeatherFetcher {
+    async fn run(&self, context: &RunContext) -> Result<()> {
+        if self.config.api_key.is_empty() {
+            bail!("An OpenWeather API key is required");
+        }
+        let client = self.config.client()?;
+        let cities: Vec<CityLocation> = sqlx::query_as(
+            r#"SELECT c.slug, avg(ci.latitude) AS latitude, avg(ci.longitude) AS longitude
+            FROM cities c
+            JOIN cinemas ci ON ci.city_slug = c.slug
+            WHERE ci.latitude IS NOT NULL AND ci.longitude IS NOT NULL
+            GROUP BY c.slug
+            ORDER BY c.slug"#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let fetched_at = Utc::now();
+        let mut forecasts = vec![];
+        let mut failed = 0;
+        for city in &cities {
+            match fetch_forecast(&client, &self.config, city).await {
+                Ok(forecast) => forecasts.extend(
+                    forecast
+                        .hourly
+                        .into_iter()
+                        .map(|hour| hour.flatten(&city.slug, fetched_at)),
+                ),
+                Err(e) => {
+                    warn!("Could not fetch the forecast of {}: {e:#}", city.slug);
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 && failed == cities.len() {
+            bail!("Could not fetch the forecast of any of the {failed} cities");
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let mut deltas = RunDeltas::new("weatherfetcher");
+        deltas
+            .track(
+                &mut tx,
+                "weather_forecasts",
+                forecasts.len(),
+                async |conn| {
+                    ForecastInserter::from(forecasts)
+                        .build()
+                        .execute(conn)
+                        .await
+                },
+            )
+            .await?;
+        info!("Requests of the fetcher for weather: {}", client.stats());
+        deltas
+            .finish(
+                tx,
+                &self.pool,
+                context,
+                self.config.max_volume_deviation,
+                self.config.notify.as_ref(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+//! Webhooks fired after every run of a job, such that external systems can react to
+//! succeeding and failing runs.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::PgPool;
+
+/// Header containing the hex encoded HMAC-SHA256 of the body, prefixed by `sha256=`
+pub static SIGNATURE_HEADER: &str = "X-Schraper-Signature";
+
+/// Receivers get this long to respond, a slow receiver delays the next job
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Endpoint to which a `RunReport` is POSTed after runs
+#[derive(Debug, Clone, Deserialize)]
+pub struct Webhook {
+    url: String,
+    /// Key with which the body is signed, unsigned when `None`
+    secret: Option<String>,
+    /// Only fire after failed runs, or runs which were flagged as anomalous
+    #[serde(default)]
+    failures_only: bool,
+}
+
+/// Outcome of a single run of a job, as sent to the webhooks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub job: String,
+    pub kind: String,
+    pub success: bool,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_secs: f64,
+    /// Rows inserted according to the `run_deltas` recorded during the run
+    pub rows_inserted: i64,
+    /// Shows of which the details or rating could not be looked up, which does not
+    /// fail the run
+    #[serde(default)]
+    pub failed_lookups: i64,
+    pub error: Option<String>,
+    /// Moment of the next attempt when the failed run is retried
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Tables of which the run wrote a lot more or less rows than usual, according to
+    /// the `run_anomalies` recorded during the run
+    #[serde(default)]
+    pub anomalies: Vec<String>,
+}
+
+impl RunReport {
+    /// Report of a run which started at `started_at` and just finished
+    pub async fn new(
+        pool: &PgPool,
+        job: &str,
+        kind: &str,
+        started_at: DateTime<Utc>,
+        result: &Result<()>,
+    ) -> Result<Self> {
+        let finished_at = Utc::now();
+        let rows_inserted: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(sum(inserted), 0)::BIGINT FROM run_deltas WHERE run_dt >= $1",
+        )
+        .bind(started_at)
+        .fetch_one(pool)
+        .await?;
+        let failed_lookups: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(sum(failed_lookups), 0)::BIGINT FROM joblogs WHERE run_dt >= $1",
+        )
+        .bind(started_at)
+        .fetch_one(pool)
+        .await?;
+        let anomalies: Vec<(String, i64, f64)> = sqlx::query_as(
+            r#"SELECT table_name, submitted, baseline FROM run_anomalies
+            WHERE run_dt >= $1
+            ORDER BY run_dt"#,
+        )
+        .bind(started_at)
+        .fetch_all(pool)
+        .await?;
+        Ok(RunReport {
+            job: job.to_string(),
+            kind: kind.to_string(),
+            success: result.is_ok(),
+            started_at,
+            finished_at,
+            duration_secs: (finished_at - started_at).as_seconds_f64(),
+            rows_inserted,
+            failed_lookups,
+            error: result.as_ref().err().map(|err| format!("{err:#}")),
+            retry_at: None,
+            anomalies: anomalies
+                .into_iter()
+                .map(|(table, submitted, baseline)| {
+                    format!("{submitted} {table} against a baseline of {baseline:.0}")
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Hex encoded HMAC-SHA256 of the body, with which receivers verify that a webhook
+/// came from this scraper
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+impl Webhook {
+    pub fn new(url: impl Into<String>) -> Self {
+        Webhook {
+            url: url.into(),
+            secret: None,
+            failures_only: false,
+        }
+    }
+
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    pub fn failures_only(mut self) -> Self {
+        self.failures_only = true;
+        self
+    }
+
+    /// Whether the webhook fires for the report
+    pub fn fires_for(&self, report: &RunReport) -> bool {
+        !(self.failures_only && report.success && report.anomalies.is_empty())
+    }
+
+    /// POSTs the report as JSON. Receivers are not retried, the next run fires again.
+    pub async fn send(&self, client: &reqwest::Client, report: &RunReport) -> Result<()> {
+        let body = serde_json::to_vec(report)?;
+        let mut request = client
+            .post(&self.url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &body)));
+        }
+        request.body(body).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
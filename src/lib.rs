@@ -1 +1,5 @@
-pub mod job;
\ No newline at end of file
+pub mod api;
+pub mod export;
+pub mod job;
+pub mod query;
+pub mod tui;
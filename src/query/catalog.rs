@@ -0,0 +1,216 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+
+use crate::job::movies::PATHE_TIMEZONE;
+
+/// A show together with its rating and genres
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct ShowEntry {
+    pub slug: String,
+    pub title: String,
+    pub original_title: Option<String>,
+    /// First release date, as `YYYY-MM-DD`
+    pub release_at: Option<String>,
+    pub movie_type: String,
+    pub duration: i32,
+    pub synopsis: Option<String>,
+    pub age_rating: Option<String>,
+    pub rating_slug: Option<String>,
+    pub critics_score: Option<i32>,
+    pub audience_score: Option<i32>,
+    pub genres: Vec<String>,
+}
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct ShowShowtime {
+    pub cinema_slug: String,
+    pub cinema_name: String,
+    pub time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub auditorium_name: String,
+    pub reservation_url: Option<String>,
+    /// Price of a regular ticket in euro cents, when Pathé lists it
+    pub price_cents: Option<i32>,
+    pub surcharge_3d_cents: Option<i32>,
+    pub surcharge_imax_cents: Option<i32>,
+}
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct CinemaEntry {
+    pub slug: String,
+    pub name: String,
+    pub city_slug: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct ScheduleEntry {
+    pub show_slug: String,
+    pub title: String,
+    pub time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub auditorium_name: String,
+    pub reservation_url: Option<String>,
+    /// Price of a regular ticket in euro cents, when Pathé lists it
+    pub price_cents: Option<i32>,
+    pub surcharge_3d_cents: Option<i32>,
+    pub surcharge_imax_cents: Option<i32>,
+}
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct RatingEntry {
+    pub slug: String,
+    pub title: String,
+    pub release_year: Option<i32>,
+    pub critics_score: Option<i32>,
+    pub audience_score: Option<i32>,
+    pub certified_fresh: Option<bool>,
+    /// Shows rated by this rating
+    pub show_slugs: Vec<String>,
+}
+
+static SHOW_ENTRY_QUERY: &str = r#"SELECT
+        s.slug, s.title, s.original_title, s.release_at, s.movie_type, s.duration,
+        s.synopsis, s.age_rating, s.rating_slug, r.critics_score, r.audience_score,
+        ARRAY(
+            SELECT g.name FROM show_genres sg JOIN genres g ON g.slug = sg.genre_slug
+            WHERE sg.show_slug = s.slug ORDER BY g.name
+        ) AS genres
+    FROM shows s
+    LEFT JOIN ratings r ON r.slug = s.rating_slug"#;
+
+/// Shows ordered by title, optionally only the ones of a genre
+pub async fn shows(
+    pool: &PgPool,
+    genre: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ShowEntry>> {
+    Ok(sqlx::query_as(&format!(
+        r#"{SHOW_ENTRY_QUERY}
+        WHERE $1::text IS NULL
+            OR EXISTS (SELECT 1 FROM show_genres g JOIN genre_mappings m ON m.genre_slug = g.genre_slug WHERE g.show_slug = s.slug AND m.label ILIKE $1)
+        ORDER BY s.title, s.slug
+        LIMIT $2 OFFSET $3"#
+    ))
+    .bind(genre)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn show(pool: &PgPool, slug: &str) -> Result<Option<ShowEntry>> {
+    Ok(
+        sqlx::query_as(&format!("{SHOW_ENTRY_QUERY} WHERE s.slug = $1"))
+            .bind(slug)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// Upcoming showtimes of a show at any cinema, soonest first
+pub async fn show_showtimes(pool: &PgPool, show_slug: &str) -> Result<Vec<ShowShowtime>> {
+    Ok(sqlx::query_as(
+        r#"SELECT
+            c.slug AS cinema_slug,
+            c.name AS cinema_name,
+            st.time,
+            st.end_time,
+            st.auditorium_name,
+            st.reservation_url,
+            st.price_cents,
+            st.surcharge_3d_cents,
+            st.surcharge_imax_cents
+        FROM showtimes st
+        JOIN cinemas c ON c.slug = st.cinema_slug
+        WHERE st.show_slug = $1 AND st.time >= current_timestamp
+        ORDER BY st.time, c.name"#,
+    )
+    .bind(show_slug)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn cinemas(pool: &PgPool) -> Result<Vec<CinemaEntry>> {
+    Ok(sqlx::query_as(
+        "SELECT slug, name, city_slug, latitude, longitude FROM cinemas ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Whether a cinema with the slug exists, to tell an unknown cinema apart from an
+/// empty schedule
+pub async fn cinema_exists(pool: &PgPool, slug: &str) -> Result<bool> {
+    Ok(
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM cinemas WHERE slug = $1)")
+            .bind(slug)
+            .fetch_one(pool)
+            .await?,
+    )
+}
+
+/// Showtimes of a cinema on the given day, which runs from midnight to midnight in
+/// the time zone of Pathé
+pub async fn cinema_schedule(
+    pool: &PgPool,
+    cinema_slug: &str,
+    date: NaiveDate,
+) -> Result<Vec<ScheduleEntry>> {
+    let local_midnight = |date: NaiveDate| {
+        PATHE_TIMEZONE
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+    };
+    let (Some(start), Some(end)) = (
+        local_midnight(date),
+        date.succ_opt().and_then(local_midnight),
+    ) else {
+        return Ok(vec![]);
+    };
+    Ok(sqlx::query_as(
+        r#"SELECT
+            s.slug AS show_slug,
+            s.title,
+            st.time,
+            st.end_time,
+            st.auditorium_name,
+            st.reservation_url,
+            st.price_cents,
+            st.surcharge_3d_cents,
+            st.surcharge_imax_cents
+        FROM showtimes st
+        JOIN shows s ON s.slug = st.show_slug
+        WHERE st.cinema_slug = $1 AND st.time >= $2 AND st.time < $3
+        ORDER BY st.time, s.title"#,
+    )
+    .bind(cinema_slug)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Ratings of shows, best critics score first
+pub async fn ratings(pool: &PgPool, limit: i64, offset: i64) -> Result<Vec<RatingEntry>> {
+    Ok(sqlx::query_as(
+        r#"SELECT
+            r.slug, r.title, r.release_year, r.critics_score, r.audience_score,
+            r.certified_fresh,
+            ARRAY(SELECT s.slug FROM shows s WHERE s.rating_slug = r.slug ORDER BY s.slug)
+                AS show_slugs
+        FROM ratings r
+        ORDER BY r.critics_score DESC NULLS LAST, r.audience_score DESC NULLS LAST, r.slug
+        LIMIT $1 OFFSET $2"#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?)
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct ShowRef {
+    pub slug: String,
+    pub title: String,
+}
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct ShowtimeRef {
+    pub show_slug: String,
+    pub cinema_slug: String,
+    pub time: DateTime<Utc>,
+    pub auditorium_name: String,
+}
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct RatingChange {
+    pub show_slug: String,
+    pub rating_slug_before: Option<String>,
+    pub rating_slug_after: Option<String>,
+    pub critics_score_before: Option<i32>,
+    pub critics_score_after: Option<i32>,
+    pub audience_score_before: Option<i32>,
+    pub audience_score_after: Option<i32>,
+}
+
+/// Structured differences between two runs, as snapshotted at the end of each run
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RunDiff {
+    pub run_a: i64,
+    pub run_b: i64,
+    pub new_shows: Vec<ShowRef>,
+    pub removed_shows: Vec<ShowRef>,
+    pub added_showtimes: Vec<ShowtimeRef>,
+    pub removed_showtimes: Vec<ShowtimeRef>,
+    pub rating_changes: Vec<RatingChange>,
+}
+
+impl RunDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new_shows.is_empty()
+            && self.removed_shows.is_empty()
+            && self.added_showtimes.is_empty()
+            && self.removed_showtimes.is_empty()
+            && self.rating_changes.is_empty()
+    }
+}
+
+async fn shows_only_in(pool: &PgPool, run: i64, other: i64) -> Result<Vec<ShowRef>> {
+    Ok(sqlx::query_as(
+        r#"SELECT slug, title FROM snapshot_shows WHERE run_id = $1
+        EXCEPT SELECT slug, title FROM snapshot_shows WHERE run_id = $2
+        ORDER BY title"#,
+    )
+    .bind(run)
+    .bind(other)
+    .fetch_all(pool)
+    .await?)
+}
+
+async fn showtimes_only_in(pool: &PgPool, run: i64, other: i64) -> Result<Vec<ShowtimeRef>> {
+    Ok(sqlx::query_as(
+        r#"SELECT show_slug, cinema_slug, time, auditorium_name FROM snapshot_showtimes
+            WHERE run_id = $1
+        EXCEPT SELECT show_slug, cinema_slug, time, auditorium_name FROM snapshot_showtimes
+            WHERE run_id = $2
+        ORDER BY time, cinema_slug, show_slug"#,
+    )
+    .bind(run)
+    .bind(other)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Diffs run `run_a` against the later run `run_b`
+pub async fn diff_runs(pool: &PgPool, run_a: i64, run_b: i64) -> Result<RunDiff> {
+    let mut removed_showtimes = showtimes_only_in(pool, run_a, run_b).await?;
+
+    // Showtimes which started in between the runs did not disappear
+    let run_b_dt: DateTime<Utc> = sqlx::query_scalar("SELECT run_dt FROM joblogs WHERE id = $1")
+        .bind(run_b)
+        .fetch_one(pool)
+        .await?;
+    removed_showtimes.retain(|showtime| showtime.time >= run_b_dt);
+
+    let rating_changes = sqlx::query_as(
+        r#"SELECT
+            COALESCE(a.show_slug, b.show_slug) AS show_slug,
+            a.rating_slug AS rating_slug_before,
+            b.rating_slug AS rating_slug_after,
+            a.critics_score AS critics_score_before,
+            b.critics_score AS critics_score_after,
+            a.audience_score AS audience_score_before,
+            b.audience_score AS audience_score_after
+        FROM (SELECT * FROM snapshot_ratings WHERE run_id = $1) a
+        FULL OUTER JOIN (SELECT * FROM snapshot_ratings WHERE run_id = $2) b
+            ON a.show_slug = b.show_slug
+        WHERE a.rating_slug IS DISTINCT FROM b.rating_slug
+            OR a.critics_score IS DISTINCT FROM b.critics_score
+            OR a.audience_score IS DISTINCT FROM b.audience_score
+        ORDER BY 1"#,
+    )
+    .bind(run_a)
+    .bind(run_b)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(RunDiff {
+        run_a,
+        run_b,
+        new_shows: shows_only_in(pool, run_b, run_a).await?,
+        removed_shows: shows_only_in(pool, run_a, run_b).await?,
+        added_showtimes: showtimes_only_in(pool, run_b, run_a).await?,
+        removed_showtimes,
+        rating_changes,
+    })
+}
+
+/// Diffs the two most recent runs of a job, if it ran at least twice
+pub async fn diff_latest(pool: &PgPool, jobname: &str) -> Result<Option<RunDiff>> {
+    let runs: Vec<i64> =
+        sqlx::query_scalar("SELECT id FROM joblogs WHERE jobname = $1 ORDER BY id DESC LIMIT 2")
+            .bind(jobname)
+            .fetch_all(pool)
+            .await?;
+    match runs[..] {
+        [run_b, run_a] => Ok(Some(diff_runs(pool, run_a, run_b).await?)),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,8 @@
+//! Read-only queries over the scraped data, intended for consumers of the tables
+//! filled by the jobs.
+
+pub mod catalog;
+pub mod diff;
+pub mod nearby;
+pub mod search;
+pub mod tonight;
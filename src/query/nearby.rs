@@ -0,0 +1,53 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct NearbyShowtime {
+    pub show_slug: String,
+    pub title: String,
+    pub cinema_slug: String,
+    pub cinema_name: String,
+    pub distance_km: f64,
+    pub time: DateTime<Utc>,
+    pub reservation_url: Option<String>,
+}
+
+/// Upcoming showtimes at cinemas within `radius_km` of the given location, sorted by
+/// the distance to the cinema and then by time.
+pub async fn showtimes_near(
+    pool: &PgPool,
+    latitude: f64,
+    longitude: f64,
+    radius_km: f64,
+    limit: i64,
+) -> Result<Vec<NearbyShowtime>> {
+    Ok(sqlx::query_as(
+        r#"SELECT
+            s.slug AS show_slug,
+            s.title,
+            c.slug AS cinema_slug,
+            c.name AS cinema_name,
+            earth_distance(ll_to_earth(c.latitude, c.longitude), ll_to_earth($1, $2)) / 1000
+                AS distance_km,
+            st.time,
+            st.reservation_url
+        FROM showtimes st
+        JOIN shows s ON s.slug = st.show_slug
+        JOIN cinemas c ON c.slug = st.cinema_slug
+        WHERE c.latitude IS NOT NULL AND c.longitude IS NOT NULL
+            AND earth_box(ll_to_earth($1, $2), $3 * 1000) @> ll_to_earth(c.latitude, c.longitude)
+            AND earth_distance(ll_to_earth(c.latitude, c.longitude), ll_to_earth($1, $2)) <= $3 * 1000
+            AND st.time >= current_timestamp
+        ORDER BY distance_km, st.time
+        LIMIT $4"#,
+    )
+    .bind(latitude)
+    .bind(longitude)
+    .bind(radius_km)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
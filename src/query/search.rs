@@ -0,0 +1,41 @@
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct SearchHit {
+    pub slug: String,
+    pub title: String,
+    pub rating_title: Option<String>,
+    pub rank: f32,
+}
+
+/// Searches shows by their title and the title and description of their rating.
+///
+/// Next to full-text matching, titles are matched on trigram similarity so that
+/// typos ("godfahter") still find the show.
+pub async fn search_shows(pool: &PgPool, query: &str, limit: i64) -> Result<Vec<SearchHit>> {
+    Ok(sqlx::query_as(
+        r#"SELECT
+            s.slug,
+            s.title,
+            r.title AS rating_title,
+            GREATEST(
+                similarity(s.title, $1),
+                ts_rank(s.search_vector, websearch_to_tsquery('simple', $1)),
+                COALESCE(ts_rank(r.search_vector, websearch_to_tsquery('english', $1)), 0)
+            ) AS rank
+        FROM shows s
+        LEFT JOIN ratings r ON r.slug = s.rating_slug
+        WHERE s.search_vector @@ websearch_to_tsquery('simple', $1)
+            OR r.search_vector @@ websearch_to_tsquery('english', $1)
+            OR s.title % $1
+        ORDER BY rank DESC
+        LIMIT $2"#,
+    )
+    .bind(query)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
@@ -0,0 +1,159 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder};
+use utoipa::ToSchema;
+
+/// An upcoming showtime, ranked by the rating of its show
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct Recommendation {
+    pub show_slug: String,
+    pub title: String,
+    pub cinema_slug: String,
+    pub cinema_name: String,
+    pub city_slug: String,
+    pub time: DateTime<Utc>,
+    pub auditorium_name: Option<String>,
+    pub reservation_url: Option<String>,
+    pub critics_score: Option<i32>,
+    pub audience_score: Option<i32>,
+    pub score: Option<i32>,
+}
+
+/// Builder for the "what to watch tonight" query.
+///
+/// Upcoming showtimes are joined with the ratings of their shows and ranked by the
+/// critics score (falling back to the audience score), best first.
+#[derive(Debug, Clone)]
+pub struct Tonight {
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+    city: Option<String>,
+    min_score: Option<i32>,
+    genre: Option<String>,
+    format: Option<String>,
+    tag: Option<String>,
+    limit: i64,
+}
+
+impl Default for Tonight {
+    fn default() -> Self {
+        let now = Utc::now();
+        Tonight {
+            from: now,
+            until: now + Duration::hours(8),
+            city: None,
+            min_score: None,
+            genre: None,
+            format: None,
+            tag: None,
+            limit: 50,
+        }
+    }
+}
+
+impl Tonight {
+    /// Showtimes starting within the next eight hours
+    pub fn new() -> Self {
+        Tonight::default()
+    }
+
+    pub fn between(mut self, from: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        self.from = from;
+        self.until = until;
+        self
+    }
+
+    pub fn city(mut self, city_slug: impl Into<String>) -> Self {
+        self.city = Some(city_slug.into());
+        self
+    }
+
+    pub fn min_score(mut self, min_score: i32) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    pub fn genre(mut self, genre: impl Into<String>) -> Self {
+        self.genre = Some(genre.into());
+        self
+    }
+
+    /// Only screenings in a premium format, e.g. `IMAX` or `4DX`. Pathé names the
+    /// auditoriums after their format, so this matches on the auditorium name.
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Only screenings with the tag, e.g. `ov` for the original version or `imax`
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into().to_lowercase());
+        self
+    }
+
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn query(&self) -> QueryBuilder<'_, Postgres> {
+        let mut query = QueryBuilder::new(
+            r#"SELECT
+                s.slug AS show_slug,
+                s.title,
+                c.slug AS cinema_slug,
+                c.name AS cinema_name,
+                c.city_slug,
+                st.time,
+                st.auditorium_name,
+                st.reservation_url,
+                r.critics_score,
+                r.audience_score,
+                COALESCE(r.critics_score, r.audience_score) AS score
+            FROM showtimes st
+            JOIN shows s ON s.slug = st.show_slug
+            JOIN cinemas c ON c.slug = st.cinema_slug
+            LEFT JOIN ratings r ON r.slug = s.rating_slug
+            WHERE st.time BETWEEN "#,
+        );
+        query.push_bind(self.from);
+        query.push(" AND ");
+        query.push_bind(self.until);
+
+        if let Some(city) = &self.city {
+            query.push(" AND c.city_slug = ").push_bind(city);
+        }
+        if let Some(min_score) = self.min_score {
+            query
+                .push(" AND COALESCE(r.critics_score, r.audience_score) >= ")
+                .push_bind(min_score);
+        }
+        if let Some(genre) = &self.genre {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM show_genres g JOIN genre_mappings m ON m.genre_slug = g.genre_slug WHERE g.show_slug = s.slug AND m.label ILIKE ")
+                .push_bind(genre)
+                .push(")");
+        }
+        if let Some(format) = &self.format {
+            query
+                .push(" AND st.auditorium_name ILIKE '%' || ")
+                .push_bind(format)
+                .push(" || '%'");
+        }
+        if let Some(tag) = &self.tag {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM showtime_tags t WHERE t.show_slug = st.show_slug AND t.cinema_slug = st.cinema_slug AND t.time = st.time AND t.auditorium_name = st.auditorium_name AND t.tag = ")
+                .push_bind(tag)
+                .push(")");
+        }
+
+        query.push(" ORDER BY score DESC NULLS LAST, st.time ASC LIMIT ");
+        query.push_bind(self.limit);
+        query
+    }
+
+    pub async fn fetch(&self, pool: &PgPool) -> Result<Vec<Recommendation>> {
+        Ok(self.query().build_query_as().fetch_all(pool).await?)
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    os::fd::{FromRawFd, RawFd},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Amount of log lines kept around for the dashboard
+const MAX_LOG_LINES: usize = 500;
+
+/// Most recent lines written to stdout, oldest first
+pub type LogLines = Arc<Mutex<VecDeque<String>>>;
+
+/// Redirects stdout into a pipe, such that log lines end up in the dashboard instead
+/// of garbling it. The original stdout stays available as `terminal`.
+pub struct StdoutCapture {
+    saved: RawFd,
+    pub terminal: File,
+    pub lines: LogLines,
+}
+
+fn check(fd: i32) -> io::Result<i32> {
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(fd)
+}
+
+impl StdoutCapture {
+    pub fn start() -> io::Result<Self> {
+        io::stdout().flush()?;
+        let mut fds = [0; 2];
+        // SAFETY: plain file descriptor juggling, every descriptor is checked before
+        // it is used and ownership of the new ones is handed to a `File` exactly once
+        let (saved, terminal, reader) = unsafe {
+            let saved = check(libc::dup(libc::STDOUT_FILENO))?;
+            let terminal = File::from_raw_fd(check(libc::dup(saved))?);
+            check(libc::pipe(fds.as_mut_ptr()))?;
+            check(libc::dup2(fds[1], libc::STDOUT_FILENO))?;
+            libc::close(fds[1]);
+            (saved, terminal, File::from_raw_fd(fds[0]))
+        };
+
+        let lines = LogLines::default();
+        let sink = lines.clone();
+        // Ends once stdout is restored, as that closes the last writing end
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                let mut lines = sink.lock().unwrap_or_else(|e| e.into_inner());
+                if lines.len() == MAX_LOG_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
+            }
+        });
+
+        Ok(StdoutCapture {
+            saved,
+            terminal,
+            lines,
+        })
+    }
+
+    /// Points stdout back to where it pointed before the capture started
+    pub fn restore(&self) {
+        let _ = io::stdout().flush();
+        // SAFETY: `saved` is a valid descriptor owned by this capture
+        unsafe {
+            libc::dup2(self.saved, libc::STDOUT_FILENO);
+            libc::close(self.saved);
+        }
+    }
+}
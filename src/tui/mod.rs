@@ -0,0 +1,224 @@
+//! Terminal dashboard showing the jobs while they are running.
+
+mod capture;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, Utc};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEventKind},
+        execute,
+        terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    },
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Paragraph, Row, Table, TableState},
+};
+
+use crate::job::{JobStatus, JobStatuses, Shutdown};
+use capture::{LogLines, StdoutCapture};
+
+/// Interval at which the dashboard is redrawn
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// Handle to the dashboard, which runs on its own thread until the user quits
+pub struct Dashboard {
+    handle: JoinHandle<Result<()>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Dashboard {
+    /// Starts drawing the dashboard on the terminal, everything printed to stdout
+    /// from now on is shown in its log pane instead. Quitting the dashboard requests
+    /// the jobs to shut down.
+    pub fn start(statuses: JobStatuses, shutdown: Shutdown) -> Result<Self> {
+        let capture = StdoutCapture::start()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let result = run(&statuses, &capture, &stop);
+                capture.restore();
+                shutdown.request();
+                result
+            }
+        });
+        Ok(Dashboard { handle, stop })
+    }
+
+    /// Waits for the user to quit the dashboard
+    pub fn join(self) -> Result<()> {
+        self.handle
+            .join()
+            .map_err(|_| anyhow!("Dashboard panicked"))?
+    }
+
+    /// Closes the dashboard without waiting for the user, restoring the terminal
+    pub fn stop(self) -> Result<()> {
+        self.stop.store(true, Ordering::SeqCst);
+        self.join()
+    }
+}
+
+fn run(statuses: &JobStatuses, capture: &StdoutCapture, stop: &AtomicBool) -> Result<()> {
+    let mut writer = capture.terminal.try_clone()?;
+    enable_raw_mode()?;
+    execute!(writer, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(writer))?;
+
+    let result = event_loop(&mut terminal, statuses, &capture.lines, stop);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<std::fs::File>>,
+    statuses: &JobStatuses,
+    lines: &LogLines,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let mut selected = TableState::default().with_selected(0);
+    while !stop.load(Ordering::SeqCst) {
+        let jobs = snapshot(statuses);
+        terminal.draw(|frame| draw(frame, &jobs, lines, &mut selected))?;
+
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let job = selected
+            .selected()
+            .and_then(|idx| jobs.get(idx))
+            .map(|(name, _)| name.clone());
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => selected.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => selected.select_next(),
+            KeyCode::Char('t') => update(statuses, job, |status| status.triggered = true),
+            KeyCode::Char('p') => update(statuses, job, |status| status.paused = !status.paused),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Statuses of all jobs, sorted by name
+fn snapshot(statuses: &JobStatuses) -> Vec<(String, JobStatus)> {
+    let statuses = statuses.read().unwrap_or_else(|e| e.into_inner());
+    let mut jobs: Vec<(String, JobStatus)> = statuses
+        .iter()
+        .map(|(name, status)| (name.clone(), status.clone()))
+        .collect();
+    jobs.sort_by(|(a, _), (b, _)| a.cmp(b));
+    jobs
+}
+
+fn update(statuses: &JobStatuses, job: Option<String>, f: impl FnOnce(&mut JobStatus)) {
+    let mut statuses = statuses.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(status) = job.and_then(|job| statuses.get_mut(&job)) {
+        f(status);
+    }
+}
+
+fn local_time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|time| time.with_timezone(&Local).format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn state(status: &JobStatus) -> String {
+    match (status.running_since, status.paused, status.triggered) {
+        (Some(since), _, _) => format!("running {}s", (Utc::now() - since).num_seconds()),
+        (None, true, _) => "paused".to_string(),
+        (None, false, true) => "triggered".to_string(),
+        (None, false, false) => "idle".to_string(),
+    }
+}
+
+fn draw(
+    frame: &mut Frame,
+    jobs: &[(String, JobStatus)],
+    lines: &LogLines,
+    selected: &mut TableState,
+) {
+    let [jobs_area, log_area, help_area] = Layout::vertical([
+        Constraint::Length(jobs.len() as u16 + 3),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let rows = jobs.iter().map(|(name, status)| {
+        Row::new([
+            name.clone(),
+            state(status),
+            local_time(status.next_run),
+            status.runs.to_string(),
+            status.consecutive_failures.to_string(),
+            local_time(status.last_success),
+            status.last_error.clone().unwrap_or_default(),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Length(13),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new([
+            "Job",
+            "State",
+            "Next run",
+            "Runs",
+            "Failures",
+            "Last success",
+            "Last error",
+        ])
+        .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+    .block(Block::bordered().title("Jobs"));
+    frame.render_stateful_widget(table, jobs_area, selected);
+
+    // Only the lines which fit, the most recent one at the bottom
+    let visible = log_area.height.saturating_sub(2) as usize;
+    let log: Vec<String> = {
+        let lines = lines.lock().unwrap_or_else(|e| e.into_inner());
+        let skip = lines.len().saturating_sub(visible);
+        lines.iter().skip(skip).cloned().collect()
+    };
+    frame.render_widget(
+        Paragraph::new(log.join("\n")).block(Block::bordered().title("Log")),
+        log_area,
+    );
+
+    frame.render_widget(
+        Paragraph::new("↑/↓ select  t trigger  p pause/resume  q quit"),
+        help_area,
+    );
+}
@@ -0,0 +1,47 @@
+//! Pauses, resumes and triggers jobs through the control socket.
+
+use std::time::Duration;
+
+use schraper::job::{
+    JobStatuses,
+    control::{send_command, serve_control},
+};
+
+#[tokio::test]
+async fn controls_the_jobs() {
+    let statuses = JobStatuses::default();
+    for job in ["movies", "events"] {
+        statuses
+            .write()
+            .unwrap()
+            .insert(job.to_string(), Default::default());
+    }
+    let path = std::env::temp_dir().join(format!("schraper-control-{}.sock", std::process::id()));
+    let server = tokio::spawn(serve_control(path.clone(), statuses.clone()));
+    while !path.exists() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(send_command(&path, "pause movies").await.unwrap(), ["ok"]);
+    assert!(statuses.read().unwrap()["movies"].paused);
+    assert_eq!(
+        send_command(&path, "trigger events now").await.unwrap(),
+        ["ok"]
+    );
+    assert!(statuses.read().unwrap()["events"].triggered);
+
+    let status = send_command(&path, "status").await.unwrap();
+    assert_eq!(status.len(), 3);
+    assert!(status[0].starts_with("events triggered runs=0"));
+    assert!(status[1].starts_with("movies paused runs=0"));
+
+    assert_eq!(send_command(&path, "resume movies").await.unwrap(), ["ok"]);
+    assert!(!statuses.read().unwrap()["movies"].paused);
+    assert_eq!(
+        send_command(&path, "pause trakt").await.unwrap(),
+        ["error: Unknown job trakt"]
+    );
+
+    server.abort();
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,41 @@
+//! Validates queries against the GraphQL schema, which happens before the database
+//! is touched.
+
+use schraper::api::graphql::schema;
+use sqlx::PgPool;
+
+fn lazy_pool() -> PgPool {
+    PgPool::connect_lazy("postgres://localhost/unused").unwrap()
+}
+
+#[tokio::test]
+async fn exposes_nested_fields() {
+    let sdl = schema(lazy_pool()).sdl();
+    for field in [
+        "showtimes(filter: ShowtimeFilter!",
+        "rating: Rating",
+        "cinema: Cinema",
+        "shows: [Show!]!",
+    ] {
+        assert!(sdl.contains(field), "Missing {field}");
+    }
+}
+
+#[tokio::test]
+async fn rejects_unknown_fields() {
+    let response = schema(lazy_pool())
+        .execute("{ shows { slug ratingSlug } }")
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    assert!(response.errors[0].message.contains("ratingSlug"));
+}
+
+#[tokio::test]
+async fn limits_the_depth_of_queries() {
+    let response = schema(lazy_pool())
+        .execute(
+            "{ shows { showtimes { cinema { showtimes { show { showtimes { cinema { showtimes { time } } } } } } } } }",
+        )
+        .await;
+    assert!(response.errors[0].message.contains("too deep"));
+}
@@ -0,0 +1,56 @@
+//! Writes showtimes as an iCalendar feed.
+
+use chrono::{TimeZone, Utc};
+use schraper::export::ics::{CalendarEvent, write_calendar};
+
+fn event() -> CalendarEvent {
+    CalendarEvent {
+        show_slug: "dune-part-two".to_string(),
+        title: "Dune: Part Two".to_string(),
+        cinema_slug: "amsterdam-arena".to_string(),
+        cinema_name: "Pathé Arena".to_string(),
+        city_name: "Amsterdam".to_string(),
+        auditorium_name: "Zaal 1".to_string(),
+        time: Utc.with_ymd_and_hms(2024, 3, 1, 19, 30, 0).unwrap(),
+        end_time: None,
+        duration: 166,
+        reservation_url: Some("https://www.pathe.nl/tickets/1234".to_string()),
+    }
+}
+
+fn calendar(events: &[CalendarEvent]) -> String {
+    let mut ics = Vec::new();
+    write_calendar("Pathé Arena", events, &mut ics).unwrap();
+    String::from_utf8(ics).unwrap()
+}
+
+#[test]
+fn writes_events() {
+    let ics = calendar(&[event()]);
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    for line in [
+        "UID:dune-part-two-amsterdam-arena-1709321400-zaal-1@schraper",
+        "DTSTART:20240301T193000Z",
+        // Derived from the duration, since there is no end time
+        "DTEND:20240301T221600Z",
+        "SUMMARY:Dune: Part Two",
+        "LOCATION:Pathé Arena\\, Amsterdam (Zaal 1)",
+        "URL:https://www.pathe.nl/tickets/1234",
+    ] {
+        assert!(ics.contains(&format!("\r\n{line}\r\n")), "Missing {line}");
+    }
+}
+
+#[test]
+fn folds_long_lines() {
+    let mut long = event();
+    long.title =
+        "Lé Très Long Titre; Avec Beaucoup de Mots, Qui Ne Tient Pas Sur Une Ligne".repeat(2);
+    let ics = calendar(&[long]);
+    for line in ics.split("\r\n") {
+        assert!(line.len() <= 75, "Too long: {line}");
+    }
+    let unfolded = ics.replace("\r\n ", "");
+    assert!(unfolded.contains(r"SUMMARY:Lé Très Long Titre\; Avec Beaucoup de Mots\, Qui"));
+}
@@ -0,0 +1,154 @@
+use proptest::prelude::*;
+use schraper::job::{
+    matching::{
+        Candidate, ExactAlias, Levenshtein, Matcher, Query, TokenSetRatio, Weighted, YearDistance,
+        best_match, best_rt_hit, rt_hit_score,
+    },
+    movies::RTHit,
+};
+
+/// Entry of some other rating source, known under multiple names
+struct Whisky {
+    name: String,
+    aliases: Vec<String>,
+}
+
+impl Candidate for Whisky {
+    fn title(&self) -> &str {
+        &self.name
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+fn query(title: &str) -> Query<'_> {
+    Query { title, year: None }
+}
+
+fn hit(title: &str, year: Option<i32>) -> RTHit {
+    serde_json::from_value(serde_json::json!({
+        "title": title,
+        "vanity": title.to_lowercase().replace(' ', "_"),
+        "releaseYear": year,
+    }))
+    .unwrap()
+}
+
+fn hits() -> impl Strategy<Value = Vec<(String, Option<i32>)>> {
+    prop::collection::vec((".{0,20}", prop::option::of(1900..2100i32)), 0..8)
+}
+
+proptest! {
+    #[test]
+    fn score_ignores_casing_and_variant_markers(
+        title in "[a-zA-Z ]{0,30}",
+        hit_title in "[a-zA-Z ]{0,30}",
+        marker in "\\((OV|NL|4K|IMAX)\\)",
+    ) {
+        let hit = hit(&hit_title, None);
+        let score = rt_hit_score(&hit, &title, None);
+        prop_assert_eq!(score, rt_hit_score(&hit, &format!("{title} {marker}"), None));
+        prop_assert_eq!(score, rt_hit_score(&hit, &title.to_uppercase(), None));
+    }
+
+    #[test]
+    fn score_is_monotonic_in_year_distance(
+        title in ".{0,30}",
+        hit_title in ".{0,30}",
+        year in 1900..2100i32,
+        near in 0..50i32,
+        further in 0..50i32,
+    ) {
+        let hit = hit(&hit_title, Some(year));
+        let near_score = rt_hit_score(&hit, &title, Some(year + near));
+        let far_score = rt_hit_score(&hit, &title, Some(year + near + further));
+        prop_assert!(near_score <= far_score);
+        prop_assert!(rt_hit_score(&hit, &title, Some(year - near)) <= rt_hit_score(&hit, &title, Some(year - near - further)));
+    }
+
+    #[test]
+    fn best_hit_does_not_depend_on_order(
+        hit_specs in hits(),
+        title in ".{0,20}",
+        year in prop::option::of(1900..2100i32),
+    ) {
+        let to_hits = |specs: &[(String, Option<i32>)]| {
+            specs.iter().map(|(title, year)| hit(title, *year)).collect::<Vec<_>>()
+        };
+        let mut reversed = hit_specs.clone();
+        reversed.reverse();
+
+        let best = best_rt_hit(to_hits(&hit_specs), title.clone(), year).map(|(_, score)| score);
+        let best_reversed = best_rt_hit(to_hits(&reversed), title, year).map(|(_, score)| score);
+        prop_assert_eq!(best, best_reversed);
+    }
+
+    #[test]
+    fn never_panics(
+        hit_specs in prop::collection::vec((any::<String>(), any::<Option<i32>>()), 0..8),
+        title in any::<String>(),
+        year in any::<Option<i32>>(),
+    ) {
+        let hits = hit_specs.iter().map(|(title, year)| hit(title, *year)).collect::<Vec<_>>();
+        let count = hits.len();
+        let best = best_rt_hit(hits, title, year);
+        prop_assert_eq!(best.is_some(), count > 0);
+        if let Some((_, score)) = best {
+            prop_assert!(!score.is_nan());
+        }
+    }
+}
+
+#[test]
+fn exact_match_wins() {
+    let hits = vec![
+        hit("The Silent Horizon 2", Some(2024)),
+        hit("The Silent Horizon", Some(2024)),
+        hit("The Silent Horizon", Some(1987)),
+    ];
+    let (best, score) =
+        best_rt_hit(hits, "The Silent Horizon (OV)".to_string(), Some(2024)).unwrap();
+    assert_eq!(best.title, "The Silent Horizon");
+    assert_eq!(best.release_year, Some(2024));
+    assert_eq!(score, 0.0);
+}
+
+proptest! {
+    #[test]
+    fn token_set_ignores_word_order(words in prop::collection::vec("[a-z]{1,8}", 1..6)) {
+        let mut shuffled = words.clone();
+        shuffled.reverse();
+        let hit = hit(&shuffled.join(" "), None);
+        prop_assert_eq!(TokenSetRatio.score(query(&words.join(" ")), &hit), 0.0);
+    }
+}
+
+#[test]
+fn token_set_forgives_subtitles() {
+    let hit = hit("Dune: Part Two", Some(2024));
+    let title = "Dune Part Two Imax Experience";
+    assert!(TokenSetRatio.score(query(title), &hit) < Levenshtein.score(query(title), &hit));
+}
+
+#[test]
+fn exact_alias_matches_any_name() {
+    let whiskies = vec![
+        Whisky {
+            name: "Laphroaig 10".to_string(),
+            aliases: vec![],
+        },
+        Whisky {
+            name: "Ardbeg Ten Years Old".to_string(),
+            aliases: vec!["Ardbeg 10".to_string()],
+        },
+    ];
+    let matcher = Weighted::new()
+        .with(1.0, ExactAlias)
+        .with(0.5, Levenshtein)
+        .with(0.1, YearDistance);
+    let (best, score) = best_match(&matcher, whiskies, query("ARDBEG 10 (Cask Strength)")).unwrap();
+    assert_eq!(best.name, "Ardbeg Ten Years Old");
+    assert!(score > 0.0 && score < 1.0);
+}
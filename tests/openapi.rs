@@ -0,0 +1,29 @@
+//! Checks that the routes of the REST API are described by the OpenAPI document.
+
+use schraper::api::openapi::openapi_json;
+use serde_json::Value;
+
+#[test]
+fn describes_the_routes() {
+    let doc: Value = serde_json::from_str(&openapi_json().unwrap()).unwrap();
+    for path in [
+        "/shows",
+        "/shows/{slug}",
+        "/shows/{slug}/showtimes",
+        "/cinemas",
+        "/cinemas/{slug}/schedule",
+        "/ratings",
+        "/tonight",
+        "/search",
+        "/showtimes",
+    ] {
+        assert!(doc["paths"][path]["get"].is_object(), "Missing {path}");
+    }
+    let params: Vec<&str> = doc["paths"]["/showtimes"]["get"]["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|param| param["name"].as_str())
+        .collect();
+    assert_eq!(params, ["lat", "lon", "radius_km", "limit"]);
+}
@@ -0,0 +1,109 @@
+use chrono::NaiveDate;
+use schraper::job::parse::{
+    ParseError, Price, parse_abv, parse_date, parse_number, parse_price, parse_volume,
+};
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[test]
+fn numbers() {
+    assert_eq!(parse_number("1.299,95"), Ok(1299.95));
+    assert_eq!(parse_number("1,299.95"), Ok(1299.95));
+    assert_eq!(parse_number("3,50"), Ok(3.5));
+    assert_eq!(parse_number("5.2"), Ok(5.2));
+    assert_eq!(parse_number("1.299"), Ok(1299.0));
+    assert_eq!(parse_number("1.000.000"), Ok(1_000_000.0));
+    assert_eq!(parse_number(" 12,- "), Ok(12.0));
+    assert_eq!(parse_number("-0,5"), Ok(-0.5));
+    assert_eq!(parse_number(",5"), Ok(0.5));
+    assert_eq!(parse_number("1\u{a0}299,95"), Ok(1299.95));
+}
+
+#[test]
+fn invalid_numbers() {
+    for input in ["", "abc", "1,2,3", "12a", "-"] {
+        assert_eq!(
+            parse_number(input),
+            Err(ParseError::Number(input.to_string()))
+        );
+    }
+}
+
+#[test]
+fn prices() {
+    let euro = |cents| Price {
+        cents,
+        currency: "EUR",
+    };
+    assert_eq!(parse_price("€ 1.299,95"), Ok(euro(129995)));
+    assert_eq!(parse_price("€1,99"), Ok(euro(199)));
+    assert_eq!(parse_price("EUR 3,50"), Ok(euro(350)));
+    assert_eq!(parse_price("12,-"), Ok(euro(1200)));
+    assert_eq!(parse_price("2,49 €"), Ok(euro(249)));
+    assert_eq!(
+        parse_price("$5.99"),
+        Ok(Price {
+            cents: 599,
+            currency: "USD"
+        })
+    );
+    assert!(parse_price("gratis").is_err());
+}
+
+#[test]
+fn dates() {
+    let reference = date(2025, 6, 10);
+    assert_eq!(parse_date("za 14 jun", reference), Ok(date(2025, 6, 14)));
+    assert_eq!(
+        parse_date("zaterdag 14 juni", reference),
+        Ok(date(2025, 6, 14))
+    );
+    assert_eq!(parse_date("14 juni 2025", reference), Ok(date(2025, 6, 14)));
+    assert_eq!(parse_date("3 mrt", reference), Ok(date(2025, 3, 3)));
+    assert_eq!(parse_date("3 maart", reference), Ok(date(2025, 3, 3)));
+    assert_eq!(parse_date("14-06-2025", reference), Ok(date(2025, 6, 14)));
+    assert_eq!(parse_date("14/6/2025", reference), Ok(date(2025, 6, 14)));
+}
+
+#[test]
+fn dates_around_new_year() {
+    let reference = date(2025, 12, 28);
+    assert_eq!(parse_date("vr 2 jan", reference), Ok(date(2026, 1, 2)));
+    assert_eq!(
+        parse_date("20 dec", date(2026, 1, 3)),
+        Ok(date(2025, 12, 20))
+    );
+}
+
+#[test]
+fn invalid_dates() {
+    let reference = date(2025, 6, 10);
+    // 14 June 2025 is a saturday
+    assert!(parse_date("ma 14 jun 2025", reference).is_err());
+    assert!(parse_date("31 feb", reference).is_err());
+    assert!(parse_date("14 foo", reference).is_err());
+    assert!(parse_date("", reference).is_err());
+}
+
+#[test]
+fn volumes() {
+    assert_eq!(parse_volume("33cl"), Ok(330.0));
+    assert_eq!(parse_volume("0,5 l"), Ok(500.0));
+    assert_eq!(parse_volume("500 ml"), Ok(500.0));
+    assert_eq!(parse_volume("1,5 liter"), Ok(1500.0));
+    assert_eq!(parse_volume("6 x 33 cl"), Ok(1980.0));
+    assert_eq!(parse_volume("4x0,5L"), Ok(2000.0));
+    assert!(parse_volume("33").is_err());
+    assert!(parse_volume("33 kg").is_err());
+}
+
+#[test]
+fn alcohol_percentages() {
+    assert_eq!(parse_abv("5,2% vol"), Ok(5.2));
+    assert_eq!(parse_abv("alc. 5.2% vol."), Ok(5.2));
+    assert_eq!(parse_abv("40%"), Ok(40.0));
+    assert!(parse_abv("5,2").is_err());
+    assert!(parse_abv("140%").is_err());
+}
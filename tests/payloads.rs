@@ -0,0 +1,67 @@
+//! Validates the captured upstream payloads in `tests/fixtures` against the structs
+//! they are decoded into. Payloads are stored as `<source>/<payload>/<name>.json`,
+//! where the payload directory names a `Payload` kind. Add captured real-world
+//! payloads there whenever they exposed a decoding issue.
+
+use std::{
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use schraper::job::movies::Payload;
+
+/// A captured payload together with the kind of payload it is
+struct Fixture {
+    path: PathBuf,
+    kind: String,
+    json: String,
+}
+
+/// Loads all fixtures below `root`, sorted by path
+fn load_corpus(root: &Path) -> Vec<Fixture> {
+    let mut fixtures = vec![];
+    for source in fs::read_dir(root).unwrap() {
+        for kind in fs::read_dir(source.unwrap().path()).unwrap() {
+            let kind = kind.unwrap().path();
+            for file in fs::read_dir(&kind).unwrap() {
+                let path = file.unwrap().path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    fixtures.push(Fixture {
+                        json: fs::read_to_string(&path).unwrap(),
+                        kind: kind.file_name().unwrap().to_string_lossy().to_string(),
+                        path,
+                    });
+                }
+            }
+        }
+    }
+    fixtures.sort_by(|a, b| a.path.cmp(&b.path));
+    fixtures
+}
+
+#[test]
+fn all_fixtures_decode() {
+    let fixtures = load_corpus(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"));
+    assert!(!fixtures.is_empty(), "No fixtures found");
+
+    let failures: Vec<String> = fixtures
+        .iter()
+        .filter_map(|fixture| {
+            let Some(payload) = Payload::from_name(&fixture.kind) else {
+                return Some(format!(
+                    "{}: unknown payload kind {}",
+                    fixture.path.display(),
+                    fixture.kind
+                ));
+            };
+            // Flattening may panic on unexpected data, which should point at the file
+            match panic::catch_unwind(AssertUnwindSafe(|| payload.decode(&fixture.json))) {
+                Ok(Ok(_)) => None,
+                Ok(Err(err)) => Some(format!("{}: {err:#}", fixture.path.display())),
+                Err(_) => Some(format!("{}: panicked", fixture.path.display())),
+            }
+        })
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,36 @@
+//! Summarizes the outcome of a poll.
+
+use std::time::Duration;
+
+use schraper::job::{JobOutcome, PollReport};
+
+fn outcome(name: &str, ran: bool, error: Option<&str>) -> JobOutcome {
+    JobOutcome {
+        name: name.to_string(),
+        ran,
+        duration: ran.then(|| Duration::from_millis(1500)),
+        rows_affected: ran.then_some(42),
+        error: error.map(str::to_string),
+    }
+}
+
+#[test]
+fn summarizes_the_jobs_which_ran() {
+    let report = PollReport {
+        jobs: vec![
+            outcome("movies", true, None),
+            outcome("trakt", false, None),
+            outcome("events", true, Some("Ticketmaster is down")),
+        ],
+    };
+    assert_eq!(report.ran().count(), 2);
+    assert_eq!(
+        report.failed().map(|job| &job.name).collect::<Vec<_>>(),
+        ["events"]
+    );
+    assert_eq!(
+        report.to_string(),
+        "movies ran in 1.5s (42 rows), events failed after 1.5s"
+    );
+    assert_eq!(PollReport::default().to_string(), "no jobs ran");
+}
@@ -0,0 +1,62 @@
+//! Records responses from a local server and replays them once it is gone.
+
+use std::path::PathBuf;
+
+use axum::{Json, Router, routing::post};
+use schraper::job::util::{Client, GetError};
+use serde_json::{Value, json};
+
+/// Fresh directory to record into
+fn recording_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("schraper-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Serves `/echo`, which answers a POST with its own body, until the task is aborted
+async fn serve() -> (String, tokio::task::JoinHandle<()>) {
+    let app = Router::new().route(
+        "/echo",
+        post(|Json(body): Json<Value>| async move { Json(json!({ "echo": body })) }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/echo", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (url, server)
+}
+
+#[tokio::test]
+async fn replays_recorded_responses() {
+    let dir = recording_dir("replay");
+    let (url, server) = serve().await;
+
+    let recorder = Client::recording(&dir);
+    let first = recorder
+        .post(&url, json!({ "query": "dune" }))
+        .await
+        .unwrap();
+    let second = recorder.post(&url, json!({ "query": "up" })).await.unwrap();
+    server.abort();
+
+    let replay = Client::replay(&dir);
+    assert_eq!(
+        replay.post(&url, json!({ "query": "dune" })).await.unwrap(),
+        first
+    );
+    assert_eq!(
+        replay.post(&url, json!({ "query": "up" })).await.unwrap(),
+        second
+    );
+    assert!(matches!(
+        replay.post(&url, json!({ "query": "cars" })).await,
+        Err(GetError::NotRecorded(..))
+    ));
+    assert!(matches!(
+        replay.get(&url).await,
+        Err(GetError::NotRecorded(..))
+    ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,27 @@
+use schraper::job::movies::PatheSite;
+
+#[test]
+fn country_and_language_follow_from_the_domain() {
+    let cases = [
+        ("https://www.pathe.nl", "nl", "NL"),
+        ("https://www.pathe.be/", "nl", "BE"),
+        ("https://www.pathe.fr", "fr", "FR"),
+        ("https://www.pathe.ch", "de", "CH"),
+        ("http://127.0.0.1:8080", "nl", "NL"),
+    ];
+    for (base_url, language, country_code) in cases {
+        let site = PatheSite::new(base_url);
+        assert_eq!(site.language(), language, "{base_url}");
+        assert_eq!(site.country_code(), country_code, "{base_url}");
+    }
+}
+
+#[test]
+fn language_can_be_overridden() {
+    let site = PatheSite::new("https://www.pathe.ch/").with_language("fr");
+    assert_eq!(site.country_code(), "CH");
+    assert_eq!(
+        site.api_url("cinema/pathe-balexert/shows"),
+        "https://www.pathe.ch/api/cinema/pathe-balexert/shows?language=fr"
+    );
+}
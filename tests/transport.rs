@@ -0,0 +1,244 @@
+//! Drives `Client` through a canned transport instead of the network.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use reqwest::{
+    Request, Response,
+    header::{AUTHORIZATION, HeaderValue},
+};
+use schraper::job::util::{Client, Clients, GetError, JsonDecodeError, Transport};
+use serde::Deserialize;
+use serde_json::json;
+
+/// A request as it reached the transport
+#[derive(Debug, Clone)]
+struct Sent {
+    method: String,
+    url: String,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+}
+
+/// Answers with the canned body of the URL, or a 404 for unknown URLs
+#[derive(Default, Clone)]
+struct Canned {
+    bodies: HashMap<String, &'static str>,
+    sent: Arc<Mutex<Vec<Sent>>>,
+}
+
+impl Canned {
+    fn with(mut self, url: &str, body: &'static str) -> Self {
+        self.bodies.insert(url.to_string(), body);
+        self
+    }
+}
+
+impl Transport for Canned {
+    fn send(
+        &self,
+        request: Request,
+    ) -> Pin<Box<dyn Future<Output = reqwest::Result<Response>> + Send + '_>> {
+        self.sent.lock().unwrap().push(Sent {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: request
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_string()))
+                .collect(),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| String::from_utf8(body.to_vec()).unwrap()),
+        });
+        let response = match self.bodies.get(request.url().as_str()) {
+            Some(body) => http::Response::new(*body),
+            None => http::Response::builder().status(404).body("").unwrap(),
+        };
+        Box::pin(async move { Ok(Response::from(response)) })
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Cinema {
+    slug: String,
+}
+
+#[tokio::test]
+async fn answers_from_the_transport() {
+    let transport = Canned::default().with(
+        "https://www.pathe.nl/api/cinemas",
+        r#"[{"slug": "pathe-tuschinski"}]"#,
+    );
+    let client = Client::new()
+        .with_user_agent(HeaderValue::from_static("schraper"))
+        .with_transport(transport.clone());
+
+    let cinemas: Vec<Cinema> = client
+        .get_json("https://www.pathe.nl/api/cinemas")
+        .await
+        .unwrap();
+    assert_eq!(
+        cinemas,
+        vec![Cinema {
+            slug: "pathe-tuschinski".to_string()
+        }]
+    );
+
+    let sent = transport.sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].method, "GET");
+    assert_eq!(sent[0].url, "https://www.pathe.nl/api/cinemas");
+    assert_eq!(sent[0].headers["user-agent"], "schraper");
+}
+
+#[tokio::test]
+async fn sends_post_bodies_as_json() {
+    let url = "https://search.example/queries";
+    let transport = Canned::default().with(url, r#"{"results": []}"#);
+    let client = Client::new().with_transport(transport.clone());
+
+    client.post(url, json!({ "query": "dune" })).await.unwrap();
+
+    let sent = transport.sent.lock().unwrap().clone();
+    assert_eq!(sent[0].method, "POST");
+    assert_eq!(sent[0].headers["content-type"], "application/json");
+    assert_eq!(sent[0].body.as_deref(), Some(r#"{"query":"dune"}"#));
+}
+
+#[tokio::test]
+async fn fails_on_error_statuses() {
+    let transport = Canned::default();
+    let client = Client::new().with_transport(transport.clone());
+
+    let result = client.get("https://www.pathe.nl/api/missing").await;
+    assert!(matches!(result, Err(GetError::MaxRetriesReached(_))));
+    assert_eq!(transport.sent.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn tells_where_decoding_failed() {
+    let url = "https://www.pathe.nl/api/shows";
+    let transport = Canned::default().with(url, r#"[{"duration": 155}, {"duration": "96 min"}]"#);
+    let client = Client::new().with_transport(transport);
+
+    let error = client
+        .get_json::<_, Vec<HashMap<String, u32>>>(url)
+        .await
+        .unwrap_err();
+    let JsonDecodeError::DecodeError {
+        url, path, excerpt, ..
+    } = error
+    else {
+        panic!("Expected a decode error, got {error}");
+    };
+    assert_eq!(url.as_str(), "https://www.pathe.nl/api/shows");
+    assert_eq!(path, "[1].duration");
+    assert!(excerpt.contains("96 min"));
+}
+
+#[tokio::test]
+async fn redacts_api_keys_from_errors() {
+    let url = "https://api.nytimes.com/svc/books/v3/lists/current/x.json?api-key=secret";
+    let client = Client::new().with_transport(Canned::default().with(url, "[1, 2]"));
+
+    let error = client.get_json::<_, String>(url).await.unwrap_err();
+    assert!(!error.to_string().contains("secret"), "Leaked in {error}");
+    assert!(error.to_string().contains("api-key=REDACTED"));
+
+    let error = client
+        .get("https://api.openweathermap.org/data/3.0/onecall?lat=52.4&appid=secret")
+        .await
+        .unwrap_err();
+    assert!(!error.to_string().contains("secret"), "Leaked in {error}");
+}
+
+#[tokio::test]
+async fn counts_requests_by_host() {
+    let body = r#"[{"slug": "pathe-tuschinski"}]"#;
+    let transport = Canned::default().with("https://www.pathe.nl/api/cinemas", body);
+    let client = Client::new().with_transport(transport);
+
+    client
+        .get("https://www.pathe.nl/api/cinemas")
+        .await
+        .unwrap();
+    // Clones share the statistics
+    client
+        .clone()
+        .get("https://search.example/queries")
+        .await
+        .unwrap_err();
+
+    let stats = client.stats();
+    let pathe = &stats.hosts["www.pathe.nl"];
+    assert_eq!(pathe.requests, 1);
+    assert_eq!(pathe.retries, 0);
+    assert_eq!(pathe.bytes, body.len() as u64);
+    assert_eq!(stats.hosts["search.example"].requests, 1);
+    assert_eq!(stats.hosts["search.example"].bytes, 0);
+}
+
+#[tokio::test]
+async fn shares_clients_by_name() {
+    let body = r#"[{"slug": "pathe-tuschinski"}]"#;
+    let transport = Canned::default().with("https://www.pathe.nl/api/cinemas", body);
+    let clients = Clients::default();
+    // Jobs hold a clone of the registry before the client is added
+    let job_clients = clients.clone();
+    clients.insert("pathe", Client::new().with_transport(transport));
+
+    for _ in 0..2 {
+        let client = job_clients.get("pathe").unwrap();
+        client
+            .get("https://www.pathe.nl/api/cinemas")
+            .await
+            .unwrap();
+    }
+    let stats = clients.get("pathe").unwrap().stats();
+    assert_eq!(stats.hosts["www.pathe.nl"].requests, 2);
+    assert!(clients.get("rottentomatoes").is_none());
+}
+
+/// Answers with an ETag, or a 304 when the request carries it
+#[derive(Default, Clone)]
+struct Etagged {
+    conditional: Arc<Mutex<Vec<bool>>>,
+}
+
+impl Transport for Etagged {
+    fn send(
+        &self,
+        request: Request,
+    ) -> Pin<Box<dyn Future<Output = reqwest::Result<Response>> + Send + '_>> {
+        let conditional = request.headers().contains_key("if-none-match");
+        self.conditional.lock().unwrap().push(conditional);
+        let response = match conditional {
+            true => http::Response::builder().status(304).body(""),
+            false => http::Response::builder()
+                .header("etag", "\"v1\"")
+                .body("[]"),
+        };
+        Box::pin(async move { Ok(Response::from(response.unwrap())) })
+    }
+}
+
+#[tokio::test]
+async fn caches_responses_by_headers() {
+    let transport = Etagged::default();
+    let client = Client::new().with_transport(transport.clone()).with_cache();
+    let token = |token| HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+    let first = client.clone().with_header(AUTHORIZATION, token("first"));
+    let second = client.with_header(AUTHORIZATION, token("second"));
+
+    let url = "https://api.ah.nl/mobile-services/product/search/v2";
+    assert_eq!(first.get(url).await.unwrap(), "[]");
+    assert_eq!(first.get(url).await.unwrap(), "[]");
+    assert_eq!(second.get(url).await.unwrap(), "[]");
+    assert_eq!(*transport.conditional.lock().unwrap(), [false, true, false]);
+}
@@ -0,0 +1,63 @@
+//! Runs the checks on fetched data which guard the tables against broken responses.
+
+use schraper::job::validation::{OnFailure, Validation, ValidationConfig};
+
+fn validation(on_failure: OnFailure) -> Validation {
+    Validation::new(Some(ValidationConfig {
+        on_failure,
+        ..ValidationConfig::default()
+    }))
+}
+
+#[test]
+fn tolerates_a_few_invalid_shows() {
+    let mut validation = validation(OnFailure::Abort);
+    let durations = [0].into_iter().chain([120; 19]);
+    validation.durations(durations);
+    validation.titles("shows", ["Dune: Part Two", "Oppenheimer"]);
+    assert!(validation.failures().is_empty());
+    assert!(validation.finish("moviefetcher").is_ok());
+}
+
+#[test]
+fn aborts_on_empty_titles() {
+    let mut validation = validation(OnFailure::Abort);
+    validation.titles("shows", ["", " ", "Oppenheimer"]);
+    assert_eq!(validation.failures(), ["2 of 3 shows have an empty title"]);
+    assert!(validation.finish("moviefetcher").is_err());
+}
+
+#[test]
+fn warns_on_duplicate_keys() {
+    let mut validation = validation(OnFailure::Warn);
+    validation.unique(
+        "cinemas",
+        ["pathe-tuschinski", "pathe-arena", "pathe-tuschinski"],
+    );
+    assert_eq!(
+        validation.failures(),
+        ["1 duplicate cinemas, such as \"pathe-tuschinski\""]
+    );
+    assert!(validation.finish("moviefetcher").is_ok());
+}
+
+#[test]
+fn compares_counts_with_the_previous_run() {
+    let mut validation = validation(OnFailure::Abort);
+    validation.count("shows", 200, 150);
+    validation.count("shows", 0, 150);
+    assert!(validation.failures().is_empty());
+    validation.count("shows", 200, 20);
+    assert_eq!(
+        validation.failures(),
+        ["20 shows against 200 during the previous run (-90%)"]
+    );
+}
+
+#[test]
+fn passes_without_config() {
+    let mut validation = Validation::new(None);
+    validation.titles("shows", [""]);
+    validation.count("shows", 200, 0);
+    assert!(validation.finish("moviefetcher").is_ok());
+}
@@ -0,0 +1,77 @@
+//! Fires webhooks at a local server and verifies their signature.
+
+use axum::{Router, body::Bytes, http::HeaderMap, routing::post};
+use chrono::Utc;
+use schraper::job::webhook::{RunReport, SIGNATURE_HEADER, Webhook, sign};
+use tokio::sync::mpsc;
+
+fn report(success: bool) -> RunReport {
+    RunReport {
+        job: "movies".to_string(),
+        kind: "Movies".to_string(),
+        success,
+        started_at: Utc::now(),
+        finished_at: Utc::now(),
+        duration_secs: 1.5,
+        rows_inserted: 42,
+        failed_lookups: 2,
+        error: (!success).then(|| "Pathé is down".to_string()),
+        retry_at: None,
+        anomalies: vec![],
+    }
+}
+
+#[test]
+fn signs_like_rfc_4231() {
+    assert_eq!(
+        sign("Jefe", b"what do ya want for nothing?"),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[test]
+fn failures_only_skips_successful_runs() {
+    let webhook = Webhook::new("http://localhost").failures_only();
+    assert!(!webhook.fires_for(&report(true)));
+    assert!(webhook.fires_for(&report(false)));
+}
+
+#[test]
+fn failures_only_fires_for_anomalous_runs() {
+    let webhook = Webhook::new("http://localhost").failures_only();
+    let mut report = report(true);
+    report.anomalies = vec!["120 showtimes against a baseline of 2400".to_string()];
+    assert!(webhook.fires_for(&report));
+}
+
+#[tokio::test]
+async fn sends_signed_reports() {
+    let (sender, mut received) = mpsc::unbounded_channel();
+    let app = Router::new().route(
+        "/hook",
+        post(move |headers: HeaderMap, body: Bytes| async move {
+            let signature = headers[SIGNATURE_HEADER].to_str().unwrap().to_string();
+            sender.send((signature, body)).unwrap();
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let webhook = Webhook::new(url).with_secret("s3cret");
+    webhook
+        .send(&reqwest::Client::new(), &report(false))
+        .await
+        .unwrap();
+    server.abort();
+
+    let (signature, body) = received.recv().await.unwrap();
+    assert_eq!(signature, format!("sha256={}", sign("s3cret", &body)));
+    let sent: RunReport = serde_json::from_slice(&body).unwrap();
+    assert!(!sent.success);
+    assert_eq!(sent.rows_inserted, 42);
+    assert_eq!(sent.failed_lookups, 2);
+    assert_eq!(sent.error.as_deref(), Some("Pathé is down"));
+}